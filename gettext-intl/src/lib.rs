@@ -0,0 +1,396 @@
+//! A C library compatible with libintl, the runtime of GNU gettext,
+//! backed by the [gettext](https://docs.rs/gettext/) crate.
+//!
+//! Built as a `cdylib`, it exports `gettext`, `dgettext`, `dcgettext`,
+//! `ngettext`, `dngettext`, `dcngettext`, `textdomain`, `bindtextdomain`
+//! and `bind_textdomain_codeset` with the signatures of `<libintl.h>`,
+//! so that C programs can link against it instead:
+//!
+//! ```c
+//! #include <libintl.h>
+//! #include <stdio.h>
+//!
+//! int main(void) {
+//!     bindtextdomain("hello", "/usr/share/locale");
+//!     textdomain("hello");
+//!     printf("%s\n", gettext("Hello, world!"));
+//!     printf(ngettext("%lu file\n", "%lu files\n", 3), 3lu);
+//!     return 0;
+//! }
+//! ```
+//!
+//! Catalogs are looked up in `<dirname>/<locale>/<category>/<domain>.mo`
+//! like libintl does, trying the variants of the locale from the most specific one,
+//! e.g. `lt_LT.UTF-8`, `lt_LT.utf8`, `lt_LT`, …, `lt`.
+//! Unlike libintl, the locale is taken from the environment variables
+//! `LANGUAGE`, `LC_ALL`, `LC_<category>` and `LANG` rather than from `setlocale`.
+//! Translations are returned in UTF-8 unless another codeset is bound to the domain
+//! with `bind_textdomain_codeset`.
+//!
+//! As with libintl, the returned strings must not be modified or freed.
+//! Loaded catalogs and returned strings are kept for the lifetime of the program.
+
+#![warn(clippy::all)]
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces
+)]
+
+mod locale;
+mod registry;
+
+use std::ffi::CStr;
+use std::path::PathBuf;
+use std::ptr;
+
+use libc::{c_char, c_int, c_ulong};
+
+use crate::registry::{Registry, REGISTRY};
+
+/// Runs the closure with the registry, creating it on first use.
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    // A panic while holding the lock can not leave the registry inconsistent,
+    // as it only ever gains entries.
+    let mut registry = REGISTRY.lock().unwrap_or_else(|err| err.into_inner());
+    f(registry.get_or_insert_with(Registry::default))
+}
+
+/// Converts a possibly null pointer to a string.
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a CStr> {
+    if s.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(s))
+    }
+}
+
+/// Looks the message up in the catalogs of the domain, or the current one if it is null,
+/// returning the untranslated message for `n` if there is no translation.
+unsafe fn translate(
+    domainname: *const c_char,
+    msgid: *const c_char,
+    msgid_plural: *const c_char,
+    n: c_ulong,
+    category: c_int,
+) -> *mut c_char {
+    if msgid.is_null() {
+        return ptr::null_mut();
+    }
+    let untranslated = if !msgid_plural.is_null() && n != 1 {
+        msgid_plural
+    } else {
+        msgid
+    };
+    let msg_id = CStr::from_ptr(msgid).to_str().ok();
+    let msg_id_plural = c_str(msgid_plural).map(CStr::to_str);
+    let (category, msg_id, msg_id_plural) =
+        match (locale::category_name(category), msg_id, msg_id_plural) {
+            (Some(category), Some(msg_id), None) => (category, msg_id, None),
+            (Some(category), Some(msg_id), Some(Ok(msg_id_plural))) => {
+                (category, msg_id, Some(msg_id_plural))
+            }
+            _ => return untranslated as *mut c_char,
+        };
+
+    // `c_ulong` is 32 bits wide on some platforms.
+    #[allow(clippy::useless_conversion)]
+    let n = u64::from(n);
+    let translated = with_registry(|registry| {
+        let domain = match c_str(domainname) {
+            Some(domain) => domain,
+            None => registry.domain(),
+        };
+        let codeset = registry.codeset(domain);
+        let dirname = PathBuf::from(registry.dirname(domain).to_str().ok()?);
+        let file_name = format!("{}.mo", domain.to_str().ok()?);
+
+        for language in locale::languages(category) {
+            // Like libintl, only the most specific catalog found for a language is used.
+            let catalog = locale::variants(&language).into_iter().find_map(|variant| {
+                let path = dirname.join(variant).join(category).join(&file_name);
+                registry.catalog(path, codeset)
+            });
+            if let Some(translated) = catalog.and_then(|c| c.translate(msg_id, msg_id_plural, n)) {
+                return Some(translated);
+            }
+        }
+        None
+    });
+    translated.unwrap_or(untranslated) as *mut c_char
+}
+
+/// Returns the translation of `msgid` in the current domain,
+/// or `msgid` itself if there is none.
+///
+/// # Safety
+///
+/// `msgid` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gettext(msgid: *const c_char) -> *mut c_char {
+    translate(ptr::null(), msgid, ptr::null(), 1, libc::LC_MESSAGES)
+}
+
+/// Returns the translation of `msgid` in the given domain,
+/// or `msgid` itself if there is none.
+///
+/// # Safety
+///
+/// `domainname` must be null or a valid NUL-terminated string,
+/// and `msgid` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dgettext(domainname: *const c_char, msgid: *const c_char) -> *mut c_char {
+    translate(domainname, msgid, ptr::null(), 1, libc::LC_MESSAGES)
+}
+
+/// Returns the translation of `msgid` in the given domain and locale category,
+/// or `msgid` itself if there is none.
+///
+/// # Safety
+///
+/// `domainname` must be null or a valid NUL-terminated string,
+/// and `msgid` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dcgettext(
+    domainname: *const c_char,
+    msgid: *const c_char,
+    category: c_int,
+) -> *mut c_char {
+    translate(domainname, msgid, ptr::null(), 1, category)
+}
+
+/// Returns the plural form of the translation of `msgid` for `n` in the current domain,
+/// or `msgid` if `n` is 1 and `msgid_plural` otherwise if there is none.
+///
+/// # Safety
+///
+/// `msgid` and `msgid_plural` must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ngettext(
+    msgid: *const c_char,
+    msgid_plural: *const c_char,
+    n: c_ulong,
+) -> *mut c_char {
+    translate(ptr::null(), msgid, msgid_plural, n, libc::LC_MESSAGES)
+}
+
+/// Returns the plural form of the translation of `msgid` for `n` in the given domain,
+/// or `msgid` if `n` is 1 and `msgid_plural` otherwise if there is none.
+///
+/// # Safety
+///
+/// `domainname` must be null or a valid NUL-terminated string,
+/// and `msgid` and `msgid_plural` must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn dngettext(
+    domainname: *const c_char,
+    msgid: *const c_char,
+    msgid_plural: *const c_char,
+    n: c_ulong,
+) -> *mut c_char {
+    translate(domainname, msgid, msgid_plural, n, libc::LC_MESSAGES)
+}
+
+/// Returns the plural form of the translation of `msgid` for `n`
+/// in the given domain and locale category,
+/// or `msgid` if `n` is 1 and `msgid_plural` otherwise if there is none.
+///
+/// # Safety
+///
+/// `domainname` must be null or a valid NUL-terminated string,
+/// and `msgid` and `msgid_plural` must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn dcngettext(
+    domainname: *const c_char,
+    msgid: *const c_char,
+    msgid_plural: *const c_char,
+    n: c_ulong,
+    category: c_int,
+) -> *mut c_char {
+    translate(domainname, msgid, msgid_plural, n, category)
+}
+
+/// Sets the current domain and returns it.
+/// If `domainname` is null, only returns the current domain;
+/// if it is empty, resets it to the default domain `messages`.
+///
+/// # Safety
+///
+/// `domainname` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn textdomain(domainname: *const c_char) -> *mut c_char {
+    let domain = with_registry(|registry| match c_str(domainname) {
+        Some(domain) => registry.set_domain(domain),
+        None => registry.domain(),
+    });
+    domain.as_ptr() as *mut c_char
+}
+
+/// Sets the directory the catalogs of the domain are looked up in and returns it.
+/// If `dirname` is null, only returns the current directory of the domain.
+/// Returns null if `domainname` is null or empty.
+///
+/// # Safety
+///
+/// `domainname` and `dirname` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn bindtextdomain(
+    domainname: *const c_char,
+    dirname: *const c_char,
+) -> *mut c_char {
+    let domain = match c_str(domainname) {
+        Some(domain) if !domain.to_bytes().is_empty() => domain,
+        _ => return ptr::null_mut(),
+    };
+    let dirname = with_registry(|registry| match c_str(dirname) {
+        Some(dirname) => registry.set_dirname(domain, dirname),
+        None => registry.dirname(domain),
+    });
+    dirname.as_ptr() as *mut c_char
+}
+
+/// Sets the codeset the translations of the domain are returned in and returns it.
+/// If `codeset` is null, only returns the current codeset of the domain,
+/// or null if none is set and translations are returned in UTF-8.
+/// Returns null if `domainname` is null or empty,
+/// or if `codeset` names an encoding which is not known, leaving the domain bound as it was.
+///
+/// Codesets are resolved like the charsets of catalogs, so GNU names such as `eucJP`
+/// or `ANSI_X3.4-1968` are understood. In ASCII, translations which are not ASCII
+/// are not returned, like those which can not be represented in any other codeset.
+///
+/// # Safety
+///
+/// `domainname` and `codeset` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn bind_textdomain_codeset(
+    domainname: *const c_char,
+    codeset: *const c_char,
+) -> *mut c_char {
+    let domain = match c_str(domainname) {
+        Some(domain) if !domain.to_bytes().is_empty() => domain,
+        _ => return ptr::null_mut(),
+    };
+    let codeset = with_registry(|registry| match c_str(codeset) {
+        Some(codeset) => registry.set_codeset(domain, codeset),
+        None => registry.codeset(domain),
+    });
+    codeset.map_or(ptr::null_mut(), |c| c.as_ptr() as *mut c_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::ffi::CString;
+    use std::fs;
+
+    fn string(s: *const c_char) -> String {
+        unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_owned()
+    }
+
+    // The registry and the environment are global, so everything is tested in a single test.
+    #[test]
+    fn test_libintl() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = dir.path().join("lt").join("LC_MESSAGES");
+        fs::create_dir_all(&messages).unwrap();
+        fs::copy("../test_cases/integration.mo", messages.join("test.mo")).unwrap();
+        fs::copy("../test_cases/cp1257_meta.mo", messages.join("garlic.mo")).unwrap();
+        env::remove_var("LANGUAGE");
+        env::set_var("LC_ALL", "lt_LT.UTF-8");
+
+        let c = |s: &str| CString::new(s).unwrap();
+        let (domain, dirname) = (c("test"), c(dir.path().to_str().unwrap()));
+        let (existent, non_existent) = (c("existent"), c("non-existent"));
+        let (singular, plural) = (c("a good string"), c("good strings"));
+        unsafe {
+            assert_eq!(string(textdomain(ptr::null())), "messages");
+            assert_eq!(string(gettext(existent.as_ptr())), "existent");
+            assert_eq!(
+                string(bindtextdomain(domain.as_ptr(), dirname.as_ptr())),
+                dirname.to_str().unwrap()
+            );
+            assert_eq!(
+                string(bindtextdomain(domain.as_ptr(), ptr::null())),
+                dirname.to_str().unwrap()
+            );
+            assert!(bindtextdomain(ptr::null(), dirname.as_ptr()).is_null());
+            assert_eq!(string(textdomain(domain.as_ptr())), "test");
+
+            assert_eq!(string(gettext(existent.as_ptr())), "egzistuojantis");
+            assert_eq!(
+                gettext(non_existent.as_ptr()),
+                non_existent.as_ptr() as *mut c_char
+            );
+            assert_eq!(
+                string(dgettext(ptr::null(), existent.as_ptr())),
+                "egzistuojantis"
+            );
+            assert_eq!(
+                string(dgettext(c("other").as_ptr(), existent.as_ptr())),
+                "existent"
+            );
+            assert_eq!(
+                string(dcgettext(ptr::null(), existent.as_ptr(), libc::LC_TIME)),
+                "existent"
+            );
+            assert_eq!(
+                string(ngettext(singular.as_ptr(), plural.as_ptr(), 1)),
+                "gera eilute"
+            );
+            assert_eq!(
+                string(ngettext(singular.as_ptr(), plural.as_ptr(), 2)),
+                "geros eilutes"
+            );
+            assert_eq!(
+                ngettext(non_existent.as_ptr(), plural.as_ptr(), 2),
+                plural.as_ptr() as *mut c_char
+            );
+
+            env::set_var("LANGUAGE", "de:lt");
+            assert_eq!(string(gettext(existent.as_ptr())), "egzistuojantis");
+            env::set_var("LC_ALL", "C");
+            assert_eq!(string(gettext(existent.as_ptr())), "existent");
+            env::set_var("LC_ALL", "lt_LT.UTF-8");
+            env::remove_var("LANGUAGE");
+
+            let (garlic, cp1257) = (c("garlic"), c("Garlic"));
+            bindtextdomain(garlic.as_ptr(), dirname.as_ptr());
+            assert_eq!(
+                string(dgettext(garlic.as_ptr(), cp1257.as_ptr())),
+                "Česnakas"
+            );
+            assert!(bind_textdomain_codeset(garlic.as_ptr(), ptr::null()).is_null());
+            let codeset = c("windows-1257");
+            assert_eq!(
+                string(bind_textdomain_codeset(garlic.as_ptr(), codeset.as_ptr())),
+                "windows-1257"
+            );
+            let translated = CStr::from_ptr(dgettext(garlic.as_ptr(), cp1257.as_ptr()));
+            assert_eq!(translated.to_bytes(), b"\xc8esnakas");
+            let unknown = c("x-klingon");
+            assert!(bind_textdomain_codeset(garlic.as_ptr(), unknown.as_ptr()).is_null());
+            assert_eq!(
+                string(bind_textdomain_codeset(garlic.as_ptr(), ptr::null())),
+                "windows-1257"
+            );
+            let ascii = c("ANSI_X3.4-1968");
+            bind_textdomain_codeset(garlic.as_ptr(), ascii.as_ptr());
+            assert_eq!(string(dgettext(garlic.as_ptr(), cp1257.as_ptr())), "Garlic");
+            let latin1 = c("ISO-8859-1");
+            bind_textdomain_codeset(garlic.as_ptr(), latin1.as_ptr());
+            assert_eq!(string(dgettext(garlic.as_ptr(), cp1257.as_ptr())), "Garlic");
+            let euc_jp = c("eucJP");
+            assert_eq!(
+                string(bind_textdomain_codeset(garlic.as_ptr(), euc_jp.as_ptr())),
+                "eucJP"
+            );
+
+            assert_eq!(string(textdomain(c("").as_ptr())), "messages");
+            assert_eq!(string(gettext(existent.as_ptr())), "existent");
+        }
+    }
+}
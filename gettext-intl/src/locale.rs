@@ -0,0 +1,167 @@
+use std::env;
+
+use libc::c_int;
+
+/// Returns the name of a locale category as used in the paths of catalogs,
+/// e.g. `LC_MESSAGES`, or `None` for `LC_ALL` and unknown categories.
+pub(crate) fn category_name(category: c_int) -> Option<&'static str> {
+    let name = match category {
+        libc::LC_CTYPE => "LC_CTYPE",
+        libc::LC_NUMERIC => "LC_NUMERIC",
+        libc::LC_TIME => "LC_TIME",
+        libc::LC_COLLATE => "LC_COLLATE",
+        libc::LC_MONETARY => "LC_MONETARY",
+        libc::LC_MESSAGES => "LC_MESSAGES",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Returns the languages to look translations up in for the given category, in order.
+///
+/// Like in libintl, the locale is the first one set of `LC_ALL`, the variable of the category
+/// (e.g. `LC_MESSAGES`) and `LANG`, and `LANGUAGE` may list several languages
+/// separated by colons, which take precedence unless the locale is `C`.
+/// No translations are used for the `C` locale.
+pub(crate) fn languages(category: &str) -> Vec<String> {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+    let locale = match var("LC_ALL")
+        .or_else(|| var(category))
+        .or_else(|| var("LANG"))
+    {
+        Some(locale) => locale,
+        None => return vec![],
+    };
+    if is_c(&locale) {
+        return vec![];
+    }
+    let languages = match var("LANGUAGE") {
+        Some(languages) => languages.split(':').map(str::to_owned).collect(),
+        None => vec![locale],
+    };
+    languages
+        .into_iter()
+        .filter(|l| !l.is_empty())
+        .take_while(|l| !is_c(l))
+        .collect()
+}
+
+fn is_c(locale: &str) -> bool {
+    locale == "C" || locale == "POSIX" || locale.starts_with("C.")
+}
+
+/// Returns the names of the directories to look a catalog for the locale up in,
+/// from the most to the least specific, e.g. `sr_RS.UTF-8@latin`, `sr_RS.utf8@latin`,
+/// `sr_RS@latin`, …, `sr_RS`, `sr.UTF-8`, `sr.utf8`, `sr`.
+pub(crate) fn variants(locale: &str) -> Vec<String> {
+    const NORMALIZED_CODESET: u8 = 1;
+    const CODESET: u8 = 2;
+    const TERRITORY: u8 = 4;
+    const MODIFIER: u8 = 8;
+
+    let (rest, modifier) = split(locale, '@');
+    let (rest, codeset) = split(rest, '.');
+    let (language, territory) = split(rest, '_');
+    let normalized = codeset
+        .map(normalize_codeset)
+        .filter(|n| Some(n.as_str()) != codeset);
+
+    let mut present = 0;
+    for (bit, part) in [
+        (NORMALIZED_CODESET, normalized.is_some()),
+        (CODESET, codeset.is_some()),
+        (TERRITORY, territory.is_some()),
+        (MODIFIER, modifier.is_some()),
+    ] {
+        if part {
+            present |= bit;
+        }
+    }
+
+    (0..=present)
+        .rev()
+        .filter(|mask| mask & !present == 0)
+        .filter(|mask| mask & (CODESET | NORMALIZED_CODESET) != CODESET | NORMALIZED_CODESET)
+        .map(|mask| {
+            let mut name = language.to_owned();
+            if mask & TERRITORY != 0 {
+                name = format!("{}_{}", name, territory.unwrap());
+            }
+            if mask & CODESET != 0 {
+                name = format!("{}.{}", name, codeset.unwrap());
+            }
+            if mask & NORMALIZED_CODESET != 0 {
+                name = format!("{}.{}", name, normalized.as_ref().unwrap());
+            }
+            if mask & MODIFIER != 0 {
+                name = format!("{}@{}", name, modifier.unwrap());
+            }
+            name
+        })
+        .collect()
+}
+
+/// Splits the string at the last occurrence of the separator.
+fn split(s: &str, separator: char) -> (&str, Option<&str>) {
+    match s.rfind(separator) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    }
+}
+
+/// Normalizes a codeset the way libintl does, e.g. `UTF-8` to `utf8`
+/// and `8859-1` to `iso88591`.
+fn normalize_codeset(codeset: &str) -> String {
+    let normalized = codeset
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<String>();
+    if normalized.chars().all(|c| c.is_ascii_digit()) {
+        format!("iso{}", normalized)
+    } else {
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants() {
+        assert_eq!(variants("lt"), ["lt"]);
+        assert_eq!(variants("lt_LT"), ["lt_LT", "lt"]);
+        assert_eq!(
+            variants("lt_LT.UTF-8"),
+            [
+                "lt_LT.UTF-8",
+                "lt_LT.utf8",
+                "lt_LT",
+                "lt.UTF-8",
+                "lt.utf8",
+                "lt"
+            ]
+        );
+        assert_eq!(
+            variants("sr_RS.utf8@latin"),
+            [
+                "sr_RS.utf8@latin",
+                "sr_RS@latin",
+                "sr.utf8@latin",
+                "sr@latin",
+                "sr_RS.utf8",
+                "sr_RS",
+                "sr.utf8",
+                "sr"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_codeset() {
+        assert_eq!(normalize_codeset("UTF-8"), "utf8");
+        assert_eq!(normalize_codeset("ISO-8859-13"), "iso885913");
+        assert_eq!(normalize_codeset("8859-1"), "iso88591");
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use gettext::{Catalog, Decoder};
+
+/// The domain used unless `textdomain` sets another one.
+const DEFAULT_DOMAIN: &str = "messages";
+
+/// The directory catalogs are looked up in unless `bindtextdomain` sets another one.
+const DEFAULT_DIRNAME: &str = "/usr/share/locale";
+
+/// Registry holds the state of the library: the current domain,
+/// the directory and codeset bound to each domain and the catalogs loaded so far.
+///
+/// Every string handed out to C is interned and never freed,
+/// as callers may hold on to them indefinitely.
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    /// The current domain, or `None` for the default `messages`.
+    domain: Option<&'static CStr>,
+    bindings: HashMap<Vec<u8>, Binding>,
+    /// Catalogs by path and codeset, or `None` if the file could not be loaded.
+    catalogs: HashMap<(PathBuf, Option<&'static CStr>), Option<&'static Loaded>>,
+    interned: HashSet<&'static CStr>,
+}
+
+/// The directory and codeset bound to a domain.
+#[derive(Debug, Default)]
+struct Binding {
+    dirname: Option<&'static CStr>,
+    codeset: Option<&'static CStr>,
+}
+
+/// Loaded is a catalog along with the translations handed out from it so far,
+/// NUL-terminated and converted to the codeset of the domain.
+#[derive(Debug)]
+pub(crate) struct Loaded {
+    catalog: Catalog,
+    /// The encoding of the codeset, or `None` for UTF-8.
+    encoding: Option<Arc<dyn Decoder>>,
+    /// Whether the codeset is ASCII, which only translations in ASCII can be returned in.
+    ascii: bool,
+    translations: Mutex<HashMap<String, CString>>,
+}
+
+impl Registry {
+    /// Returns the current domain.
+    pub(crate) fn domain(&mut self) -> &'static CStr {
+        match self.domain {
+            Some(domain) => domain,
+            None => self.intern(&CString::new(DEFAULT_DOMAIN).unwrap()),
+        }
+    }
+
+    /// Sets the current domain, resetting it to the default one if it is empty.
+    pub(crate) fn set_domain(&mut self, domain: &CStr) -> &'static CStr {
+        self.domain = if domain.is_empty() {
+            None
+        } else {
+            Some(self.intern(domain))
+        };
+        self.domain()
+    }
+
+    /// Returns the directory bound to the domain, or the default one.
+    pub(crate) fn dirname(&mut self, domain: &CStr) -> &'static CStr {
+        let bound = self.bindings.get(domain.to_bytes()).and_then(|b| b.dirname);
+        match bound {
+            Some(dirname) => dirname,
+            None => self.intern(&CString::new(DEFAULT_DIRNAME).unwrap()),
+        }
+    }
+
+    /// Binds the domain to the directory its catalogs are in.
+    pub(crate) fn set_dirname(&mut self, domain: &CStr, dirname: &CStr) -> &'static CStr {
+        let dirname = self.intern(dirname);
+        self.binding(domain).dirname = Some(dirname);
+        dirname
+    }
+
+    /// Returns the codeset bound to the domain, if any.
+    pub(crate) fn codeset(&self, domain: &CStr) -> Option<&'static CStr> {
+        self.bindings.get(domain.to_bytes()).and_then(|b| b.codeset)
+    }
+
+    /// Binds the domain to the codeset its translations are returned in,
+    /// or returns `None` and leaves the binding as is if the codeset is unknown.
+    pub(crate) fn set_codeset(&mut self, domain: &CStr, codeset: &CStr) -> Option<&'static CStr> {
+        codeset.to_str().ok().and_then(<dyn Decoder>::for_label)?;
+        let codeset = self.intern(codeset);
+        self.binding(domain).codeset = Some(codeset);
+        Some(codeset)
+    }
+
+    fn binding(&mut self, domain: &CStr) -> &mut Binding {
+        self.bindings.entry(domain.to_bytes().to_vec()).or_default()
+    }
+
+    /// Returns the catalog at the given path, loading it on first use,
+    /// or `None` if the file does not exist or is invalid.
+    pub(crate) fn catalog(
+        &mut self,
+        path: PathBuf,
+        codeset: Option<&'static CStr>,
+    ) -> Option<&'static Loaded> {
+        *self
+            .catalogs
+            .entry((path, codeset))
+            .or_insert_with_key(|(path, codeset)| {
+                let catalog = Catalog::parse_bytes(&fs::read(path).ok()?).ok()?;
+                let label = codeset.and_then(|c| c.to_str().ok()).unwrap_or("UTF-8");
+                let encoding = <dyn Decoder>::for_label(label)
+                    .filter(|e| !e.name().eq_ignore_ascii_case("utf-8"));
+                let ascii = <dyn Decoder>::is_ascii_label(label);
+                Some(Box::leak(Box::new(Loaded {
+                    catalog,
+                    encoding,
+                    ascii,
+                    translations: Mutex::new(HashMap::new()),
+                })))
+            })
+    }
+
+    fn intern(&mut self, s: &CStr) -> &'static CStr {
+        match self.interned.get(s) {
+            Some(interned) => interned,
+            None => {
+                let interned = Box::leak(s.to_owned().into_boxed_c_str());
+                self.interned.insert(interned);
+                interned
+            }
+        }
+    }
+}
+
+impl Loaded {
+    /// Returns the translation of the message, NUL-terminated,
+    /// or `None` if the catalog does not have one
+    /// or it can not be represented in the codeset of the domain.
+    pub(crate) fn translate(
+        &self,
+        msg_id: &str,
+        msg_id_plural: Option<&str>,
+        n: u64,
+    ) -> Option<*const libc::c_char> {
+        let translated = match msg_id_plural {
+            Some(msg_id_plural) => self.catalog.ngettext(msg_id, msg_id_plural, n),
+            None => self.catalog.gettext(msg_id),
+        };
+        // The catalog returns the original strings themselves if it has no translation.
+        if translated.as_ptr() == msg_id.as_ptr()
+            || msg_id_plural.is_some_and(|p| translated.as_ptr() == p.as_ptr())
+        {
+            return None;
+        }
+
+        if self.ascii && !translated.is_ascii() {
+            return None;
+        }
+        let mut translations = self.translations.lock().unwrap();
+        if let Some(translation) = translations.get(translated) {
+            return Some(translation.as_ptr());
+        }
+        let bytes = match self.encoding {
+            Some(ref encoding) => encoding.encode(translated)?,
+            None => translated.as_bytes().to_vec(),
+        };
+        let translation = CString::new(bytes).ok()?;
+        let ptr = translation.as_ptr();
+        translations.insert(translated.to_owned(), translation);
+        Some(ptr)
+    }
+}
+
+/// The registry shared by all threads.
+pub(crate) static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
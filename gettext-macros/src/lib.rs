@@ -0,0 +1,239 @@
+//! Compile-time macros for the [gettext](https://docs.rs/gettext/) crate.
+//!
+//! These live in a crate of their own
+//! because procedural macros can not be defined in the `gettext` crate itself
+//! and the macros use the `gettext` parsers at compile time.
+//! For the same reason `gettext` does not re-export them:
+//! they are used as `gettext_macros::include_catalog!`, not `gettext::include_catalog!`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use gettext::StaticCatalog;
+//! use gettext_macros::include_catalog;
+//!
+//! static FRENCH: StaticCatalog = include_catalog!("locale/fr.mo");
+//!
+//! fn main() {
+//!     println!("{}", FRENCH.gettext("Name"));
+//! }
+//! ```
+
+#![warn(clippy::all)]
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces
+)]
+
+extern crate proc_macro;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gettext::__private::{build_phf, message_key, plural_ast, Ast, Operator};
+use gettext::Catalog;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+mod translate;
+
+use crate::translate::{expand, Kind};
+
+/// Embeds a translation catalog into the program as a `gettext::StaticCatalog`.
+///
+/// The path is resolved relative to the directory
+/// containing the manifest of the crate being compiled.
+/// Files with a `.po` or `.pot` extension are parsed as PO files,
+/// anything else is parsed as a binary MO file.
+///
+/// The catalog is parsed and validated at compile time:
+/// a missing file, an invalid plural formula
+/// or an invalid byte sequence in strings fail the build.
+///
+/// ```ignore
+/// static FRENCH: gettext::StaticCatalog = gettext_macros::include_catalog!("locale/fr.mo");
+/// ```
+#[proc_macro]
+pub fn include_catalog(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    let root = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let path_buf = root.join(path.value());
+    let result = fs::read(&path_buf)
+        .map_err(|e| format!("could not read {}: {}", path_buf.display(), e))
+        .and_then(|contents| parse_catalog(&path_buf, &contents));
+    match result {
+        Ok(catalog) => expand_catalog(&catalog, Some(&path_buf)).into(),
+        Err(msg) => syn::Error::new(path.span(), msg).to_compile_error().into(),
+    }
+}
+
+/// Translates a message using a `gettext::Catalog`
+/// and substitutes the arguments into its placeholders,
+/// like `Catalog::format` does.
+///
+/// The message id must be a string literal, so that it can be extracted,
+/// and the arguments are checked against its placeholders at compile time,
+/// like `format!` does.
+///
+/// ```ignore
+/// let greeting = gettext!(&catalog, "Hello, {name}!", name = user.name);
+/// let title = gettext!(&catalog, "{} of {}", page, pages);
+/// ```
+#[proc_macro]
+pub fn gettext(input: TokenStream) -> TokenStream {
+    translate(input, false, false)
+}
+
+/// Translates a message with a plural form for the given number of objects
+/// using a `gettext::Catalog`, like `Catalog::nformat` does.
+///
+/// Both message ids must be string literals with the same placeholders.
+///
+/// ```ignore
+/// let text = ngettext!(&catalog, "{n} file", "{n} files", count, n = count);
+/// ```
+#[proc_macro]
+pub fn ngettext(input: TokenStream) -> TokenStream {
+    translate(input, false, true)
+}
+
+/// Translates a message in the given context using a `gettext::Catalog`,
+/// like `Catalog::pformat` does.
+///
+/// ```ignore
+/// let text = pgettext!(&catalog, "menu", "Open {file}", file = path.display());
+/// ```
+#[proc_macro]
+pub fn pgettext(input: TokenStream) -> TokenStream {
+    translate(input, true, false)
+}
+
+/// Translates a message with a plural form in the given context
+/// for the given number of objects using a `gettext::Catalog`,
+/// like `Catalog::npformat` does.
+///
+/// ```ignore
+/// let text = npgettext!(&catalog, "cart", "{} item", "{} items", count, count);
+/// ```
+#[proc_macro]
+pub fn npgettext(input: TokenStream) -> TokenStream {
+    translate(input, true, true)
+}
+
+fn translate(input: TokenStream, context: bool, plural: bool) -> TokenStream {
+    expand(input.into(), Kind { context, plural })
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn parse_catalog(path: &Path, contents: &[u8]) -> Result<Catalog, String> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    let parsed = if matches!(extension, Some("po") | Some("pot")) {
+        Catalog::parse_po(contents)
+    } else {
+        Catalog::parse(contents)
+    };
+    parsed.map_err(|e| format!("could not parse {}: {}", path.display(), e))
+}
+
+/// Generates an expression evaluating to a `StaticCatalog` with the given contents.
+fn expand_catalog(catalog: &Catalog, path: Option<&Path>) -> TokenStream2 {
+    let messages = catalog.messages().collect::<Vec<_>>();
+    let keys = messages.iter().map(|m| message_key(m)).collect::<Vec<_>>();
+    let table = build_phf(&keys.iter().map(|k| k.as_str()).collect::<Vec<_>>());
+
+    let seed = table.seed;
+    let displacements_len = table.displacements.len();
+    let displacements = table
+        .displacements
+        .iter()
+        .map(|(d1, d2)| quote!((#d1, #d2)));
+    let messages_len = messages.len();
+    let entries = table.order.iter().map(|&i| {
+        let key = &keys[i];
+        let translated = messages[i].translations();
+        quote!(::gettext::__private::StaticMessage::__new(#key, &[#(#translated),*]))
+    });
+    let plural = match plural_ast(catalog) {
+        Some(ast) => expand_ast(ast),
+        None => quote!(if n == 1 { 0 } else { 1 }),
+    };
+    // Makes the compiler rebuild the catalog when the file changes.
+    let track = path.and_then(|p| p.to_str()).map(|p| {
+        quote!(
+            const _: &[u8] = include_bytes!(#p);
+        )
+    });
+
+    quote! {{
+        #track
+        #[allow(unused_parens)]
+        fn __plural(n: u64) -> usize {
+            #plural
+        }
+        static __DISPLACEMENTS: [(u32, u32); #displacements_len] = [#(#displacements),*];
+        static __MESSAGES: [::gettext::__private::StaticMessage; #messages_len] = [#(#entries),*];
+        ::gettext::StaticCatalog::__new(#seed, &__DISPLACEMENTS, &__MESSAGES, __plural)
+    }}
+}
+
+/// Compiles a plural formula into a Rust expression of type `usize`,
+/// with the same semantics as evaluating the formula at runtime.
+fn expand_ast(ast: &Ast) -> TokenStream2 {
+    match *ast {
+        Ast::Ternary(ref cond, ref ok, ref nok) => {
+            let (cond, ok, nok) = (expand_ast(cond), expand_ast(ok), expand_ast(nok));
+            quote!((if #cond != 0 { #ok } else { #nok }))
+        }
+        Ast::N => quote!((n as usize)),
+        Ast::Integer(x) => quote!((#x as usize)),
+        Ast::Op(ref op, ref lhs, ref rhs) => {
+            let (lhs, rhs) = (expand_ast(lhs), expand_ast(rhs));
+            match *op {
+                Operator::Equal => quote!(((#lhs == #rhs) as usize)),
+                Operator::NotEqual => quote!(((#lhs != #rhs) as usize)),
+                Operator::GreaterOrEqual => quote!(((#lhs >= #rhs) as usize)),
+                Operator::SmallerOrEqual => quote!(((#lhs <= #rhs) as usize)),
+                Operator::Greater => quote!(((#lhs > #rhs) as usize)),
+                Operator::Smaller => quote!(((#lhs < #rhs) as usize)),
+                Operator::And => quote!(((#lhs != 0 && #rhs != 0) as usize)),
+                Operator::Or => quote!(((#lhs != 0 || #rhs != 0) as usize)),
+                Operator::Modulo => quote!((#lhs % #rhs)),
+            }
+        }
+        Ast::Not(ref val) => {
+            let val = expand_ast(val);
+            quote!(((#val == 0) as usize))
+        }
+    }
+}
+
+#[test]
+fn test_parse_catalog_errors() {
+    let bad_plural = b"msgid \"\"\nmsgstr \"Plural-Forms: nplurals=2; plural=n ! 1;\\n\"\n";
+    let err = parse_catalog(Path::new("fr.po"), bad_plural).unwrap_err();
+    assert!(err.contains("invalid plural expression"), "{}", err);
+
+    let bad_encoding = b"msgid \"a\"\nmsgstr \"\\xFF\"\n";
+    let err = parse_catalog(Path::new("fr.po"), bad_encoding).unwrap_err();
+    assert!(err.contains("invalid byte sequence"), "{}", err);
+
+    let err = parse_catalog(Path::new("fr.mo"), bad_plural).unwrap_err();
+    assert!(err.contains("bad magic number"), "{}", err);
+}
+
+#[test]
+fn test_expand_ast() {
+    let ast = Ast::parse("n%10==1 && n%100!=11 ? 0 : !(n < 5) ? 1 : 2").unwrap();
+    let expanded = expand_ast(&ast).to_string();
+    assert!(expanded.contains("if"), "{}", expanded);
+    assert!(expanded.contains("% (100u64 as usize)"), "{}", expanded);
+}
@@ -0,0 +1,271 @@
+//! Expansion of the `gettext!` family of macros.
+
+use gettext::__private::{placeholders, Placeholder};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Ident, Lit, LitStr, Token};
+
+/// Which of the strings a macro takes besides the message id.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Kind {
+    pub(crate) context: bool,
+    pub(crate) plural: bool,
+}
+
+/// The arguments of a translation macro.
+struct Input {
+    catalog: Expr,
+    context: Option<LitStr>,
+    id: LitStr,
+    plural: Option<(LitStr, Expr)>,
+    positional: Vec<Expr>,
+    named: Vec<(Ident, Expr)>,
+}
+
+/// Parses a string literal, which is required for the strings to be extracted.
+fn parse_literal(input: ParseStream, what: &str) -> syn::Result<LitStr> {
+    match input.parse::<Expr>()? {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit),
+        other => Err(syn::Error::new_spanned(
+            other,
+            format!("the {} must be a string literal", what),
+        )),
+    }
+}
+
+impl Input {
+    fn parse(input: ParseStream, kind: Kind) -> syn::Result<Self> {
+        let catalog = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let context = if kind.context {
+            let context = parse_literal(input, "message context")?;
+            input.parse::<Token![,]>()?;
+            Some(context)
+        } else {
+            None
+        };
+        let id = parse_literal(input, "message id")?;
+        let plural = if kind.plural {
+            input.parse::<Token![,]>()?;
+            let plural = parse_literal(input, "plural message id")?;
+            input.parse::<Token![,]>()?;
+            Some((plural, input.parse()?))
+        } else {
+            None
+        };
+
+        let mut positional = vec![];
+        let mut named: Vec<(Ident, Expr)> = vec![];
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+        for arg in Punctuated::<Expr, Token![,]>::parse_terminated(input)? {
+            match arg {
+                Expr::Assign(assign) => {
+                    let name = match *assign.left {
+                        Expr::Path(ref path) if path.path.get_ident().is_some() => {
+                            path.path.get_ident().unwrap().clone()
+                        }
+                        ref other => {
+                            return Err(syn::Error::new_spanned(other, "expected an argument name"))
+                        }
+                    };
+                    if named.iter().any(|(n, _)| *n == name) {
+                        return Err(syn::Error::new(
+                            name.span(),
+                            format!("duplicate argument named `{}`", name),
+                        ));
+                    }
+                    named.push((name, *assign.right));
+                }
+                _ if !named.is_empty() => {
+                    return Err(syn::Error::new_spanned(
+                        arg,
+                        "positional arguments cannot follow named arguments",
+                    ))
+                }
+                arg => positional.push(arg),
+            }
+        }
+        Ok(Input {
+            catalog,
+            context,
+            id,
+            plural,
+            positional,
+            named,
+        })
+    }
+}
+
+/// Expands a translation macro of the given kind.
+pub(crate) fn expand(tokens: TokenStream2, kind: Kind) -> syn::Result<TokenStream2> {
+    let input = (|input: ParseStream| Input::parse(input, kind)).parse2(tokens)?;
+
+    let id = input.id.value();
+    let id_placeholders = placeholders(&id);
+    if let Some((ref plural, _)) = input.plural {
+        let plural_value = plural.value();
+        let plural_placeholders = placeholders(&plural_value);
+        if plural_placeholders != id_placeholders {
+            return Err(syn::Error::new(
+                plural.span(),
+                format!(
+                    "the plural message id must use the same placeholders as the singular one: {} vs {}",
+                    describe(&plural_placeholders),
+                    describe(&id_placeholders),
+                ),
+            ));
+        }
+    }
+
+    // Check the arguments the way `format!` does.
+    let mut captured = vec![];
+    for placeholder in &id_placeholders {
+        match *placeholder {
+            Placeholder::Index(i) if i >= input.positional.len() => {
+                return Err(syn::Error::new(
+                    input.id.span(),
+                    format!(
+                        "the message refers to positional argument {}, but {} given",
+                        i,
+                        match input.positional.len() {
+                            1 => "there is 1 argument".to_owned(),
+                            n => format!("there are {} arguments", n),
+                        }
+                    ),
+                ));
+            }
+            Placeholder::Name(name) if !input.named.iter().any(|(n, _)| n == name) => {
+                // Like `format!`, a variable of that name is captured.
+                captured.push(Ident::new(name, input.id.span()));
+            }
+            _ => (),
+        }
+    }
+    for (i, arg) in input.positional.iter().enumerate() {
+        if !id_placeholders.contains(&Placeholder::Index(i)) {
+            return Err(syn::Error::new_spanned(arg, "argument never used"));
+        }
+    }
+    for (name, _) in &input.named {
+        if !id_placeholders.contains(&Placeholder::Name(&name.to_string())) {
+            return Err(syn::Error::new(name.span(), "named argument never used"));
+        }
+    }
+
+    let values = input
+        .positional
+        .iter()
+        .chain(input.named.iter().map(|(_, e)| e))
+        .map(|e| quote!(&(#e)))
+        .chain(captured.iter().map(|ident| quote!(&#ident)))
+        .collect::<Vec<_>>();
+    let bindings = (0..values.len())
+        .map(|i| format_ident!("__arg{}", i, span = Span::mixed_site()))
+        .collect::<Vec<_>>();
+    let names = input
+        .named
+        .iter()
+        .map(|(n, _)| n.to_string())
+        .chain(captured.iter().map(|n| n.to_string()));
+    let (positional, named) = bindings.split_at(input.positional.len());
+    let args = quote!(
+        ::gettext::FormatArgs::new()
+            #(.arg(#positional))*
+            #(.named(#names, #named))*
+    );
+
+    let catalog = &input.catalog;
+    let id = &input.id;
+    let n = Ident::new("__n", Span::mixed_site());
+    let call = match (input.context, input.plural) {
+        (None, None) => quote!((#catalog).format(#id, &#args)),
+        (Some(context), None) => quote!((#catalog).pformat(#context, #id, &#args)),
+        (context, Some((plural, count))) => {
+            let call = match context {
+                Some(context) => quote!((#catalog).npformat(#context, #id, #plural, #n, &#args)),
+                None => quote!((#catalog).nformat(#id, #plural, #n, &#args)),
+            };
+            quote!({
+                let #n: u64 = ::core::convert::TryFrom::try_from(#count).unwrap_or(u64::MAX);
+                #call
+            })
+        }
+    };
+    Ok(quote! {
+        match (#(#values,)*) {
+            (#(#bindings,)*) => #call,
+        }
+    })
+}
+
+/// Describes a set of placeholders in error messages.
+fn describe(placeholders: &[Placeholder]) -> String {
+    if placeholders.is_empty() {
+        return "none".to_owned();
+    }
+    placeholders
+        .iter()
+        .map(|p| match *p {
+            Placeholder::Index(i) => format!("{{{}}}", i),
+            Placeholder::Name(name) => format!("{{{}}}", name),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[test]
+fn test_expand_errors() {
+    let plain = Kind {
+        context: false,
+        plural: false,
+    };
+    let plural = Kind {
+        context: false,
+        plural: true,
+    };
+    let error = |tokens: TokenStream2, kind: Kind| expand(tokens, kind).unwrap_err().to_string();
+
+    assert_eq!(
+        error(quote!(catalog, msg), plain),
+        "the message id must be a string literal"
+    );
+    assert_eq!(
+        error(quote!(catalog, "{n} file", "files", n), plural),
+        "the plural message id must use the same placeholders as the singular one: none vs {n}"
+    );
+    assert_eq!(
+        error(quote!(catalog, "{} of {}", 1), plain),
+        "the message refers to positional argument 1, but there is 1 argument given"
+    );
+    assert_eq!(
+        error(quote!(catalog, "{}", 1, 2), plain),
+        "argument never used"
+    );
+    assert_eq!(
+        error(quote!(catalog, "text", name = 1), plain),
+        "named argument never used"
+    );
+    assert_eq!(
+        error(quote!(catalog, "{a}", a = 1, 2), plain),
+        "positional arguments cannot follow named arguments"
+    );
+}
+
+#[test]
+fn test_expand() {
+    let plain = Kind {
+        context: true,
+        plural: false,
+    };
+    let expanded = expand(quote!(&catalog, "menu", "{} {name}", 1), plain)
+        .unwrap()
+        .to_string();
+    assert!(expanded.contains("pformat"), "{}", expanded);
+    assert!(expanded.contains("& name"), "{}", expanded);
+}
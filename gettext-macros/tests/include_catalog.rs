@@ -0,0 +1,47 @@
+use gettext::{Catalog, StaticCatalog};
+use gettext_macros::include_catalog;
+
+use std::fs::File;
+
+static INTEGRATION: StaticCatalog = include_catalog!("../test_cases/integration.mo");
+static INTEGRATION_PO: StaticCatalog = include_catalog!("../test_cases/integration.po");
+static COMPLEX_PLURAL: StaticCatalog = include_catalog!("../test_cases/complex_plural.mo");
+
+#[test]
+fn test_include_catalog() {
+    for catalog in &[&INTEGRATION, &INTEGRATION_PO] {
+        assert_eq!(catalog.gettext("non-existent"), "non-existent");
+        assert_eq!(catalog.gettext("existent"), "egzistuojantis");
+        assert_eq!(
+            catalog.ngettext("a bad string", "bad strings", 2),
+            "bad strings"
+        );
+        assert_eq!(
+            catalog.ngettext("a good string", "good strings", 1),
+            "gera eilute"
+        );
+        assert_eq!(
+            catalog.ngettext("a good string", "good strings", 2),
+            "geros eilutes"
+        );
+        assert_eq!(
+            catalog.pgettext("ctxt", "existent"),
+            "egzistuojantis kontekste"
+        );
+        assert_eq!(
+            catalog.npgettext("ctxt", "a good string", "good strings", 2),
+            "geros eilutes kontekste"
+        );
+    }
+}
+
+#[test]
+fn test_include_catalog_plural_matches_runtime() {
+    let runtime = Catalog::parse(File::open("../test_cases/complex_plural.mo").unwrap()).unwrap();
+    for n in 0..200 {
+        assert_eq!(
+            COMPLEX_PLURAL.ngettext("Test", "Tests", n),
+            runtime.ngettext("Test", "Tests", n)
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use gettext::Catalog;
+use gettext_macros::{gettext, ngettext, npgettext, pgettext};
+
+const PO: &str = r#"
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2));\n"
+
+msgid "Hello, {name}!"
+msgstr "Labas, {name}!"
+
+msgid "{} of {}"
+msgstr "{1}: {0}"
+
+msgctxt "menu"
+msgid "Open {file}"
+msgstr "Atidaryti {file}"
+
+msgid "{n} file"
+msgid_plural "{n} files"
+msgstr[0] "{n} failas"
+msgstr[1] "{n} failai"
+msgstr[2] "{n} failų"
+
+msgctxt "cart"
+msgid "%d item"
+msgid_plural "%d items"
+msgstr[0] "%d prekė"
+msgstr[1] "%d prekės"
+msgstr[2] "%d prekių"
+"#;
+
+#[test]
+fn test_translate() {
+    let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+    let name = "Jonas";
+    assert_eq!(gettext!(&catalog, "Hello, {name}!"), "Labas, Jonas!");
+    assert_eq!(
+        gettext!(&catalog, "Hello, {name}!", name = "Ona"),
+        "Labas, Ona!"
+    );
+    assert_eq!(gettext!(catalog, "{} of {}", 1, 2 + 1), "3: 1");
+    assert_eq!(gettext!(&catalog, "Untranslated {}", 'x'), "Untranslated x");
+    assert_eq!(
+        pgettext!(&catalog, "menu", "Open {file}", file = "a.txt"),
+        "Atidaryti a.txt"
+    );
+
+    for &(n, expected) in &[(1, "1 failas"), (3, "3 failai"), (10, "10 failų")] {
+        assert_eq!(
+            ngettext!(&catalog, "{n} file", "{n} files", n, n = n),
+            expected
+        );
+    }
+    let count = 21u8;
+    assert_eq!(
+        npgettext!(&catalog, "cart", "%d item", "%d items", count, count),
+        "21 prekė"
+    );
+    let empty = Catalog::empty();
+    assert_eq!(
+        npgettext!(empty, "cart", "%d item", "%d items", -1i32, -1),
+        "-1 items"
+    );
+}
@@ -0,0 +1,449 @@
+//! Conversion between catalogs and Android string resources (`res/values*/strings.xml`).
+//!
+//! Android resources are looked up by name rather than by original string.
+//! A message is written as the resource named after its context, or after its original
+//! string if it has none, with the characters not allowed in resource names replaced by `_`.
+//! Messages sharing a context are named after both, as in `menu_Open`,
+//! and a number is appended to names still used by more than one message, as in `Open_2`.
+//! When parsing, the messages are keyed by their resource name, or, if the resources
+//! of the source language are given, have the resource name as their context
+//! and the source string as their original string, like `msgctxt` keys in PO files.
+//!
+//! Messages with a plural form become `plurals` resources, with the quantity categories
+//! (`one`, `few`, ...) derived from the catalog's plural formula and language.
+//! Strings are escaped the way Android requires, but inline markup and
+//! format arguments are not converted.
+//!
+//! ```
+//! use gettext::android;
+//!
+//! let catalog = android::parse(
+//!     &br#"<resources><string name="open">Atverti</string></resources>"#[..],
+//!     "lt",
+//! ).unwrap();
+//! assert_eq!(catalog.gettext("open"), "Atverti");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::po::{PoEntry, PoFile};
+use crate::xml::{self, Element};
+use crate::Error::{self, InvalidDocument};
+use crate::{cldr, Catalog, Message};
+
+/// Returns the name of the resource for a message,
+/// after both its context and original string if `shared_context` is set.
+fn resource_name(message: &Message, shared_context: bool) -> String {
+    let name = match message.context {
+        Some(ref context) if shared_context => format!("{}_{}", context, message.id),
+        Some(ref context) => context.clone(),
+        None => message.id.clone(),
+    };
+    let mut resource = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    if !resource.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        resource.insert(0, '_');
+    }
+    resource
+}
+
+/// Escapes a string for a resource value.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '@' | '?' if i == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    // Runs of whitespace are collapsed, except within double quotes.
+    if s.starts_with(' ') || s.ends_with(' ') || s.contains("  ") {
+        escaped = format!("\"{}\"", escaped);
+    }
+    escaped
+}
+
+/// Unescapes a resource value, collapsing whitespace outside of double quotes.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut quoted = false;
+    let mut chars = s.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) => unescaped.push(c),
+                        None => unescaped.push_str(&hex),
+                    }
+                }
+                Some(c) => unescaped.push(c),
+                None => (),
+            },
+            c if c.is_whitespace() && !quoted => {
+                if !unescaped.ends_with(' ') {
+                    unescaped.push(' ');
+                }
+            }
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Writes the translations of the catalog as Android string resources.
+///
+/// Untranslated messages are left out, and the resources are sorted by name.
+/// Every message gets a resource of its own, see the module documentation for their names.
+/// The forms of a plural message get the quantity category of the numbers they are used for;
+/// the last form is also used for `other` if no form has that category.
+pub fn write<W: Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let categories = catalog.plural_categories();
+    let mut messages = catalog
+        .messages()
+        .filter(|m| !(m.id.is_empty() && m.context.is_none()))
+        .filter(|m| m.translated.iter().any(|t| !t.is_empty()))
+        .collect::<Vec<_>>();
+    // Sorted, so that the numbers appended to names do not depend on the order of the catalog.
+    messages.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+    let mut contexts = BTreeMap::new();
+    for context in messages.iter().filter_map(|m| m.context.as_deref()) {
+        *contexts.entry(context).or_insert(0) += 1;
+    }
+
+    let mut resources = BTreeMap::new();
+    for message in messages {
+        let shared = message.context.as_deref().is_some_and(|c| contexts[c] > 1);
+        let mut name = resource_name(message, shared);
+        if resources.contains_key(&name) {
+            name = (2..)
+                .map(|n| format!("{}_{}", name, n))
+                .find(|n| !resources.contains_key(n))
+                .unwrap();
+        }
+        let resource = match message.plural {
+            None => Element::new("string")
+                .attr("name", &name)
+                .text(&escape(&message.translated[0])),
+            Some(_) => {
+                let mut plurals = Element::new("plurals").attr("name", &name);
+                for (category, translated) in cldr::variants(&message.translated, &categories) {
+                    plurals = plurals.child(
+                        Element::new("item")
+                            .attr("quantity", category)
+                            .text(&escape(translated)),
+                    );
+                }
+                plurals
+            }
+        };
+        resources.insert(name, resource);
+    }
+
+    let root = resources
+        .into_values()
+        .fold(Element::new("resources"), Element::child);
+    writer.write_all(root.to_document(None).as_bytes())?;
+    Ok(())
+}
+
+/// A string or plurals resource, with the values of the quantity categories.
+///
+/// Other formats keyed by name build their catalogs from resources as well.
+pub(crate) enum Resource {
+    String(String),
+    Plurals(Vec<(String, String)>),
+}
+
+fn read_resources<R: Read>(reader: R) -> Result<Vec<(String, Resource)>, Error> {
+    let root = xml::read(reader)?;
+    if root.name != "resources" {
+        return Err(InvalidDocument(format!(
+            "expected a `resources` element, found `{}`",
+            root.name
+        )));
+    }
+    let mut resources = vec![];
+    for element in root.elements() {
+        if element.get("translatable") == Some("false") {
+            continue;
+        }
+        let resource = match element.name.as_str() {
+            "string" => Resource::String(unescape(&element.content())),
+            "plurals" => Resource::Plurals(
+                element
+                    .all("item")
+                    .map(|item| {
+                        let quantity = item.get("quantity").unwrap_or_default().to_owned();
+                        (quantity, unescape(&item.content()))
+                    })
+                    .collect(),
+            ),
+            _ => continue,
+        };
+        let name = element.get("name").ok_or_else(|| {
+            InvalidDocument(format!("`{}` resource without a name", element.name))
+        })?;
+        resources.push((name.to_owned(), resource));
+    }
+    Ok(resources)
+}
+
+/// Parses Android string resources of the given language, e.g. `lt` or `pt-rBR`,
+/// into a catalog with the resource names as the original strings.
+///
+/// The plural formula is the one of the language, if it is known,
+/// and the forms of plural messages are taken from the items of their quantity category,
+/// falling back to `other`. The plural original string is the resource name as well.
+pub fn parse<R: Read>(reader: R, language: &str) -> Result<Catalog, Error> {
+    build_catalog(read_resources(reader)?, None, &language_code(language))
+}
+
+/// Parses Android string resources of the given language into a catalog,
+/// with the resource names as the contexts and the strings of the resources
+/// of the source language as the original strings.
+///
+/// The original plural strings are the `other` items of the source resources,
+/// and the singular ones the `one` items, if any.
+/// Resources missing from the source are keyed by their name, like `parse` does.
+pub fn parse_with_source<R: Read, S: Read>(
+    reader: R,
+    source: S,
+    language: &str,
+) -> Result<Catalog, Error> {
+    let source: BTreeMap<_, _> = read_resources(source)?.into_iter().collect();
+    build_catalog(
+        read_resources(reader)?,
+        Some(&source),
+        &language_code(language),
+    )
+}
+
+/// Converts an Android language qualifier like `pt-rBR` to a code like `pt_BR`.
+fn language_code(qualifier: &str) -> String {
+    qualifier.replacen("-r", "_", 1)
+}
+
+/// Builds a catalog of the given language from the named resources,
+/// keyed by their name or, if the resources of the source language are given,
+/// by their name as the context and their source string.
+pub(crate) fn build_catalog(
+    resources: Vec<(String, Resource)>,
+    source: Option<&BTreeMap<String, Resource>>,
+    language: &str,
+) -> Result<Catalog, Error> {
+    let mut po = PoFile::new();
+    po.entries.push(PoEntry::header_for(Some(language)));
+    let header = po.to_catalog()?;
+    let categories = header.plural_categories();
+    let (nplurals, _) = header.plural_forms();
+
+    let quantity = |items: &[(String, String)], category: &str| {
+        let find = |c: &str| items.iter().find(|(q, _)| q == c).map(|(_, v)| v.clone());
+        find(category).or_else(|| find("other"))
+    };
+    for (name, resource) in resources {
+        let original = source.and_then(|s| s.get(&name));
+        let mut entry = match original {
+            Some(Resource::String(id)) => PoEntry::new(id.clone()),
+            Some(Resource::Plurals(items)) => {
+                let mut entry = PoEntry::new(quantity(items, "one").unwrap_or_default());
+                entry.id_plural = quantity(items, "other");
+                entry
+            }
+            None => PoEntry::new(name.clone()),
+        };
+        if original.is_some() {
+            entry.context = Some(name.clone());
+        }
+        match resource {
+            Resource::String(value) => entry.translations = vec![value],
+            Resource::Plurals(items) => {
+                entry.id_plural.get_or_insert_with(|| name.clone());
+                entry.translations = categories
+                    .iter()
+                    .take(nplurals)
+                    .map(|category| quantity(&items, category).unwrap_or_default())
+                    .collect();
+            }
+        }
+        po.entries.push(entry);
+    }
+    po.to_catalog()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);\n"
+
+msgctxt "open_file"
+msgid "Open \"%s\"?"
+msgstr "Atverti „%s“?\nTai užtruks."
+
+msgctxt "files"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d failas"
+msgstr[1] "%d failai"
+msgstr[2] "%d failų"
+
+msgid "@ home"
+msgstr "@ namuose  "
+
+msgid "Untranslated"
+msgstr ""
+"#;
+
+    #[test]
+    fn test_write() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<resources>
+  <string name="__home">"\@ namuose  "</string>
+  <plurals name="files">
+    <item quantity="one">%d failas</item>
+    <item quantity="few">%d failai</item>
+    <item quantity="other">%d failų</item>
+  </plurals>
+  <string name="open_file">Atverti „%s“?\nTai užtruks.</string>
+</resources>
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_names() {
+        let catalog = Catalog::parse_po(
+            &br#"
+msgctxt "menu"
+msgid "File"
+msgstr "Failas"
+
+msgctxt "menu"
+msgid "Edit"
+msgstr "Taisyti"
+
+msgid "Save as"
+msgstr "Issaugoti kaip"
+
+msgid "Save-as"
+msgstr "Issaugoti kaip..."
+
+msgctxt "Save as"
+msgid "Save"
+msgstr "Issaugoti"
+"#[..],
+        )
+        .unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        let parsed = parse(&output[..], "lt").unwrap();
+        assert_eq!(parsed.gettext("menu_File"), "Failas");
+        assert_eq!(parsed.gettext("menu_Edit"), "Taisyti");
+        assert_eq!(parsed.gettext("Save_as"), "Issaugoti kaip");
+        assert_eq!(parsed.gettext("Save_as_2"), "Issaugoti kaip...");
+        assert_eq!(parsed.gettext("Save_as_3"), "Issaugoti");
+        assert_eq!(parsed.messages().count(), 6);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+
+        let parsed = parse(&output[..], "lt").unwrap();
+        assert_eq!(parsed.gettext("open_file"), "Atverti „%s“?\nTai užtruks.");
+        assert_eq!(parsed.gettext("__home"), "@ namuose  ");
+        for n in 0..30 {
+            assert_eq!(
+                parsed.ngettext("files", "files", n),
+                catalog.npgettext("files", "%d file", "%d files", n)
+            );
+        }
+
+        let source = r#"<resources>
+  <string name="open_file">Open \"%s\"?</string>
+  <plurals name="files">
+    <item quantity="one">%d file</item>
+    <item quantity="other">%d files</item>
+  </plurals>
+</resources>"#;
+        let parsed = parse_with_source(&output[..], source.as_bytes(), "lt").unwrap();
+        assert_eq!(
+            parsed.pgettext("open_file", "Open \"%s\"?"),
+            "Atverti „%s“?\nTai užtruks."
+        );
+        assert_eq!(parsed.gettext("__home"), "@ namuose  ");
+        for n in 0..30 {
+            assert_eq!(
+                parsed.npgettext("files", "%d file", "%d files", n),
+                catalog.npgettext("files", "%d file", "%d files", n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let catalog = parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:tools="http://schemas.android.com/tools">
+    <string name="app_name" translatable="false">Notes</string>
+    <string name="greeting">
+        Hello,   <b>world</b>!
+        It\'s été.
+    </string>
+    <plurals name="days">
+        <item quantity="one">%d день</item>
+        <item quantity="few">%d дня</item>
+        <item quantity="many">%d дней</item>
+        <item quantity="other">%d дня</item>
+    </plurals>
+    <string-array name="planets"><item>Mercury</item></string-array>
+</resources>"#
+                .as_bytes(),
+            "ru",
+        )
+        .unwrap();
+        assert_eq!(catalog.gettext("app_name"), "app_name");
+        assert_eq!(catalog.gettext("greeting"), "Hello, world! It's été.");
+        assert_eq!(catalog.ngettext("days", "days", 1), "%d день");
+        assert_eq!(catalog.ngettext("days", "days", 3), "%d дня");
+        assert_eq!(catalog.ngettext("days", "days", 11), "%d дней");
+        assert_eq!(catalog.gettext("planets"), "planets");
+
+        assert!(matches!(
+            parse(&b"<resources><string>x</string></resources>"[..], "en"),
+            Err(InvalidDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! Export of catalogs to the localization files of Apple platforms:
+//! `Localizable.strings` for the messages without a plural form,
+//! and `Localizable.stringsdict` for the ones with one.
+//!
+//! The key of a message is its context, if it has one, or else its original string,
+//! which is what `NSLocalizedString` is usually given.
+//! Untranslated messages are left out, and the entries are sorted by key.
+//! Writing fails with `Error::InvalidDocument` if two messages have the same key,
+//! e.g. two messages in the same context, in either file.
+//!
+//! ```
+//! use gettext::{apple, Catalog};
+//!
+//! let catalog = Catalog::parse_po(&b"msgid \"Open\"\nmsgstr \"Atverti\"\n"[..]).unwrap();
+//! let mut output = vec![];
+//! apple::write_strings(&catalog, &mut output).unwrap();
+//! assert_eq!(output, b"\"Open\" = \"Atverti\";\n");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::writer::message_location;
+use crate::xml::Element;
+use crate::Error::{self, InvalidDocument};
+use crate::{check, cldr, Catalog, Message};
+
+/// Returns the translated messages of the catalog with or without a plural form,
+/// by key.
+///
+/// The keys of the messages of the other kind are checked as well,
+/// as `.stringsdict` entries take precedence over `.strings` ones with the same key.
+fn messages(catalog: &Catalog, plural: bool) -> Result<BTreeMap<&str, &Message>, Error> {
+    let mut messages = catalog
+        .messages()
+        .filter(|m| !m.id.is_empty() || m.context.is_some())
+        .filter(|m| m.translated.iter().any(|t| !t.is_empty()))
+        .collect::<Vec<_>>();
+    // Sorted, so that the same duplicates are reported every time.
+    messages.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+
+    let mut by_key = BTreeMap::new();
+    for message in messages {
+        let key = message.context.as_deref().unwrap_or(&message.id);
+        if let Some(other) = by_key.insert(key, message) {
+            return Err(InvalidDocument(format!(
+                "{} and {} have the same key {:?}",
+                message_location(other.context.as_deref(), &other.id),
+                message_location(message.context.as_deref(), &message.id),
+                key
+            )));
+        }
+    }
+    by_key.retain(|_, m| m.plural.is_some() == plural);
+    Ok(by_key)
+}
+
+/// Quotes and escapes a string of a `.strings` file.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\U{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes the messages of the catalog without a plural form as a UTF-8 encoded
+/// `.strings` file, one `"key" = "translation";` pair per line.
+pub fn write_strings<W: Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    for (key, message) in messages(catalog, false)? {
+        writeln!(
+            writer,
+            "{} = {};",
+            quote(key),
+            quote(&message.translated[0])
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the messages of the catalog with a plural form as a `.stringsdict` property list.
+///
+/// Each message is formatted as `%#@count@`, with a variant of the `count` variable
+/// for the CLDR category of each plural form, derived from the plural formula
+/// and the language of the catalog; the last form is also used for `other` if no form
+/// has that category. The format of the number is the one of the first integer directive
+/// of the original plural string, e.g. `ld` for `%ld`, or `d` if it has none.
+pub fn write_stringsdict<W: Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let categories = catalog.plural_categories();
+    let key = |key: &str| Element::new("key").text(key);
+    let string = |value: &str| Element::new("string").text(value);
+
+    let mut dict = Element::new("dict");
+    for (message_key, message) in messages(catalog, true)? {
+        let original = message.plural.as_deref().unwrap_or(&message.id);
+        let value_type = check::integer_conversion(original).unwrap_or_else(|| "d".to_owned());
+        let mut variable = Element::new("dict")
+            .child(key("NSStringFormatSpecTypeKey"))
+            .child(string("NSStringPluralRuleType"))
+            .child(key("NSStringFormatValueTypeKey"))
+            .child(string(&value_type));
+        for (category, translated) in cldr::variants(&message.translated, &categories) {
+            variable = variable.child(key(category)).child(string(translated));
+        }
+        dict = dict.child(key(message_key)).child(
+            Element::new("dict")
+                .child(key("NSStringLocalizedFormatKey"))
+                .child(string("%#@count@"))
+                .child(key("count"))
+                .child(variable),
+        );
+    }
+
+    let plist = Element::new("plist").attr("version", "1.0").child(dict);
+    let doctype = "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+                   \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">";
+    writer.write_all(plist.to_document(Some(doctype)).as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);\n"
+
+msgid "Open \"%@\"?"
+msgstr "Atverti „%@“?\n\tTai užtruks."
+
+msgctxt "close_button"
+msgid "Close"
+msgstr "Uždaryti"
+
+msgid "%ld file"
+msgid_plural "%ld files"
+msgstr[0] "%ld failas"
+msgstr[1] "%ld failai"
+msgstr[2] "%ld failų"
+
+msgid "Untranslated"
+msgstr ""
+"#;
+
+    #[test]
+    fn test_write_strings() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write_strings(&catalog, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#""Open \"%@\"?" = "Atverti „%@“?\n\tTai užtruks.";
+"close_button" = "Uždaryti";
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_stringsdict() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write_stringsdict(&catalog, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+  <dict>
+    <key>%ld file</key>
+    <dict>
+      <key>NSStringLocalizedFormatKey</key>
+      <string>%#@count@</string>
+      <key>count</key>
+      <dict>
+        <key>NSStringFormatSpecTypeKey</key>
+        <string>NSStringPluralRuleType</string>
+        <key>NSStringFormatValueTypeKey</key>
+        <string>ld</string>
+        <key>one</key>
+        <string>%ld failas</string>
+        <key>few</key>
+        <string>%ld failai</string>
+        <key>other</key>
+        <string>%ld failų</string>
+      </dict>
+    </dict>
+  </dict>
+</plist>
+"#
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let catalog = Catalog::parse_po(
+            &br#"
+msgctxt "menu"
+msgid "File"
+msgstr "Failas"
+
+msgctxt "menu"
+msgid "Edit"
+msgstr "Taisyti"
+"#[..],
+        )
+        .unwrap();
+        match write_strings(&catalog, &mut vec![]) {
+            Err(InvalidDocument(reason)) => assert_eq!(
+                reason,
+                r#"msgctxt "menu" msgid "Edit" and msgctxt "menu" msgid "File" have the same key "menu""#
+            ),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // A plural message would override a singular one with the same key.
+        let catalog = Catalog::parse_po(
+            &br#"
+msgid "%d file"
+msgstr "%d failas"
+
+msgctxt "%d file"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d failas"
+msgstr[1] "%d failai"
+"#[..],
+        )
+        .unwrap();
+        assert!(matches!(
+            write_stringsdict(&catalog, &mut vec![]),
+            Err(InvalidDocument(_))
+        ));
+    }
+}
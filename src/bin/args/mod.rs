@@ -0,0 +1,134 @@
+//! Parsing of the command line, shared by the binaries.
+
+// Not every binary uses every method.
+#![allow(dead_code)]
+
+/// An argument of the command line.
+#[derive(Debug, PartialEq)]
+pub enum Arg {
+    /// The name of an option, e.g. `-o` or `--output-file`,
+    /// without its value if given as `--output-file=FILE`.
+    Option(String),
+    /// Any other argument, e.g. an input file or `-` for stdin.
+    Positional(String),
+}
+
+/// Args splits the command line into options, their values and positional arguments.
+pub struct Args<I> {
+    args: I,
+    /// The last option returned by `next`.
+    option: String,
+    /// Its value, if given as `--name=value` and not taken yet.
+    value: Option<String>,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    /// Creates a parser of the given arguments, not including the program name.
+    pub fn new(args: I) -> Self {
+        Args {
+            args,
+            option: String::new(),
+            value: None,
+        }
+    }
+
+    /// Returns the next argument, or `Err` if the previous option was given a value
+    /// which neither `value` nor `optional_value` took.
+    pub fn next(&mut self) -> Result<Option<Arg>, String> {
+        if self.value.take().is_some() {
+            return Err(format!(
+                "option `{}` does not take an argument",
+                self.option
+            ));
+        }
+        let arg = match self.args.next() {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        if !arg.starts_with('-') || arg == "-" {
+            return Ok(Some(Arg::Positional(arg)));
+        }
+        self.option = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                self.value = Some(arg[i + 1..].to_owned());
+                arg[..i].to_owned()
+            }
+            _ => arg,
+        };
+        Ok(Some(Arg::Option(self.option.clone())))
+    }
+
+    /// Returns the value of the last option, given either as `--name=value`
+    /// or as the following argument.
+    pub fn value(&mut self) -> Result<String, String> {
+        self.value
+            .take()
+            .or_else(|| self.args.next())
+            .ok_or_else(|| format!("option `{}` requires an argument", self.option))
+    }
+
+    /// Returns the value of the last option if given as `--name=value`.
+    pub fn optional_value(&mut self) -> Option<String> {
+        self.value.take()
+    }
+
+    /// Returns the error for the last option, which is not a known one.
+    pub fn unknown(&self) -> String {
+        format!("unknown option `{}`", self.option)
+    }
+}
+
+/// Returns the only input file among the positional arguments.
+pub fn single_input(mut inputs: Vec<String>) -> Result<String, String> {
+    match inputs.len() {
+        0 => Err("no input file given".to_owned()),
+        1 => Ok(inputs.pop().unwrap()),
+        _ => Err("only one input file can be given".to_owned()),
+    }
+}
+
+#[test]
+fn test_args() {
+    let mut args = Args::new(
+        [
+            "-o",
+            "out.po",
+            "--output=-",
+            "--add-comments",
+            "-",
+            "--keyword=tr",
+            "in.rs",
+        ]
+        .iter()
+        .map(|s| s.to_string()),
+    );
+    let option = |name: &str| Ok(Some(Arg::Option(name.to_owned())));
+    assert_eq!(args.next(), option("-o"));
+    assert_eq!(args.value().unwrap(), "out.po");
+    assert_eq!(args.next(), option("--output"));
+    assert_eq!(args.value().unwrap(), "-");
+    assert_eq!(args.next(), option("--add-comments"));
+    assert_eq!(args.optional_value(), None);
+    assert_eq!(args.next(), Ok(Some(Arg::Positional("-".to_owned()))));
+    assert_eq!(args.next(), option("--keyword"));
+    assert_eq!(args.optional_value().unwrap(), "tr");
+    assert_eq!(args.next(), Ok(Some(Arg::Positional("in.rs".to_owned()))));
+    assert_eq!(args.next(), Ok(None));
+    assert_eq!(args.unknown(), "unknown option `--keyword`");
+
+    let mut args = Args::new(["--update=yes", "-o"].iter().map(|s| s.to_string()));
+    assert_eq!(args.next(), option("--update"));
+    assert_eq!(
+        args.next().unwrap_err(),
+        "option `--update` does not take an argument"
+    );
+    assert_eq!(args.next(), option("-o"));
+    assert_eq!(
+        args.value().unwrap_err(),
+        "option `-o` requires an argument"
+    );
+
+    assert_eq!(single_input(vec!["a.po".to_owned()]).unwrap(), "a.po");
+    assert!(single_input(vec![]).is_err());
+    assert!(single_input(vec!["a.po".to_owned(), "b.po".to_owned()]).is_err());
+}
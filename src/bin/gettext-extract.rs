@@ -0,0 +1,543 @@
+//! Extracts translatable strings from Rust source files
+//! into a POT template, like `xgettext` does for C.
+//!
+//! Calls to the `Catalog` methods `gettext`, `ngettext`, `pgettext` and `npgettext`
+//! and to the `gettext!` family of macros of `gettext-macros`
+//! are recognized by default; more keyword functions, methods and macros
+//! can be configured using the `--keyword` option.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gettext::po::{PoEntry, PoFile};
+use proc_macro2::Span;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprLit, Lit, Token};
+
+use crate::args::{Arg, Args};
+
+mod args;
+
+const USAGE: &str = "\
+Usage: gettext-extract [OPTION]... [FILE|DIRECTORY]...
+Extracts translatable strings from the given Rust source files
+(or all the .rs files under the given directories) into a POT file.
+
+Options:
+  -o, --output=FILE           write the template to FILE instead of stdout
+  -k, --keyword=SPEC          also look for the keyword SPEC, e.g. `tr`, `tr:1c,2`
+                              or `tr!:2,3` for a macro (see xgettext --keyword)
+      --no-default-keywords   do not look for gettext, ngettext, pgettext and npgettext,
+                              neither the methods nor the macros
+  -c, --add-comments[=TAG]    place comment blocks preceding keyword lines
+                              (starting with TAG, if given) into the template
+      --omit-header           do not write the header entry
+  -h, --help                  display this help and exit";
+
+const DEFAULT_KEYWORDS: &[&str] = &[
+    "gettext",
+    "ngettext:1,2",
+    "pgettext:1c,2",
+    "npgettext:1c,2,3",
+    "gettext!:2",
+    "ngettext!:2,3",
+    "pgettext!:2c,3",
+    "npgettext!:2c,3,4",
+];
+
+/// Describes which arguments of a keyword call hold the strings to extract.
+/// Argument positions are 1-based and do not count the receiver of method calls.
+#[derive(Clone, Debug, PartialEq)]
+struct Keyword {
+    name: String,
+    is_macro: bool,
+    context: Option<usize>,
+    singular: usize,
+    plural: Option<usize>,
+}
+
+impl Keyword {
+    /// Parses a keyword specification in the syntax of `xgettext --keyword`.
+    fn parse(spec: &str) -> Result<Keyword, String> {
+        let invalid = || format!("invalid keyword specification `{}`", spec);
+        let (name, args) = match spec.find(':') {
+            Some(i) => (&spec[..i], Some(&spec[i + 1..])),
+            None => (spec, None),
+        };
+        let (name, is_macro) = match name.strip_suffix('!') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        if name.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut keyword = Keyword {
+            name: name.to_owned(),
+            is_macro,
+            context: None,
+            singular: 1,
+            plural: None,
+        };
+        if let Some(args) = args {
+            let mut positions = vec![];
+            for arg in args.split(',') {
+                match arg.strip_suffix('c') {
+                    Some(n) if keyword.context.is_none() => {
+                        keyword.context = Some(n.parse().map_err(|_| invalid())?)
+                    }
+                    Some(_) => return Err(invalid()),
+                    None => positions.push(arg.parse().map_err(|_| invalid())?),
+                }
+            }
+            match positions[..] {
+                [singular] => keyword.singular = singular,
+                [singular, plural] => {
+                    keyword.singular = singular;
+                    keyword.plural = Some(plural);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        let positions = [keyword.context, Some(keyword.singular), keyword.plural];
+        if positions.contains(&Some(0)) {
+            return Err(invalid());
+        }
+        Ok(keyword)
+    }
+}
+
+/// The messages extracted so far, in the order of their first appearance.
+#[derive(Debug, Default)]
+struct Messages {
+    entries: Vec<PoEntry>,
+    index: HashMap<(Option<String>, String), usize>,
+}
+
+impl Messages {
+    fn add(
+        &mut self,
+        context: Option<String>,
+        id: String,
+        plural: Option<String>,
+        reference: String,
+        comments: Vec<String>,
+    ) {
+        let entries = &mut self.entries;
+        let i = *self
+            .index
+            .entry((context.clone(), id.clone()))
+            .or_insert_with(|| {
+                entries.push(PoEntry {
+                    context,
+                    ..PoEntry::new(id)
+                });
+                entries.len() - 1
+            });
+        let entry = &mut entries[i];
+        if entry.id_plural.is_none() {
+            entry.id_plural = plural;
+        }
+        if !entry.references.contains(&reference) {
+            entry.references.push(reference);
+        }
+        for comment in comments {
+            if !entry.extracted_comments.contains(&comment) {
+                entry.extracted_comments.push(comment);
+            }
+        }
+    }
+}
+
+struct Extractor<'a> {
+    keywords: &'a [Keyword],
+    comment_tag: Option<&'a str>,
+    file: &'a str,
+    lines: Vec<&'a str>,
+    messages: &'a mut Messages,
+}
+
+impl Extractor<'_> {
+    fn check_call<'e, I>(&mut self, name: &str, is_macro: bool, span: Span, args: I)
+    where
+        I: IntoIterator<Item = &'e Expr>,
+    {
+        let args = args.into_iter().collect::<Vec<_>>();
+        let string_arg = |position: usize| match args.get(position - 1) {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            })) => Some(s.value()),
+            _ => None,
+        };
+
+        for keyword in self.keywords {
+            if keyword.name != name || keyword.is_macro != is_macro {
+                continue;
+            }
+            let id = match string_arg(keyword.singular) {
+                Some(id) => id,
+                None => continue,
+            };
+            let context = match keyword.context.map(string_arg) {
+                Some(None) => continue,
+                Some(context) => context,
+                None => None,
+            };
+            let plural = match keyword.plural.map(string_arg) {
+                Some(None) => continue,
+                Some(plural) => plural,
+                None => None,
+            };
+            let line = span.start().line;
+            let reference = format!("{}:{}", self.file, line);
+            let comments = self.comments_before(line);
+            self.messages.add(context, id, plural, reference, comments);
+            return;
+        }
+    }
+
+    /// Returns the block of `//` comments directly preceding the given line.
+    fn comments_before(&self, line: usize) -> Vec<String> {
+        let tag = match self.comment_tag {
+            Some(tag) => tag,
+            None => return vec![],
+        };
+        let mut comments = self.lines[..line.saturating_sub(1)]
+            .iter()
+            .rev()
+            .map(|l| l.trim())
+            .take_while(|l| l.starts_with("//"))
+            .map(|l| l.trim_start_matches('/').trim().to_owned())
+            .collect::<Vec<_>>();
+        comments.reverse();
+        match comments.iter().position(|c| c.starts_with(tag)) {
+            Some(start) => comments.split_off(start),
+            None => vec![],
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Extractor<'_> {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let name = call.method.to_string();
+        self.check_call(&name, false, call.method.span(), &call.args);
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let Expr::Path(ref func) = *call.func {
+            if let Some(segment) = func.path.segments.last() {
+                let name = segment.ident.to_string();
+                self.check_call(&name, false, segment.ident.span(), &call.args);
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // Macro arguments are opaque to syn, but most macros
+        // (println!, format!, vec! and alike) take a list of expressions.
+        let args = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated);
+        if let (Ok(args), Some(segment)) = (args, mac.path.segments.last()) {
+            let name = segment.ident.to_string();
+            self.check_call(&name, true, segment.ident.span(), &args);
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// Extracts the messages from the given Rust source code.
+fn extract_source(
+    file: &str,
+    source: &str,
+    keywords: &[Keyword],
+    comment_tag: Option<&str>,
+    messages: &mut Messages,
+) -> Result<(), syn::Error> {
+    let ast = syn::parse_file(source)?;
+    let mut extractor = Extractor {
+        keywords,
+        comment_tag,
+        file,
+        lines: source.lines().collect(),
+        messages,
+    };
+    extractor.visit_file(&ast);
+    Ok(())
+}
+
+/// Returns the Rust source files at the given path, recursing into directories.
+fn source_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_owned()]);
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    let mut files = vec![];
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(source_files(&entry)?);
+        } else if entry.extension().is_some_and(|e| e == "rs") {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+fn header() -> PoEntry {
+    PoEntry {
+        translator_comments: vec![
+            "SOME DESCRIPTIVE TITLE.".to_owned(),
+            "Copyright (C) YEAR THE PACKAGE'S COPYRIGHT HOLDER".to_owned(),
+            "This file is distributed under the same license as the PACKAGE package.".to_owned(),
+            "FIRST AUTHOR <EMAIL@ADDRESS>, YEAR.".to_owned(),
+            "".to_owned(),
+        ],
+        flags: vec!["fuzzy".to_owned()],
+        translations: vec![format!(
+            "Project-Id-Version: PACKAGE VERSION\n\
+             Report-Msgid-Bugs-To: \n\
+             POT-Creation-Date: {}\n\
+             PO-Revision-Date: YEAR-MO-DA HO:MI+ZONE\n\
+             Last-Translator: FULL NAME <EMAIL@ADDRESS>\n\
+             Language-Team: LANGUAGE <LL@li.org>\n\
+             Language: \n\
+             MIME-Version: 1.0\n\
+             Content-Type: text/plain; charset=UTF-8\n\
+             Content-Transfer-Encoding: 8bit\n\
+             Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n",
+            format_date(SystemTime::now())
+        )],
+        ..PoEntry::new("")
+    }
+}
+
+/// Formats the time in UTC as in the `POT-Creation-Date` header.
+fn format_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}+0000",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+struct Options {
+    output: Option<String>,
+    keywords: Vec<Keyword>,
+    comment_tag: Option<String>,
+    omit_header: bool,
+    inputs: Vec<PathBuf>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut opts = Options {
+        output: None,
+        keywords: vec![],
+        comment_tag: None,
+        omit_header: false,
+        inputs: vec![],
+    };
+    let mut args = Args::new(args);
+    let mut default_keywords = true;
+    while let Some(arg) = args.next()? {
+        let name = match arg {
+            Arg::Option(name) => name,
+            Arg::Positional(input) => {
+                opts.inputs.push(PathBuf::from(input));
+                continue;
+            }
+        };
+        match name.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" | "--output" => opts.output = Some(args.value()?),
+            "-k" | "--keyword" => opts.keywords.push(Keyword::parse(&args.value()?)?),
+            "--no-default-keywords" => default_keywords = false,
+            "--add-comments" => opts.comment_tag = Some(args.optional_value().unwrap_or_default()),
+            "--omit-header" => opts.omit_header = true,
+            _ if name.starts_with("-c") => opts.comment_tag = Some(name[2..].to_owned()),
+            _ => return Err(args.unknown()),
+        }
+    }
+    if default_keywords {
+        for spec in DEFAULT_KEYWORDS {
+            opts.keywords.push(Keyword::parse(spec).unwrap());
+        }
+    }
+    if opts.inputs.is_empty() {
+        return Err("no input files given".to_owned());
+    }
+    Ok(opts)
+}
+
+fn run() -> Result<(), String> {
+    let opts = parse_args(env::args().skip(1))?;
+
+    let mut messages = Messages::default();
+    for input in &opts.inputs {
+        let files = source_files(input).map_err(|e| format!("{}: {}", input.display(), e))?;
+        for file in files {
+            let name = file.display().to_string();
+            let source = fs::read_to_string(&file).map_err(|e| format!("{}: {}", name, e))?;
+            extract_source(
+                &name,
+                &source,
+                &opts.keywords,
+                opts.comment_tag.as_deref(),
+                &mut messages,
+            )
+            .map_err(|e| {
+                let start = e.span().start();
+                format!("{}:{}:{}: {}", name, start.line, start.column + 1, e)
+            })?;
+        }
+    }
+
+    let mut pot = PoFile::new();
+    if !opts.omit_header {
+        pot.entries.push(header());
+    }
+    pot.entries.extend(messages.entries);
+
+    let result = match opts.output.as_deref() {
+        None | Some("-") => pot.write(io::stdout().lock()),
+        Some(path) => File::create(path).and_then(|f| {
+            let mut writer = io::BufWriter::new(f);
+            pot.write(&mut writer)?;
+            writer.flush()
+        }),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("gettext-extract: {}", err);
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_keyword_parse() {
+    assert_eq!(
+        Keyword::parse("tr").unwrap(),
+        Keyword {
+            name: "tr".to_owned(),
+            is_macro: false,
+            context: None,
+            singular: 1,
+            plural: None,
+        }
+    );
+    assert_eq!(
+        Keyword::parse("tr!:1c,3,2").unwrap(),
+        Keyword {
+            name: "tr".to_owned(),
+            is_macro: true,
+            context: Some(1),
+            singular: 3,
+            plural: Some(2),
+        }
+    );
+    assert!(Keyword::parse("").is_err());
+    assert!(Keyword::parse("tr:").is_err());
+    assert!(Keyword::parse("tr:0").is_err());
+    assert!(Keyword::parse("tr:1c,2c,3").is_err());
+    assert!(Keyword::parse("tr:1,2,3").is_err());
+}
+
+#[test]
+fn test_extract_source() {
+    let source = r#"
+fn main() {
+    let catalog = gettext::Catalog::empty();
+    // not a translator comment
+    // TRANSLATORS: greeting shown on startup
+    println!("{}", catalog.gettext("Hello, world!"));
+    let n = 3;
+    println!("{}", catalog.ngettext("One file", "{} files", n).replace("{}", &n.to_string()));
+    catalog.pgettext("menu", "Open");
+    catalog.npgettext("menu", "Recent file", "Recent files", n);
+    catalog.gettext(some_variable);
+    tr!("Macro string");
+    ngettext!(&catalog, "{n} macro", "{n} macros", n, n = n);
+    // the receiver is the first argument here, so the call is not recognized
+    gettext::Catalog::gettext(&catalog, "Not a method call");
+    catalog.gettext("Hello, world!");
+}
+"#;
+    let keywords = DEFAULT_KEYWORDS
+        .iter()
+        .chain(&["tr!"])
+        .map(|k| Keyword::parse(k).unwrap())
+        .collect::<Vec<_>>();
+    let mut messages = Messages::default();
+    extract_source(
+        "src/main.rs",
+        source,
+        &keywords,
+        Some("TRANSLATORS:"),
+        &mut messages,
+    )
+    .unwrap();
+
+    let entries = messages.entries;
+    let ids = entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        [
+            "Hello, world!",
+            "One file",
+            "Open",
+            "Recent file",
+            "Macro string",
+            "{n} macro",
+        ]
+    );
+    assert_eq!(entries[0].references, ["src/main.rs:6", "src/main.rs:16"]);
+    assert_eq!(
+        entries[0].extracted_comments,
+        ["TRANSLATORS: greeting shown on startup"]
+    );
+    assert_eq!(entries[1].id_plural.as_deref(), Some("{} files"));
+    assert!(entries[1].extracted_comments.is_empty());
+    assert_eq!(entries[2].context.as_deref(), Some("menu"));
+    assert_eq!(entries[3].context.as_deref(), Some("menu"));
+    assert_eq!(entries[3].id_plural.as_deref(), Some("Recent files"));
+    assert_eq!(entries[5].id_plural.as_deref(), Some("{n} macros"));
+}
+
+#[test]
+fn test_format_date() {
+    let time = UNIX_EPOCH + std::time::Duration::from_secs(1_455_014_580);
+    assert_eq!(format_date(time), "2016-02-09 10:43+0000");
+    assert_eq!(format_date(UNIX_EPOCH), "1970-01-01 00:00+0000");
+}
@@ -0,0 +1,297 @@
+//! Compiles a PO file into a binary MO catalog, like `msgfmt` does.
+//!
+//! Fuzzy entries are left out unless `--use-fuzzy` is given,
+//! and the output is encoded in UTF-8 unless `--charset` is given.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use gettext::metadata::parse_metadata;
+use gettext::po::{PoEntry, PoFile};
+use gettext::{Counts, Decoder, ParseOptions, WriteOptions};
+
+use crate::args::{Arg, Args};
+
+mod args;
+
+const USAGE: &str = "\
+Usage: gettext-fmt [OPTION]... FILE
+Compiles the given PO file (or stdin, if FILE is `-`) into a binary MO file.
+
+Options:
+  -o, --output-file=FILE      write the output to FILE instead of messages.mo
+                              (`-` for stdout)
+      --charset=CHARSET       write the strings in CHARSET instead of UTF-8
+  -c, --check                 perform all the checks below
+      --check-format          check that format strings in translations
+                              match the ones in the original strings
+      --check-header          check the presence and contents of the header entry
+  -f, --use-fuzzy             use fuzzy entries in the output
+      --statistics            print statistics about the translations
+  -h, --help                  display this help and exit";
+
+/// The header fields `msgfmt --check-header` requires to be filled in.
+const REQUIRED_FIELDS: &[&str] = &[
+    "Project-Id-Version",
+    "PO-Revision-Date",
+    "Last-Translator",
+    "Language-Team",
+    "MIME-Version",
+    "Content-Type",
+    "Content-Transfer-Encoding",
+];
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    input: String,
+    output: String,
+    charset: Option<String>,
+    check_format: bool,
+    check_header: bool,
+    use_fuzzy: bool,
+    statistics: bool,
+}
+
+/// Describes an entry in error messages.
+fn describe(entry: &PoEntry) -> String {
+    match entry.context {
+        Some(ref ctxt) => format!("msgctxt {:?}, msgid {:?}", ctxt, entry.id),
+        None => format!("msgid {:?}", entry.id),
+    }
+}
+
+/// Checks the header entry and the number of plural forms in each entry.
+fn check_header(po: &PoFile) -> Vec<String> {
+    let header = match po.header() {
+        Some(header) => header,
+        None => return vec!["header entry is missing".to_owned()],
+    };
+    let text = header.translations.first().map_or("", |s| s.as_str());
+    let metadata = match parse_metadata(text) {
+        Ok(metadata) => metadata,
+        Err(e) => return vec![format!("header entry: {}", e)],
+    };
+    let mut errors = REQUIRED_FIELDS
+        .iter()
+        .filter(|field| metadata.get(*field).is_none_or(|v| v.is_empty()))
+        .map(|field| format!("header field `{}` is missing", field))
+        .collect::<Vec<_>>();
+
+    let (nplurals, formula) = metadata.plural_forms();
+    let has_plurals = po
+        .entries
+        .iter()
+        .any(|e| e.id_plural.is_some() && !e.obsolete);
+    match (nplurals, formula) {
+        (Some(nplurals), Some(_)) => {
+            for entry in po
+                .entries
+                .iter()
+                .filter(|e| e.id_plural.is_some() && !e.obsolete)
+            {
+                let count = entry.translations.len();
+                if count != nplurals {
+                    errors.push(format!(
+                        "{}: {} plural forms given, but the header specifies {}",
+                        describe(entry),
+                        count,
+                        nplurals
+                    ));
+                }
+            }
+        }
+        _ if has_plurals => errors
+            .push("plural entries are used, but header field `Plural-Forms` is missing".to_owned()),
+        _ => {}
+    }
+    errors
+}
+
+/// Counts the entries the way `msgfmt --statistics` does,
+/// with fuzzy translations counted as translated ones if they are used.
+fn statistics(po: &PoFile, use_fuzzy: bool) -> Counts {
+    let mut counts = po.stats().total;
+    if use_fuzzy {
+        counts.translated += counts.fuzzy;
+        counts.fuzzy = 0;
+    }
+    counts
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut opts = Options {
+        input: String::new(),
+        output: "messages.mo".to_owned(),
+        charset: None,
+        check_format: false,
+        check_header: false,
+        use_fuzzy: false,
+        statistics: false,
+    };
+    let mut args = Args::new(args);
+    let mut inputs = vec![];
+    while let Some(arg) = args.next()? {
+        let name = match arg {
+            Arg::Option(name) => name,
+            Arg::Positional(input) => {
+                inputs.push(input);
+                continue;
+            }
+        };
+        match name.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" | "--output-file" => opts.output = args.value()?,
+            "--charset" => opts.charset = Some(args.value()?),
+            "-c" | "--check" => {
+                opts.check_format = true;
+                opts.check_header = true;
+            }
+            "--check-format" => opts.check_format = true,
+            "--check-header" => opts.check_header = true,
+            "-f" | "--use-fuzzy" => opts.use_fuzzy = true,
+            "--statistics" => opts.statistics = true,
+            _ => return Err(args.unknown()),
+        }
+    }
+    opts.input = args::single_input(inputs)?;
+    Ok(opts)
+}
+
+fn run() -> Result<(), String> {
+    let opts = parse_args(env::args().skip(1))?;
+    let mut write_options = WriteOptions::new();
+    if let Some(ref charset) = opts.charset {
+        let encoding = <dyn Decoder>::for_label(charset)
+            .ok_or_else(|| format!("unknown charset `{}`", charset))?;
+        write_options = write_options.encoding(encoding);
+    }
+
+    let mut contents = vec![];
+    let read = if opts.input == "-" {
+        io::stdin().read_to_end(&mut contents)
+    } else {
+        File::open(&opts.input).and_then(|mut f| f.read_to_end(&mut contents))
+    };
+    read.map_err(|e| format!("{}: {}", opts.input, e))?;
+    let po = PoFile::parse(&contents[..]).map_err(|e| format!("{}: {}", opts.input, e))?;
+
+    let mut errors = vec![];
+    if opts.check_header {
+        errors.extend(check_header(&po));
+    }
+    if opts.check_format {
+        errors.extend(po.check_format().iter().map(|m| m.to_string()));
+    }
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("{}: {}", opts.input, error);
+        }
+        return Err(match errors.len() {
+            1 => "found 1 fatal error".to_owned(),
+            n => format!("found {} fatal errors", n),
+        });
+    }
+
+    if opts.statistics {
+        eprintln!("{}", statistics(&po, opts.use_fuzzy));
+    }
+    let catalog = po
+        .to_catalog_with_options(&ParseOptions::new().include_fuzzy(opts.use_fuzzy))
+        .map_err(|e| format!("{}: {}", opts.input, e))?;
+
+    let mut mo = vec![];
+    write_options
+        .write_mo(&catalog, &mut mo)
+        .map_err(|e| format!("{}: {}", opts.input, e))?;
+    let written = if opts.output == "-" {
+        io::stdout().write_all(&mo)
+    } else {
+        File::create(&opts.output).and_then(|mut f| f.write_all(&mo))
+    };
+    written.map_err(|e| format!("{}: {}", opts.output, e))
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("gettext-fmt: {}", err);
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(args.iter().map(|s| s.to_string()));
+    assert_eq!(
+        args(&["-c", "--charset=cp1257", "-f", "fr.po"]).unwrap(),
+        Options {
+            input: "fr.po".to_owned(),
+            output: "messages.mo".to_owned(),
+            charset: Some("cp1257".to_owned()),
+            check_format: true,
+            check_header: true,
+            use_fuzzy: true,
+            statistics: false,
+        }
+    );
+    assert_eq!(args(&["-o", "-", "-"]).unwrap().output, "-");
+    assert!(args(&["--statistics"]).is_err());
+}
+
+#[test]
+fn test_check_header() {
+    let po = PoFile::parse(
+        &br#"
+msgid ""
+msgstr ""
+"Project-Id-Version: test\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failai"
+msgstr[2] "Failu"
+"#[..],
+    )
+    .unwrap();
+    let errors = check_header(&po);
+    assert!(errors.contains(&"header field `Last-Translator` is missing".to_owned()));
+    assert!(!errors.iter().any(|e| e.contains("Project-Id-Version")));
+    assert!(errors
+        .contains(&"msgid \"File\": 3 plural forms given, but the header specifies 2".to_owned()));
+
+    assert_eq!(check_header(&PoFile::new()), ["header entry is missing"]);
+}
+
+#[test]
+fn test_statistics() {
+    let po = PoFile::parse(
+        &br#"
+msgid "Open"
+msgstr "Atverti"
+
+#, fuzzy
+msgid "Close"
+msgstr "Uzdaryti"
+
+#, fuzzy
+msgid "Save"
+msgstr ""
+"#[..],
+    )
+    .unwrap();
+    assert_eq!(
+        statistics(&po, false).to_string(),
+        "1 translated message, 1 fuzzy translation, 1 untranslated message."
+    );
+    assert_eq!(
+        statistics(&po, true).to_string(),
+        "2 translated messages, 1 untranslated message."
+    );
+}
@@ -0,0 +1,126 @@
+//! Updates the translations of a PO file to a new POT template, like `msgmerge` does.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process;
+
+use gettext::po::{MergeOptions, PoFile};
+
+use crate::args::{Arg, Args};
+
+mod args;
+
+const USAGE: &str = "\
+Usage: gettext-merge [OPTION]... DEF.po REF.pot
+Merges the translations of DEF.po into the messages of the template REF.pot.
+Changed messages are marked as fuzzy and removed ones are kept as obsolete.
+
+Options:
+  -o, --output-file=FILE      write the output to FILE instead of stdout
+  -U, --update                update DEF.po in place
+  -N, --no-fuzzy-matching     do not carry over translations of similar messages
+  -h, --help                  display this help and exit";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    translations: String,
+    template: String,
+    output: Option<String>,
+    fuzzy_matching: bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut args = Args::new(args);
+    let mut output = None;
+    let mut update = false;
+    let mut fuzzy_matching = true;
+    let mut inputs = vec![];
+    while let Some(arg) = args.next()? {
+        let name = match arg {
+            Arg::Option(name) => name,
+            Arg::Positional(input) => {
+                inputs.push(input);
+                continue;
+            }
+        };
+        match name.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" | "--output-file" => output = Some(args.value()?),
+            "-U" | "--update" => update = true,
+            "-N" | "--no-fuzzy-matching" => fuzzy_matching = false,
+            _ => return Err(args.unknown()),
+        }
+    }
+    if inputs.len() != 2 {
+        return Err("exactly two input files must be given".to_owned());
+    }
+    let template = inputs.pop().unwrap();
+    let translations = inputs.pop().unwrap();
+    if update {
+        if output.is_some() {
+            return Err("--update and --output-file can not be used together".to_owned());
+        }
+        output = Some(translations.clone());
+    }
+    Ok(Options {
+        translations,
+        template,
+        output: output.filter(|o| o != "-"),
+        fuzzy_matching,
+    })
+}
+
+fn read(path: &str) -> Result<PoFile, String> {
+    File::open(path)
+        .map_err(Into::into)
+        .and_then(PoFile::parse)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run() -> Result<(), String> {
+    let opts = parse_args(env::args().skip(1))?;
+    let translations = read(&opts.translations)?;
+    let template = read(&opts.template)?;
+
+    let merged = MergeOptions::new()
+        .fuzzy_matching(opts.fuzzy_matching)
+        .merge(&translations, &template);
+    let mut po = vec![];
+    merged.write(&mut po).map_err(|e| e.to_string())?;
+    let written = match opts.output {
+        Some(ref output) => File::create(output).and_then(|mut f| f.write_all(&po)),
+        None => io::stdout().write_all(&po),
+    };
+    written.map_err(|e| format!("{}: {}", opts.output.as_deref().unwrap_or("-"), e))
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("gettext-merge: {}", err);
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(args.iter().map(|s| s.to_string()));
+    assert_eq!(
+        args(&["-N", "lt.po", "messages.pot"]).unwrap(),
+        Options {
+            translations: "lt.po".to_owned(),
+            template: "messages.pot".to_owned(),
+            output: None,
+            fuzzy_matching: false,
+        }
+    );
+    assert_eq!(
+        args(&["-U", "lt.po", "messages.pot"]).unwrap().output,
+        Some("lt.po".to_owned())
+    );
+    assert!(args(&["-U", "-o", "new.po", "lt.po", "messages.pot"]).is_err());
+    assert!(args(&["lt.po"]).is_err());
+}
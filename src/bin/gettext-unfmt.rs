@@ -0,0 +1,85 @@
+//! Decompiles a binary MO catalog into a PO file, like `msgunfmt` does.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use gettext::po::PoFile;
+use gettext::Catalog;
+
+use crate::args::{Arg, Args};
+
+mod args;
+
+const USAGE: &str = "\
+Usage: gettext-unfmt [OPTION]... FILE
+Decompiles the given MO file (or stdin, if FILE is `-`) into a PO file.
+The entries are sorted by context and original string, and encoded in UTF-8.
+
+Options:
+  -o, --output-file=FILE      write the output to FILE instead of stdout
+  -h, --help                  display this help and exit";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    input: String,
+    output: Option<String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut args = Args::new(args);
+    let mut output = None;
+    let mut inputs = vec![];
+    while let Some(arg) = args.next()? {
+        let name = match arg {
+            Arg::Option(name) => name,
+            Arg::Positional(input) => {
+                inputs.push(input);
+                continue;
+            }
+        };
+        match name.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" | "--output-file" => output = Some(args.value()?),
+            _ => return Err(args.unknown()),
+        }
+    }
+    Ok(Options {
+        input: args::single_input(inputs)?,
+        output: output.filter(|o| o != "-"),
+    })
+}
+
+fn run() -> Result<(), String> {
+    let opts = parse_args(env::args().skip(1))?;
+
+    let mut contents = vec![];
+    let read = if opts.input == "-" {
+        io::stdin().read_to_end(&mut contents)
+    } else {
+        File::open(&opts.input).and_then(|mut f| f.read_to_end(&mut contents))
+    };
+    read.map_err(|e| format!("{}: {}", opts.input, e))?;
+    let catalog = Catalog::parse(&contents[..]).map_err(|e| format!("{}: {}", opts.input, e))?;
+
+    let mut po = vec![];
+    PoFile::from_catalog(&catalog)
+        .write(&mut po)
+        .map_err(|e| e.to_string())?;
+    let written = match opts.output {
+        Some(ref output) => File::create(output).and_then(|mut f| f.write_all(&po)),
+        None => io::stdout().write_all(&po),
+    };
+    written.map_err(|e| format!("{}: {}", opts.output.as_deref().unwrap_or("-"), e))
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("gettext-unfmt: {}", err);
+        process::exit(1);
+    }
+}
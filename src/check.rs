@@ -0,0 +1,503 @@
+use std::fmt;
+
+use crate::format;
+use crate::metadata::parse_metadata;
+use crate::parser::default_resolver;
+use crate::plurals::{Ast, Resolver};
+use crate::po::{PoEntry, PoFile};
+use crate::Catalog;
+
+/// FormatSyntax is the syntax of the placeholders in a message,
+/// as given by its `*-format` flag in a PO file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FormatSyntax {
+    /// `printf` directives like `%s`, `%5.2f` or `%2$d` (`c-format`).
+    C,
+    /// `format!` placeholders like `{}`, `{0}` or `{name:?}` (`rust-format`).
+    Rust,
+    /// `%` directives like `%s` or `%(name)d` (`python-format`).
+    Python,
+    /// `str.format` fields like `{}`, `{0}` or `{name!r}` (`python-brace-format`).
+    PythonBrace,
+}
+
+impl FormatSyntax {
+    /// Returns the syntax a flag like `c-format` stands for, if it is a known one.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "c-format" => Some(FormatSyntax::C),
+            "rust-format" => Some(FormatSyntax::Rust),
+            "python-format" => Some(FormatSyntax::Python),
+            "python-brace-format" => Some(FormatSyntax::PythonBrace),
+            _ => None,
+        }
+    }
+
+    /// Returns the flag which marks messages of this syntax in a PO file.
+    pub fn flag(self) -> &'static str {
+        match self {
+            FormatSyntax::C => "c-format",
+            FormatSyntax::Rust => "rust-format",
+            FormatSyntax::Python => "python-format",
+            FormatSyntax::PythonBrace => "python-brace-format",
+        }
+    }
+
+    /// Returns the directives of a string in this syntax.
+    fn directives(self, s: &str) -> Vec<Directive> {
+        let mut directives = match self {
+            FormatSyntax::C => percent_directives(s, false),
+            FormatSyntax::Python => percent_directives(s, true),
+            FormatSyntax::Rust | FormatSyntax::PythonBrace => brace_directives(s),
+        };
+        directives.sort();
+        directives
+    }
+}
+
+/// FormatMismatch describes a translation whose format directives
+/// do not match the ones of the original string,
+/// as returned by `Catalog::check_format` and `PoFile::check_format`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatMismatch {
+    /// The context of the message, if any.
+    pub context: Option<String>,
+    /// The original string of the message.
+    pub id: String,
+    /// The syntax of the directives which were compared.
+    pub syntax: FormatSyntax,
+    /// The index of the translated string (the plural form) which does not match.
+    pub form: usize,
+    /// The directives of the original string the translation lacks.
+    pub missing: Vec<String>,
+    /// The directives of the translation the original string lacks.
+    pub unexpected: Vec<String>,
+}
+
+/// Formats the mismatch the way `msgfmt --check-format` reports it,
+/// followed by the differing directives.
+impl fmt::Display for FormatMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref ctxt) = self.context {
+            write!(f, "msgctxt {:?}, ", ctxt)?;
+        }
+        write!(
+            f,
+            "msgid {:?}: format specifications in msgid and msgstr[{}] do not match",
+            self.id, self.form
+        )?;
+        let mut details = vec![];
+        if !self.missing.is_empty() {
+            details.push(format!("missing `{}`", self.missing.join("`, `")));
+        }
+        if !self.unexpected.is_empty() {
+            details.push(format!("unexpected `{}`", self.unexpected.join("`, `")));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// What a directive refers to, and how it formats it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Directive {
+    /// The 1-based position of the argument, or 0 for a named one.
+    position: usize,
+    name: String,
+    /// The conversion, e.g. `d` for `%5d`; flags and widths may differ.
+    conversion: String,
+    /// The directive as written, for error messages.
+    text: String,
+}
+
+impl Directive {
+    fn matches(&self, other: &Directive) -> bool {
+        self.position == other.position
+            && self.name == other.name
+            && self.conversion == other.conversion
+    }
+}
+
+/// Parses the directives of C (or Python, with `%(name)s`) format strings,
+/// by the same grammar as `format` uses.
+fn percent_directives(s: &str, python: bool) -> Vec<Directive> {
+    let syntax = if python {
+        &format::PYTHON
+    } else {
+        &format::PRINTF
+    };
+    let mut directives = vec![];
+    let mut next_arg = 1;
+    let mut rest = s;
+    while let Some(start) = rest.find('%') {
+        let spec = &rest[start + 1..];
+        if let Some(after) = spec.strip_prefix('%') {
+            rest = after;
+            continue;
+        }
+        // The name of a Python mapping key may contain any character.
+        let name = match spec.find(')') {
+            Some(i) if python && spec.starts_with('(') => Some(&spec[1..i]),
+            _ => None,
+        };
+        let skip = name.map_or(0, |name| name.len() + 2);
+        let directive = match syntax.parse(&spec[skip..]) {
+            Some(directive) => directive,
+            None => {
+                rest = spec;
+                continue;
+            }
+        };
+        let end = skip + directive.len;
+        let position = match (directive.position, name) {
+            (Some(p), _) => p + 1,
+            (None, Some(_)) => 0,
+            (None, None) => {
+                next_arg += 1;
+                next_arg - 1
+            }
+        };
+        directives.push(Directive {
+            position,
+            name: name.unwrap_or_default().to_owned(),
+            conversion: directive.conversion.to_owned(),
+            text: rest[start..start + 1 + end].to_owned(),
+        });
+        rest = &spec[end..];
+    }
+    directives
+}
+
+/// Returns the conversion of the first integer directive of a C format string,
+/// e.g. `ld` for `%ld`.
+pub(crate) fn integer_conversion(s: &str) -> Option<String> {
+    percent_directives(s, false)
+        .into_iter()
+        .map(|d| d.conversion)
+        .find(|c| c.ends_with(['d', 'i', 'u']))
+}
+
+/// Parses the fields of `format!` (or `str.format`) strings.
+fn brace_directives(s: &str) -> Vec<Directive> {
+    let mut directives = vec![];
+    let mut next_arg = 1;
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let doubled = chars.peek().map(|&(_, n)| n) == Some(c);
+        match c {
+            '{' | '}' if doubled => {
+                chars.next();
+                continue;
+            }
+            '{' => (),
+            _ => continue,
+        }
+        let end = match s[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        while chars.peek().is_some_and(|&(i, _)| i <= end) {
+            chars.next();
+        }
+        let field = &s[start + 1..end];
+        let (arg, format) = match field.find([':', '!']) {
+            Some(i) => (&field[..i], &field[i..]),
+            None => (field, ""),
+        };
+        let (position, name) = if arg.is_empty() {
+            next_arg += 1;
+            (next_arg - 1, String::new())
+        } else if let Ok(index) = arg.parse::<usize>() {
+            (index + 1, String::new())
+        } else {
+            (0, arg.to_owned())
+        };
+        // The conversion (`!r`) and the type (`?`, `x`, `e`...) matter,
+        // the alignment and widths may differ.
+        let (conversion, spec) = match format.strip_prefix('!') {
+            Some(f) => match f.find(':') {
+                Some(i) => (&f[..i], &f[i..]),
+                None => (f, ""),
+            },
+            None => ("", format),
+        };
+        let kind = spec
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '?' || c == '%')
+            .len();
+        directives.push(Directive {
+            position,
+            name,
+            conversion: format!("{}{}", conversion, &spec[kind..]),
+            text: s[start..=end].to_owned(),
+        });
+    }
+    directives
+}
+
+/// Compares the translations of a message against its original strings.
+///
+/// A plural message is compared against its plural original string.
+/// Like `msgfmt` allows, the translations of the forms which are only used
+/// for a single number, e.g. the one for `n == 1`, may leave out directives;
+/// `single` tells which forms these are.
+fn check_message(
+    context: Option<&str>,
+    id: &str,
+    id_plural: Option<&str>,
+    translations: &[String],
+    syntax: FormatSyntax,
+    single: &[bool],
+) -> Vec<FormatMismatch> {
+    let original = syntax.directives(id_plural.unwrap_or(id));
+    let mut mismatches = vec![];
+    for (form, translation) in translations.iter().enumerate() {
+        if translation.is_empty() {
+            continue;
+        }
+        let translated = syntax.directives(translation);
+        let missing = if id_plural.is_some() && single.get(form) == Some(&true) {
+            vec![]
+        } else {
+            unmatched(&original, &translated)
+        };
+        let unexpected = unmatched(&translated, &original);
+        if !missing.is_empty() || !unexpected.is_empty() {
+            mismatches.push(FormatMismatch {
+                context: context.map(str::to_owned),
+                id: id.to_owned(),
+                syntax,
+                form,
+                missing,
+                unexpected,
+            });
+        }
+    }
+    mismatches
+}
+
+/// Returns whether each of the plural forms is used for a single number only,
+/// among the numbers below 1000.
+fn single_number_forms(resolver: &Resolver, forms: usize) -> Vec<bool> {
+    let mut counts = vec![0; forms];
+    for n in 0..1000 {
+        if let Some(count) = counts.get_mut(resolver.resolve(n)) {
+            *count += 1;
+        }
+    }
+    counts.into_iter().map(|count| count == 1).collect()
+}
+
+/// Returns the directives of `a` which have no counterpart in `b`.
+fn unmatched(a: &[Directive], b: &[Directive]) -> Vec<String> {
+    let mut texts: Vec<String> = a
+        .iter()
+        .filter(|d| !b.iter().any(|o| o.matches(d)))
+        .map(|d| d.text.clone())
+        .collect();
+    texts.dedup();
+    texts
+}
+
+/// Returns the syntax of the entry's directives, given by its flags.
+pub(crate) fn entry_syntax(entry: &PoEntry) -> Option<FormatSyntax> {
+    entry.flags.iter().find_map(|f| FormatSyntax::from_flag(f))
+}
+
+pub(crate) fn check_po(po: &PoFile) -> Vec<FormatMismatch> {
+    let plural = po
+        .header()
+        .and_then(|h| h.translations.first())
+        .and_then(|h| parse_metadata(h).ok()?.plural_forms().1.map(Ast::parse))
+        .and_then(Result::ok);
+    let resolver = match plural {
+        Some(ast) => Resolver::Expr(ast),
+        None => Resolver::Function(default_resolver),
+    };
+    let mut mismatches = vec![];
+    for entry in po.entries.iter().filter(|e| !e.obsolete && !e.is_header()) {
+        if let Some(syntax) = entry_syntax(entry) {
+            mismatches.extend(check_message(
+                entry.context.as_deref(),
+                &entry.id,
+                entry.id_plural.as_deref(),
+                &entry.translations,
+                syntax,
+                &single_number_forms(&resolver, entry.translations.len()),
+            ));
+        }
+    }
+    mismatches
+}
+
+pub(crate) fn check_catalog(catalog: &Catalog, syntax: FormatSyntax) -> Vec<FormatMismatch> {
+    let mut messages = catalog
+        .messages()
+        .filter(|m| !m.id().is_empty() || m.context().is_some())
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| (a.context(), a.id()).cmp(&(b.context(), b.id())));
+    messages
+        .into_iter()
+        .flat_map(|m| {
+            let single = single_number_forms(&catalog.resolver, m.translations().len());
+            check_message(
+                m.context(),
+                m.id(),
+                m.id_plural(),
+                m.translations(),
+                syntax,
+                &single,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(syntax: FormatSyntax, s: &str) -> Vec<(usize, String, String)> {
+        syntax
+            .directives(s)
+            .into_iter()
+            .map(|d| (d.position, d.name, d.conversion))
+            .collect()
+    }
+
+    #[test]
+    fn test_c_directives() {
+        let d = |p, c: &str| (p, String::new(), c.to_owned());
+        assert_eq!(
+            texts(FormatSyntax::C, "%s has %5.2f%% of %1$s, %ld"),
+            [d(1, "s"), d(1, "s"), d(2, "f"), d(3, "ld")]
+        );
+        assert_eq!(texts(FormatSyntax::C, "%2$d %1$s"), [d(1, "s"), d(2, "d")]);
+        // Unlike `format`, the space flag is read as `printf` does.
+        assert_eq!(texts(FormatSyntax::C, "100% done, %y"), [d(1, "d")]);
+    }
+
+    #[test]
+    fn test_python_directives() {
+        assert_eq!(
+            texts(FormatSyntax::Python, "%(name)s has %d%% of %(total)5.1f %r"),
+            [
+                (0, "name".to_owned(), "s".to_owned()),
+                (0, "total".to_owned(), "f".to_owned()),
+                (1, String::new(), "d".to_owned()),
+                (2, String::new(), "r".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_brace_directives() {
+        assert_eq!(
+            texts(FormatSyntax::Rust, "{} {{literal}} {name:>5} {0:?} {:x}"),
+            [
+                (0, "name".to_owned(), String::new()),
+                (1, String::new(), String::new()),
+                (1, String::new(), "?".to_owned()),
+                (2, String::new(), "x".to_owned()),
+            ]
+        );
+        assert_eq!(
+            texts(FormatSyntax::PythonBrace, "{user!r:<10} {0:.2f}"),
+            [
+                (0, "user".to_owned(), "r".to_owned()),
+                (1, String::new(), "f".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_po() {
+        let po = PoFile::parse(
+            &br#"
+#, c-format
+msgid "%s of %d"
+msgstr "%2$d is %1$s"
+
+#, c-format
+msgctxt "bad"
+msgid "%s of %d"
+msgstr "%d of %s"
+
+#, c-format
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "Vienas failas"
+msgstr[1] "%d failai"
+msgstr[2] "%s failu"
+
+#, c-format
+msgid "%d folder"
+msgid_plural "%d folders"
+msgstr[0] "aplankas"
+msgstr[1] "aplankai"
+
+#, rust-format
+msgid "{count} items in {}"
+msgstr "{} turi {cont}"
+
+#, no-c-format
+msgid "Not %s checked"
+msgstr "%d"
+"#[..],
+        )
+        .unwrap();
+        let mismatches = check_po(&po);
+        let messages = mismatches.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "msgctxt \"bad\", msgid \"%s of %d\": format specifications in msgid and msgstr[0] do not match (missing `%s`, `%d`; unexpected `%d`, `%s`)",
+                "msgid \"One file\": format specifications in msgid and msgstr[2] do not match (missing `%d`; unexpected `%s`)",
+                "msgid \"%d folder\": format specifications in msgid and msgstr[1] do not match (missing `%d`)",
+                "msgid \"{count} items in {}\": format specifications in msgid and msgstr[0] do not match (missing `{count}`; unexpected `{cont}`)",
+            ]
+        );
+        assert_eq!(mismatches[0].syntax, FormatSyntax::C);
+        assert_eq!(mismatches[1].form, 2);
+        assert_eq!(mismatches[2].form, 1);
+
+        let catalog = po.to_catalog().unwrap();
+        let mismatches = check_catalog(&catalog, FormatSyntax::C);
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(|m| (m.context.as_deref(), m.id.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (None, "%d folder"),
+                (None, "Not %s checked"),
+                (None, "One file"),
+                (Some("bad"), "%s of %d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_single_number_forms() {
+        let po = PoFile::parse(
+            &br#"
+msgid ""
+msgstr ""
+"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);\n"
+
+#, c-format
+msgid "One file"
+msgid_plural "%d files"
+msgstr[0] "Vienas failas"
+msgstr[1] "%d failai"
+msgstr[2] "%d failu"
+"#[..],
+        )
+        .unwrap();
+        // The first form is used for 1, 21, 31...
+        let mismatches = check_po(&po);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].form, 0);
+        assert_eq!(mismatches[0].missing, ["%d"]);
+    }
+}
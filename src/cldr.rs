@@ -0,0 +1,371 @@
+//! The CLDR plural categories (`zero`, `one`, `two`, `few`, `many` and `other`)
+//! which formats other than gettext use instead of plural formulas.
+
+/// A category and the rule telling whether a whole number belongs to it.
+type Rule = (&'static str, fn(u64) -> bool);
+
+/// The plural rules for whole numbers shared by a group of languages.
+struct Family {
+    languages: &'static [&'static str],
+    /// The `Plural-Forms` header value whose forms stand for the categories, in order.
+    plural_forms: &'static str,
+    /// The categories other than `other`, tested in order.
+    categories: &'static [Rule],
+}
+
+static FAMILIES: &[Family] = &[
+    Family {
+        languages: &[
+            "ja", "ko", "zh", "vi", "th", "id", "ms", "lo", "km", "my", "jv", "yo", "bo", "dz",
+        ],
+        plural_forms: "nplurals=1; plural=0;",
+        categories: &[],
+    },
+    Family {
+        languages: &[
+            "en", "de", "nl", "sv", "da", "nb", "nn", "no", "fi", "et", "it", "es", "pt", "el",
+            "hu", "bg", "tr", "ca", "eo", "eu", "gl", "af", "sq", "az", "ka", "kk", "ky", "mn",
+            "ta", "te", "ur", "sw", "fo", "fy", "lb", "ne", "ps", "so", "tk", "uz",
+        ],
+        plural_forms: "nplurals=2; plural=(n != 1);",
+        categories: &[("one", |n| n == 1)],
+    },
+    Family {
+        languages: &[
+            "fr", "pt_BR", "hy", "ln", "am", "hi", "bn", "fa", "gu", "kn", "mr", "zu", "as", "pa",
+            "si", "ti", "wa",
+        ],
+        plural_forms: "nplurals=2; plural=(n > 1);",
+        categories: &[("one", |n| n <= 1)],
+    },
+    Family {
+        languages: &["ru", "uk", "be"],
+        plural_forms: "nplurals=3; plural=((n%10==1) && (n%100!=11)) ? 0 : (((n%10>=2) && (n%10<=4) && ((n%100<10) || (n%100>=20))) ? 1 : 2);",
+        categories: &[
+            ("one", |n| n % 10 == 1 && n % 100 != 11),
+            ("few", |n| {
+                (2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))
+            }),
+            ("many", |_| true),
+        ],
+    },
+    Family {
+        languages: &["pl"],
+        plural_forms: "nplurals=3; plural=(n==1) ? 0 : (((n%10>=2) && (n%10<=4) && ((n%100<10) || (n%100>=20))) ? 1 : 2);",
+        categories: &[
+            ("one", |n| n == 1),
+            ("few", |n| {
+                (2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))
+            }),
+            ("many", |_| true),
+        ],
+    },
+    Family {
+        languages: &["cs", "sk"],
+        plural_forms: "nplurals=3; plural=(n==1) ? 0 : (((n>=2) && (n<=4)) ? 1 : 2);",
+        categories: &[("one", |n| n == 1), ("few", |n| (2..=4).contains(&n))],
+    },
+    Family {
+        languages: &["lt"],
+        plural_forms: "nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);",
+        categories: &[
+            ("one", |n| n % 10 == 1 && !(11..=19).contains(&(n % 100))),
+            ("few", |n| {
+                (2..=9).contains(&(n % 10)) && !(11..=19).contains(&(n % 100))
+            }),
+        ],
+    },
+    Family {
+        languages: &["lv"],
+        plural_forms: "nplurals=3; plural=((n%10==0) || ((n%100>=11) && (n%100<=19))) ? 0 : (((n%10==1) && (n%100!=11)) ? 1 : 2);",
+        categories: &[
+            ("zero", |n| n % 10 == 0 || (11..=19).contains(&(n % 100))),
+            ("one", |n| n % 10 == 1 && n % 100 != 11),
+        ],
+    },
+    Family {
+        languages: &["ro", "mo"],
+        plural_forms: "nplurals=3; plural=(n==1) ? 0 : (((n==0) || ((n%100>=1) && (n%100<=19))) ? 1 : 2);",
+        categories: &[
+            ("one", |n| n == 1),
+            ("few", |n| n == 0 || (1..=19).contains(&(n % 100))),
+        ],
+    },
+    Family {
+        languages: &["sl"],
+        plural_forms: "nplurals=4; plural=(n%100==1) ? 0 : ((n%100==2) ? 1 : (((n%100==3) || (n%100==4)) ? 2 : 3));",
+        categories: &[
+            ("one", |n| n % 100 == 1),
+            ("two", |n| n % 100 == 2),
+            ("few", |n| (3..=4).contains(&(n % 100))),
+        ],
+    },
+    Family {
+        languages: &["ar"],
+        plural_forms: "nplurals=6; plural=(n==0) ? 0 : ((n==1) ? 1 : ((n==2) ? 2 : (((n%100>=3) && (n%100<=10)) ? 3 : ((n%100>=11) ? 4 : 5))));",
+        categories: &[
+            ("zero", |n| n == 0),
+            ("one", |n| n == 1),
+            ("two", |n| n == 2),
+            ("few", |n| (3..=10).contains(&(n % 100))),
+            ("many", |n| (11..=99).contains(&(n % 100))),
+        ],
+    },
+    Family {
+        languages: &["ga"],
+        plural_forms: "nplurals=5; plural=(n==1) ? 0 : ((n==2) ? 1 : (((n>=3) && (n<=6)) ? 2 : (((n>=7) && (n<=10)) ? 3 : 4)));",
+        categories: &[
+            ("one", |n| n == 1),
+            ("two", |n| n == 2),
+            ("few", |n| (3..=6).contains(&n)),
+            ("many", |n| (7..=10).contains(&n)),
+        ],
+    },
+    Family {
+        languages: &["cy"],
+        plural_forms: "nplurals=6; plural=(n==0) ? 0 : ((n==1) ? 1 : ((n==2) ? 2 : ((n==3) ? 3 : ((n==6) ? 4 : 5))));",
+        categories: &[
+            ("zero", |n| n == 0),
+            ("one", |n| n == 1),
+            ("two", |n| n == 2),
+            ("few", |n| n == 3),
+            ("many", |n| n == 6),
+        ],
+    },
+    Family {
+        languages: &["hr", "sr", "bs", "sh"],
+        plural_forms: "nplurals=3; plural=((n%10==1) && (n%100!=11)) ? 0 : (((n%10>=2) && (n%10<=4) && ((n%100<10) || (n%100>=20))) ? 1 : 2);",
+        categories: &[
+            ("one", |n| n % 10 == 1 && n % 100 != 11),
+            ("few", |n| {
+                (2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))
+            }),
+        ],
+    },
+    Family {
+        languages: &["he", "iw"],
+        plural_forms: "nplurals=3; plural=(n==1) ? 0 : ((n==2) ? 1 : 2);",
+        categories: &[("one", |n| n == 1), ("two", |n| n == 2)],
+    },
+    Family {
+        languages: &["is", "mk"],
+        plural_forms: "nplurals=2; plural=((n%10==1) && (n%100!=11)) ? 0 : 1;",
+        categories: &[("one", |n| n % 10 == 1 && n % 100 != 11)],
+    },
+    Family {
+        languages: &["gd"],
+        plural_forms: "nplurals=4; plural=((n==1) || (n==11)) ? 0 : (((n==2) || (n==12)) ? 1 : ((((n>=3) && (n<=10)) || ((n>=13) && (n<=19))) ? 2 : 3));",
+        categories: &[
+            ("one", |n| n == 1 || n == 11),
+            ("two", |n| n == 2 || n == 12),
+            ("few", |n| (3..=10).contains(&n) || (13..=19).contains(&n)),
+        ],
+    },
+    Family {
+        languages: &["mt"],
+        plural_forms: "nplurals=5; plural=(n==1) ? 0 : ((n==2) ? 1 : (((n==0) || ((n%100>=3) && (n%100<=10))) ? 2 : (((n%100>=11) && (n%100<=19)) ? 3 : 4)));",
+        categories: &[
+            ("one", |n| n == 1),
+            ("two", |n| n == 2),
+            ("few", |n| n == 0 || (3..=10).contains(&(n % 100))),
+            ("many", |n| (11..=19).contains(&(n % 100))),
+        ],
+    },
+];
+
+/// The numbers the plural formula is evaluated for.
+const SAMPLES: u64 = 1000;
+
+impl Family {
+    fn category(&self, n: u64) -> &'static str {
+        self.categories
+            .iter()
+            .find(|(_, rule)| rule(n))
+            .map_or("other", |(category, _)| category)
+    }
+
+    /// Assigns a category to each plural form, the one most of its numbers belong to,
+    /// and counts how many of the numbers agree.
+    fn assign(
+        &self,
+        nplurals: usize,
+        resolve: &dyn Fn(u64) -> usize,
+    ) -> (Vec<&'static str>, usize) {
+        let names = self
+            .categories
+            .iter()
+            .map(|(category, _)| *category)
+            .chain(Some("other"))
+            .collect::<Vec<_>>();
+        let mut counts = vec![vec![0; names.len()]; nplurals];
+        for n in 0..SAMPLES {
+            let form = resolve(n);
+            if form < nplurals {
+                let category = self.category(n);
+                counts[form][names.iter().position(|c| *c == category).unwrap()] += 1;
+            }
+        }
+        let mut score = 0;
+        let categories = counts
+            .iter()
+            .map(|counts| {
+                // Ties are resolved in favor of `other`, the last category.
+                let (i, count) = counts
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, count)| **count)
+                    .unwrap();
+                score += count;
+                names[i]
+            })
+            .collect();
+        (categories, score)
+    }
+}
+
+/// Returns the rules of a language like `lt` or `pt_BR`, falling back to its base language.
+fn family(language: &str) -> Option<&'static Family> {
+    let language = language.replace('-', "_");
+    let base = language.split('_').next().unwrap_or_default();
+    let find = |code: &str| {
+        FAMILIES
+            .iter()
+            .find(|f| f.languages.iter().any(|l| l.eq_ignore_ascii_case(code)))
+    };
+    find(&language).or_else(|| find(base))
+}
+
+/// Returns the `Plural-Forms` header value for the given language, if it is known.
+pub(crate) fn plural_forms(language: &str) -> Option<&'static str> {
+    family(language).map(|f| f.plural_forms)
+}
+
+/// Returns the CLDR category of each of the `nplurals` forms of a gettext catalog
+/// with the given plural formula.
+///
+/// The rules of the given language are used if it is known,
+/// e.g. `lt` or `pt_BR`; otherwise the rules which agree with the formula best.
+pub(crate) fn plural_categories(
+    language: Option<&str>,
+    nplurals: usize,
+    resolve: &dyn Fn(u64) -> usize,
+) -> Vec<&'static str> {
+    match language.and_then(family) {
+        Some(family) => family.assign(nplurals, resolve).0,
+        None => {
+            let mut best: Option<(Vec<_>, usize)> = None;
+            for family in FAMILIES {
+                let (categories, score) = family.assign(nplurals, resolve);
+                // Each form is expected to stand for a different category.
+                let distinct = categories
+                    .iter()
+                    .enumerate()
+                    .all(|(i, c)| !categories[..i].contains(c));
+                if distinct && best.as_ref().is_none_or(|b| score > b.1) {
+                    best = Some((categories, score));
+                }
+            }
+            best.map_or_else(|| vec!["other"; nplurals], |b| b.0)
+        }
+    }
+}
+
+/// Returns the CLDR category and the translation of each form of a plural message,
+/// leaving out the forms whose category was already used by an earlier one.
+///
+/// The last form is also returned for `other` if no form has that category,
+/// as formats keyed by category fall back to it.
+pub(crate) fn variants<'a>(
+    translated: &'a [String],
+    categories: &[&'static str],
+) -> Vec<(&'static str, &'a str)> {
+    let mut variants: Vec<(&'static str, &str)> = vec![];
+    for (translated, category) in translated.iter().zip(categories) {
+        if variants.iter().all(|(c, _)| c != category) {
+            variants.push((category, translated));
+        }
+    }
+    if !categories.contains(&"other") {
+        if let Some(last) = translated.get(categories.len().saturating_sub(1)) {
+            variants.push(("other", last));
+        }
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plurals::{Ast, Resolver};
+
+    fn categories(language: Option<&str>, nplurals: usize, formula: &str) -> Vec<&'static str> {
+        let resolver = Resolver::Expr(Ast::parse(formula).unwrap());
+        plural_categories(language, nplurals, &|n| resolver.resolve(n))
+    }
+
+    #[test]
+    fn test_plural_categories() {
+        assert_eq!(categories(Some("en"), 2, "n != 1"), ["one", "other"]);
+        assert_eq!(categories(Some("fr_FR"), 2, "n > 1"), ["one", "other"]);
+        assert_eq!(categories(Some("ja"), 1, "0"), ["other"]);
+        let lt = "(n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2)";
+        assert_eq!(categories(Some("lt"), 3, lt), ["one", "few", "other"]);
+        let ru = "(n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20)) ? 1 : 2)";
+        assert_eq!(categories(Some("ru"), 3, ru), ["one", "few", "many"]);
+        // Without a language, the formula tells the rules apart.
+        assert_eq!(categories(None, 3, ru), ["one", "few", "many"]);
+        assert_eq!(categories(None, 2, "n != 1"), ["one", "other"]);
+        let ar =
+            "n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : (n%100>=3 && n%100<=10) ? 3 : (n%100>=11) ? 4 : 5";
+        assert_eq!(
+            categories(Some("ar"), 6, ar),
+            ["zero", "one", "two", "few", "many", "other"]
+        );
+    }
+
+    #[test]
+    fn test_variants() {
+        let translated = ["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        assert_eq!(
+            variants(&translated, &["one", "few", "other"]),
+            [("one", "a"), ("few", "b"), ("other", "c")]
+        );
+        assert_eq!(
+            variants(&translated, &["one", "other", "one"]),
+            [("one", "a"), ("other", "b")]
+        );
+        assert_eq!(
+            variants(&translated, &["one", "few", "many"]),
+            [("one", "a"), ("few", "b"), ("many", "c"), ("other", "c")]
+        );
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        assert_eq!(plural_forms("pt-BR"), Some("nplurals=2; plural=(n > 1);"));
+        assert_eq!(plural_forms("de_AT"), Some("nplurals=2; plural=(n != 1);"));
+        assert_eq!(plural_forms("tlh"), None);
+        // The formula of each language agrees with its categories for every number.
+        for family in FAMILIES {
+            let language = family.languages[0];
+            let (nplurals, formula) = family
+                .plural_forms
+                .strip_prefix("nplurals=")
+                .and_then(|f| f.split_once("; plural="))
+                .unwrap();
+            let formula = formula.trim_end_matches(';');
+            let resolver = Resolver::Expr(Ast::parse(formula).unwrap());
+            let nplurals = nplurals.parse().unwrap();
+            let categories = plural_categories(Some(language), nplurals, &|n| resolver.resolve(n));
+            for n in 0..SAMPLES {
+                assert_eq!(
+                    categories[resolver.resolve(n)],
+                    family.category(n),
+                    "{} {}",
+                    language,
+                    n
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,546 @@
+//! Conversion between PO files and CSV or TSV spreadsheets, for reviewing translations
+//! in a spreadsheet program.
+//!
+//! The first row names the columns and each further row holds an entry:
+//!
+//! | `msgctxt` | `msgid` | `msgid_plural` | `msgstr[0]` … | `fuzzy` | `comments` | `extracted_comments` |
+//! |-----------|---------|----------------|---------------|---------|------------|----------------------|
+//!
+//! with a `msgstr[N]` column for each plural form, or a single `msgstr` column
+//! if no entry has a plural form. Comments are written one per line of the cell,
+//! and the `fuzzy` column holds `yes` for fuzzy entries.
+//! When reading, the columns may come in any order, all but `msgid` are optional
+//! and unknown ones are ignored.
+//!
+//! CSV files are written as RFC 4180 describes, with quoted fields where needed,
+//! and TSV files with `\t`, `\n`, `\r` and `\\` escapes, as tabs and line breaks
+//! can not be quoted in them.
+//!
+//! A `Catalog` can be converted using `PoFile::from_catalog` and `PoFile::to_catalog`.
+//!
+//! ```
+//! use gettext::csv::{self, Format};
+//!
+//! let po = csv::parse(&b"msgid,msgstr\nOpen,Atverti\n"[..], Format::Csv).unwrap();
+//! assert_eq!(po.to_catalog().unwrap().gettext("Open"), "Atverti");
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::metadata::parse_metadata;
+use crate::po::{PoEntry, PoFile};
+use crate::Error::{self, DecodingError, InvalidDocument};
+
+/// Format is the kind of delimited text a spreadsheet is saved as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Comma-separated values.
+    Csv,
+    /// Tab-separated values.
+    Tsv,
+}
+
+impl Format {
+    fn write_row(self, out: &mut String, fields: &[&str]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i != 0 {
+                out.push(if self == Format::Csv { ',' } else { '\t' });
+            }
+            match self {
+                Format::Csv if field.contains([',', '"', '\n', '\r']) => {
+                    out.push('"');
+                    out.push_str(&field.replace('"', "\"\""));
+                    out.push('"');
+                }
+                Format::Csv => out.push_str(field),
+                Format::Tsv => {
+                    for c in field.chars() {
+                        match c {
+                            '\t' => out.push_str("\\t"),
+                            '\n' => out.push_str("\\n"),
+                            '\r' => out.push_str("\\r"),
+                            '\\' => out.push_str("\\\\"),
+                            c => out.push(c),
+                        }
+                    }
+                }
+            }
+        }
+        out.push_str(if self == Format::Csv { "\r\n" } else { "\n" });
+    }
+
+    fn parse_rows(self, s: &str) -> Result<Vec<Vec<String>>, Error> {
+        match self {
+            Format::Csv => parse_csv(s),
+            Format::Tsv => Ok(s
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.split('\t').map(unescape_tsv).collect())
+                .collect()),
+        }
+    }
+}
+
+fn unescape_tsv(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(e @ ('t' | 'n' | 'r' | '\\'))) => {
+                chars.next();
+                unescaped.push(match e {
+                    't' => '\t',
+                    'n' => '\n',
+                    'r' => '\r',
+                    _ => '\\',
+                });
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn parse_csv(s: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let start = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(InvalidDocument(format!(
+                                "unterminated quoted field on line {}",
+                                start
+                            )))
+                        }
+                    }
+                }
+            }
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => {
+                line += 1;
+                row.push(std::mem::take(&mut field));
+                // Blank lines are skipped.
+                if row.len() > 1 || !row[0].is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Writes the entries of the PO file, including the header but not the obsolete ones,
+/// as a spreadsheet in the given format.
+pub fn write<W: Write>(po: &PoFile, format: Format, mut writer: W) -> io::Result<()> {
+    let entries = po
+        .entries
+        .iter()
+        .filter(|e| !e.obsolete)
+        .collect::<Vec<_>>();
+    let forms = entries
+        .iter()
+        .filter(|e| e.id_plural.is_some())
+        .map(|e| e.translations.len())
+        .max();
+
+    let mut out = String::new();
+    let mut header = vec![
+        "msgctxt".to_owned(),
+        "msgid".to_owned(),
+        "msgid_plural".to_owned(),
+    ];
+    match forms {
+        Some(forms) => header.extend((0..forms.max(1)).map(|i| format!("msgstr[{}]", i))),
+        None => header.push("msgstr".to_owned()),
+    }
+    header.extend(["fuzzy", "comments", "extracted_comments"].map(str::to_owned));
+    format.write_row(
+        &mut out,
+        &header.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+
+    for entry in entries {
+        let comments = entry.translator_comments.join("\n");
+        let extracted = entry.extracted_comments.join("\n");
+        let mut row = vec![
+            entry.context.as_deref().unwrap_or_default(),
+            &entry.id,
+            entry.id_plural.as_deref().unwrap_or_default(),
+        ];
+        for i in 0..forms.unwrap_or(1).max(1) {
+            row.push(entry.translations.get(i).map_or("", String::as_str));
+        }
+        row.push(if entry.has_flag("fuzzy") { "yes" } else { "" });
+        row.push(&comments);
+        row.push(&extracted);
+        format.write_row(&mut out, &row);
+    }
+    writer.write_all(out.as_bytes())
+}
+
+/// The positions of the known columns.
+struct Columns {
+    context: Option<usize>,
+    id: usize,
+    plural: Option<usize>,
+    /// The translation columns, by plural form.
+    forms: Vec<usize>,
+    fuzzy: Option<usize>,
+    comments: Option<usize>,
+    extracted: Option<usize>,
+}
+
+impl Columns {
+    fn new(header: &[String]) -> Result<Self, Error> {
+        let find = |name: &str| header.iter().position(|h| h.trim() == name);
+        let mut forms = header
+            .iter()
+            .enumerate()
+            .filter_map(|(i, h)| {
+                let h = h.trim();
+                let form = match h.strip_prefix("msgstr[")?.strip_suffix(']') {
+                    Some(form) => form.parse().ok()?,
+                    None => return None,
+                };
+                Some((form, i))
+            })
+            .collect::<Vec<(usize, usize)>>();
+        forms.sort();
+        let mut forms = forms.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+        if forms.is_empty() {
+            forms.extend(find("msgstr"));
+        }
+        Ok(Columns {
+            context: find("msgctxt"),
+            id: find("msgid").ok_or_else(|| InvalidDocument("no msgid column".to_owned()))?,
+            plural: find("msgid_plural"),
+            forms,
+            fuzzy: find("fuzzy"),
+            comments: find("comments"),
+            extracted: find("extracted_comments"),
+        })
+    }
+
+    fn entry(&self, row: &[String]) -> PoEntry {
+        let cell = |i: usize| row.get(i).map_or("", String::as_str);
+        let optional = |i: Option<usize>| Some(cell(i?)).filter(|s| !s.is_empty());
+        let lines = |i: Option<usize>| {
+            optional(i).map_or_else(Vec::new, |s| s.split('\n').map(str::to_owned).collect())
+        };
+
+        let mut entry = PoEntry::new(cell(self.id));
+        entry.context = optional(self.context).map(str::to_owned);
+        entry.id_plural = optional(self.plural).map(str::to_owned);
+        let forms = if entry.id_plural.is_some() {
+            self.forms.len()
+        } else {
+            1
+        };
+        entry.translations = self.translations(row, forms);
+        let fuzzy = optional(self.fuzzy).map(|s| s.trim().to_ascii_lowercase());
+        if let Some("yes" | "y" | "true" | "1" | "x") = fuzzy.as_deref() {
+            entry.flags.push("fuzzy".to_owned());
+        }
+        entry.translator_comments = lines(self.comments);
+        entry.extracted_comments = lines(self.extracted);
+        entry
+    }
+
+    /// Returns the given number of translations of a row, empty for missing columns.
+    fn translations(&self, row: &[String], forms: usize) -> Vec<String> {
+        (0..forms)
+            .map(|form| {
+                self.forms
+                    .get(form)
+                    .and_then(|&i| row.get(i))
+                    .map_or_else(String::new, String::clone)
+            })
+            .collect()
+    }
+}
+
+/// Reads the rows of a spreadsheet, after the header row.
+fn read<R: Read>(mut reader: R, format: Format) -> Result<(Columns, Vec<Vec<String>>), Error> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    let contents = String::from_utf8(contents).map_err(|_| DecodingError)?;
+    let mut rows = format.parse_rows(contents.trim_start_matches('\u{feff}'))?;
+    if rows.is_empty() {
+        return Err(InvalidDocument("no header row".to_owned()));
+    }
+    let header = rows.remove(0);
+    Ok((Columns::new(&header)?, rows))
+}
+
+/// Parses a spreadsheet in the given format into a PO file.
+///
+/// A header with the UTF-8 charset is added if the spreadsheet has none,
+/// and plural entries get as many translations as the header's `Plural-Forms` tells.
+pub fn parse<R: Read>(reader: R, format: Format) -> Result<PoFile, Error> {
+    let (columns, rows) = read(reader, format)?;
+    let mut entries = rows
+        .iter()
+        .map(|row| columns.entry(row))
+        .collect::<Vec<_>>();
+    let nplurals = match entries.iter().find(|e| e.is_header()) {
+        Some(header) => {
+            let header = header.translations.first().map_or("", String::as_str);
+            parse_metadata(header)?.plural_forms().0
+        }
+        None => {
+            entries.insert(0, PoEntry::header_for(None));
+            None
+        }
+    };
+    if let Some(nplurals) = nplurals {
+        for entry in entries.iter_mut().filter(|e| e.id_plural.is_some()) {
+            entry.translations.resize(nplurals, String::new());
+        }
+    }
+    let mut po = PoFile::new();
+    po.entries = entries;
+    Ok(po)
+}
+
+/// Updates the translations of a PO file with the ones of a spreadsheet in the given format,
+/// e.g. one written by `write` and edited by a reviewer,
+/// and returns the number of entries which were changed.
+///
+/// Rows are matched to entries by context and original string,
+/// and the ones without a matching entry, as well as the header, are ignored.
+/// The translations, the fuzzy flag and the translator comments are only updated
+/// if the spreadsheet has columns for them: a sheet with a single `msgstr` column
+/// only replaces the first form. An entry keeps its number of plural forms.
+pub fn update<R: Read>(po: &mut PoFile, reader: R, format: Format) -> Result<usize, Error> {
+    let (columns, rows) = read(reader, format)?;
+    let mut changed = 0;
+    for cells in rows {
+        let row = columns.entry(&cells);
+        if row.is_header() {
+            continue;
+        }
+        let entry = po
+            .entries
+            .iter_mut()
+            .find(|e| !e.obsolete && e.context == row.context && e.id == row.id);
+        let entry = match entry {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let original = entry.clone();
+        if entry.translations.is_empty() && !columns.forms.is_empty() {
+            entry.translations.push(String::new());
+        }
+        // Only the forms with a column are replaced.
+        let forms = entry.translations.len().min(columns.forms.len());
+        let translations = columns.translations(&cells, forms);
+        entry.translations[..forms].clone_from_slice(&translations);
+        let fuzzy = row.has_flag("fuzzy");
+        if columns.fuzzy.is_some() && fuzzy != entry.has_flag("fuzzy") {
+            if fuzzy {
+                entry.flags.insert(0, "fuzzy".to_owned());
+            } else {
+                entry.flags.retain(|f| f != "fuzzy");
+            }
+        }
+        if columns.comments.is_some() {
+            entry.translator_comments = row.translator_comments;
+        }
+        if *entry != original {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"# Lithuanian translation
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);\n"
+
+# Check with "marketing",
+# please
+#. Shown in the toolbar
+#, fuzzy, c-format
+msgid "Open \"%s\", now"
+msgstr "Atverti „%s“,\tdabar\\"
+
+msgctxt "menu"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d failas"
+msgstr[1] "%d failai"
+msgstr[2] "%d failų"
+
+msgid "Line one\nline two"
+msgstr ""
+"#;
+
+    fn round_trip(format: Format) -> String {
+        let po = PoFile::parse(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&po, format, &mut output).unwrap();
+        let parsed = parse(&output[..], format).unwrap();
+        // Flags other than fuzzy and references are not kept.
+        let mut expected = po.clone();
+        expected.entries[1].flags = vec!["fuzzy".to_owned()];
+        assert_eq!(parsed, expected);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        let output = round_trip(Format::Csv);
+        let rows = output.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(
+            rows[0],
+            "msgctxt,msgid,msgid_plural,msgstr[0],msgstr[1],msgstr[2],fuzzy,comments,extracted_comments"
+        );
+        assert!(rows[1].starts_with(",,,\"Language: lt\nContent-Type:"));
+        assert_eq!(
+            rows[2..4],
+            [
+                ",\"Open \"\"%s\"\", now\",,\"Atverti „%s“,\tdabar\\\",,,yes,\"Check with \"\"marketing\"\",\nplease\",Shown in the toolbar",
+                "menu,%d file,%d files,%d failas,%d failai,%d failų,,,",
+            ]
+        );
+        assert_eq!(rows[4], ",\"Line one\nline two\",,,,,,,");
+    }
+
+    #[test]
+    fn test_tsv() {
+        let output = round_trip(Format::Tsv);
+        let rows = output.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(
+            rows[2],
+            "\tOpen \"%s\", now\t\tAtverti „%s“,\\tdabar\\\\\t\t\tyes\tCheck with \"marketing\",\\nplease\tShown in the toolbar"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let po = parse(
+            "\u{feff}msgstr,msgid,Notes\r\nAtverti,Open,ignored\r\n\r\n\"Ne\"\"\",No\n".as_bytes(),
+            Format::Csv,
+        )
+        .unwrap();
+        assert_eq!(po.entries.len(), 3);
+        assert!(po.entries[0].is_header());
+        assert_eq!(po.entries[1].id, "Open");
+        assert_eq!(po.entries[1].translations, ["Atverti"]);
+        assert_eq!(po.entries[2].id, "No");
+        assert_eq!(po.entries[2].translations, ["Ne\""]);
+
+        assert!(matches!(
+            parse(&b"msgid\n\"Open\n"[..], Format::Csv),
+            Err(InvalidDocument(ref e)) if e == "unterminated quoted field on line 2"
+        ));
+        assert!(matches!(
+            parse(&b"msgctxt,msgstr\n"[..], Format::Csv),
+            Err(InvalidDocument(_))
+        ));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut po = PoFile::parse(PO.as_bytes()).unwrap();
+        let sheet = "msgid\tmsgid_plural\tmsgctxt\tmsgstr[0]\tmsgstr[1]\tmsgstr[2]\tfuzzy\n\
+                     Open \"%s\", now\t\t\tAtverti „%s“, dabar\t\t\t\n\
+                     %d file\t%d files\tmenu\t%d failas\t%d failai\t%d failų\t\n\
+                     Line one\\nline two\t\t\tPirma eilutė\\nantra eilutė\t\t\tyes\n\
+                     Missing\t\t\tTrūksta\t\t\t\n";
+        assert_eq!(update(&mut po, sheet.as_bytes(), Format::Tsv).unwrap(), 2);
+
+        let entry = &po.entries[1];
+        assert_eq!(entry.translations, ["Atverti „%s“, dabar"]);
+        assert_eq!(entry.flags, ["c-format"]);
+        assert_eq!(entry.translator_comments.len(), 2);
+        assert_eq!(po.entries[2].translations.len(), 3);
+        assert_eq!(po.entries[3].translations, ["Pirma eilutė\nantra eilutė"]);
+        assert_eq!(po.entries[3].flags, ["fuzzy"]);
+        assert_eq!(po.entries.len(), 4);
+    }
+
+    #[test]
+    fn test_update_without_translations() {
+        let mut po = PoFile::parse(PO.as_bytes()).unwrap();
+        let sheet = "msgctxt,msgid,comments
+menu,%d file,Check the plural
+";
+        assert_eq!(update(&mut po, sheet.as_bytes(), Format::Csv).unwrap(), 1);
+        assert_eq!(
+            po.entries[2].translations,
+            ["%d failas", "%d failai", "%d failų"]
+        );
+        assert_eq!(po.entries[2].translator_comments, ["Check the plural"]);
+    }
+
+    #[test]
+    fn test_update_without_plural_column() {
+        let mut po = PoFile::parse(PO.as_bytes()).unwrap();
+        let sheet = "msgctxt,msgid,msgstr[0],msgstr[1],msgstr[2]
+menu,%d file,%d byla,%d bylos,%d bylų
+";
+        assert_eq!(update(&mut po, sheet.as_bytes(), Format::Csv).unwrap(), 1);
+        assert_eq!(
+            po.entries[2].translations,
+            ["%d byla", "%d bylos", "%d bylų"]
+        );
+
+        // A single translation column only holds the first form.
+        let sheet = "msgctxt,msgid,msgstr\nmenu,%d file,%d dokumentas\n";
+        assert_eq!(update(&mut po, sheet.as_bytes(), Format::Csv).unwrap(), 1);
+        assert_eq!(
+            po.entries[2].translations,
+            ["%d dokumentas", "%d bylos", "%d bylų"]
+        );
+    }
+
+    #[test]
+    fn test_update_keeps_flags() {
+        let source = "#, c-format, fuzzy\nmsgid \"Open %s\"\nmsgstr \"Atverti %s\"\n\n\
+                      msgid \"Close\"\nmsgstr \"\"\n";
+        let mut po = PoFile::parse(source.as_bytes()).unwrap();
+        let sheet = "msgid,msgstr,fuzzy\nOpen %s,Atverti %s,yes\nClose,Uždaryti,\n";
+        assert_eq!(update(&mut po, sheet.as_bytes(), Format::Csv).unwrap(), 1);
+        assert_eq!(po.entries[0].flags, ["c-format", "fuzzy"]);
+        let mut output = vec![];
+        po.write(&mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("#, c-format, fuzzy\n"));
+    }
+}
@@ -0,0 +1,295 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::{fmt, str};
+
+/// Decoder converts the strings of a catalog between its encoding and UTF-8.
+///
+/// It is implemented for the encodings of [`encoding_rs`](https://docs.rs/encoding_rs),
+/// which the charsets given in catalog headers are resolved to,
+/// and, with the `encoding` feature enabled, for the ones of the `encoding` crate.
+/// Without the `std` feature, only UTF-8 is supported out of the box.
+/// Other encodings can be supported by implementing it,
+/// see `ParseOptions::force_encoding`.
+///
+/// ```
+/// use gettext::{Decoder, ParseOptions};
+///
+/// /// Decodes every byte as the character with the same code point.
+/// struct Latin1;
+///
+/// impl Decoder for Latin1 {
+///     fn name(&self) -> &str {
+///         "ISO-8859-1"
+///     }
+///
+///     fn decode(&self, bytes: &[u8]) -> Option<String> {
+///         Some(bytes.iter().map(|&b| char::from(b)).collect())
+///     }
+///
+///     fn encode(&self, s: &str) -> Option<Vec<u8>> {
+///         s.chars().map(|c| if c <= '\u{ff}' { Some(c as u8) } else { None }).collect()
+///     }
+/// }
+///
+/// # #[cfg(feature = "std")] {
+/// let catalog = ParseOptions::new()
+///     .force_encoding(Latin1)
+///     .parse_po(&b"msgid \"Garlic\"\nmsgstr \"Ail r\xf4ti\"\n"[..])
+///     .unwrap();
+/// assert_eq!(catalog.gettext("Garlic"), "Ail rôti");
+/// # }
+/// ```
+pub trait Decoder: Send + Sync {
+    /// Returns the name of the encoding, as written in the `Content-Type` header.
+    fn name(&self) -> &str;
+
+    /// Decodes the bytes, or returns `None` if they are not valid in this encoding.
+    fn decode(&self, bytes: &[u8]) -> Option<String>;
+
+    /// Encodes the string, or returns `None` if it has characters
+    /// this encoding can not represent.
+    ///
+    /// It is used for writing out modified PO files in their original encoding,
+    /// which fails if it is not implemented; the default implementation never succeeds.
+    fn encode(&self, s: &str) -> Option<Vec<u8>> {
+        let _ = s;
+        None
+    }
+}
+
+/// The UTF-8 encoding, which needs no tables.
+struct Utf8;
+
+impl Decoder for Utf8 {
+    fn name(&self) -> &str {
+        "UTF-8"
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        str::from_utf8(bytes).ok().map(str::to_owned)
+    }
+
+    fn encode(&self, s: &str) -> Option<Vec<u8>> {
+        Some(s.as_bytes().to_vec())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decoder for &'static encoding_rs::Encoding {
+    fn name(&self) -> &str {
+        encoding_rs::Encoding::name(self)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        self.decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|s| s.into_owned())
+    }
+
+    fn encode(&self, s: &str) -> Option<Vec<u8>> {
+        // UTF-16 and the replacement encoding can not be encoded to by `encoding_rs`.
+        if self.output_encoding() != *self {
+            return None;
+        }
+        let (bytes, _, unmappable) = encoding_rs::Encoding::encode(self, s);
+        if unmappable {
+            None
+        } else {
+            Some(bytes.into_owned())
+        }
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl Decoder for encoding::types::EncodingRef {
+    fn name(&self) -> &str {
+        encoding::types::Encoding::name(*self)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        encoding::types::Encoding::decode(*self, bytes, encoding::DecoderTrap::Strict).ok()
+    }
+
+    fn encode(&self, s: &str) -> Option<Vec<u8>> {
+        encoding::types::Encoding::encode(*self, s, encoding::EncoderTrap::Strict).ok()
+    }
+}
+
+impl<D: Decoder + ?Sized> Decoder for Arc<D> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        (**self).decode(bytes)
+    }
+
+    fn encode(&self, s: &str) -> Option<Vec<u8>> {
+        (**self).encode(s)
+    }
+}
+
+impl dyn Decoder {
+    /// Returns the decoder of the encoding with the given label,
+    /// resolved the same way as the charset in the header of a catalog:
+    /// case-insensitively, regardless of using `_` or `-`,
+    /// with ASCII read as UTF-8 and with the names used by GNU gettext,
+    /// e.g. `eucJP` or `ANSI_X3.4-1968`, besides the labels of the WHATWG Encoding Standard.
+    ///
+    /// ```
+    /// use gettext::Decoder;
+    ///
+    /// assert_eq!(<dyn Decoder>::for_label("cp1257").unwrap().name(), "windows-1257");
+    /// assert_eq!(<dyn Decoder>::for_label("ANSI_X3.4-1968").unwrap().name(), "UTF-8");
+    /// assert!(<dyn Decoder>::for_label("x-klingon").is_none());
+    /// ```
+    ///
+    /// Without the `std` feature, only UTF-8 and ASCII are known.
+    pub fn for_label(label: &str) -> Option<Arc<dyn Decoder>> {
+        for_label(label)
+    }
+
+    /// Returns whether the label names ASCII, which `for_label` resolves to UTF-8,
+    /// e.g. for writing only the strings which ASCII can represent.
+    ///
+    /// ```
+    /// use gettext::Decoder;
+    ///
+    /// assert!(<dyn Decoder>::is_ascii_label("ANSI_X3.4-1968"));
+    /// assert!(!<dyn Decoder>::is_ascii_label("UTF-8"));
+    /// ```
+    pub fn is_ascii_label(label: &str) -> bool {
+        ASCII.contains(&normalize(label).as_str())
+    }
+}
+
+impl fmt::Debug for dyn Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Decoder({})", self.name())
+    }
+}
+
+/// Returns the decoder of UTF-8, the encoding used unless a catalog specifies another one.
+pub(crate) fn utf8() -> Arc<dyn Decoder> {
+    Arc::new(Utf8)
+}
+
+/// Names of encodings used by GNU gettext and other tools
+/// which are not labels of the WHATWG Encoding Standard, mapped to the labels
+/// of the same or a compatible encoding.
+/// The Standard decodes Big5-HKSCS as Big5, GBK as GB18030
+/// and Windows-31J as Shift_JIS.
+#[cfg(feature = "std")]
+static ALIASES: &[(&str, &str)] = &[
+    ("big5hkscs", "big5-hkscs"),
+    ("cp932", "shift_jis"),
+    ("cp936", "gbk"),
+    ("cp949", "euc-kr"),
+    ("cp950", "big5"),
+    ("eucjp", "euc-jp"),
+    ("euckr", "euc-kr"),
+    ("gb-18030", "gb18030"),
+    ("koi8r", "koi8-r"),
+    ("koi8u", "koi8-u"),
+    ("ms932", "shift_jis"),
+    ("sjis", "shift_jis"),
+    ("uhc", "euc-kr"),
+    ("ujis", "euc-jp"),
+];
+
+/// Names of ASCII, which is decoded as its superset UTF-8
+/// rather than as windows-1252 like the Standard does.
+/// `CHARSET` is the placeholder of templates which a translator replaces
+/// with the actual charset; GNU gettext treats it as ASCII.
+static ASCII: &[&str] = &["ascii", "us-ascii", "ansi-x3.4-1968", "charset"];
+
+/// Labels of UTF-8, as listed by the WHATWG Encoding Standard.
+static UTF8: &[&str] = &[
+    "utf-8",
+    "utf8",
+    "unicode-1-1-utf-8",
+    "unicode11utf8",
+    "unicode20utf8",
+    "x-unicode20utf8",
+];
+
+/// Returns the decoder of the encoding with the given label, e.g. `windows-1257`,
+/// matched case-insensitively and regardless of using `_` or `-`.
+pub(crate) fn for_label(label: &str) -> Option<Arc<dyn Decoder>> {
+    let label = normalize(label);
+    if UTF8.contains(&label.as_str()) || ASCII.contains(&label.as_str()) {
+        Some(utf8())
+    } else {
+        legacy_for_label(&label)
+    }
+}
+
+/// Returns the label in lowercase, with `-` in place of `_`.
+fn normalize(label: &str) -> String {
+    label.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Returns the decoder of a legacy encoding with the given normalized label.
+#[cfg(feature = "std")]
+fn legacy_for_label(label: &str) -> Option<Arc<dyn Decoder>> {
+    let label = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == label)
+        .map_or(label, |(_, l)| l);
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+        .or_else(|| encoding_rs::Encoding::for_label(label.replace('-', "_").as_bytes()))?;
+    Some(Arc::new(encoding))
+}
+
+/// Legacy encodings need the tables of `encoding_rs`.
+#[cfg(not(feature = "std"))]
+fn legacy_for_label(_: &str) -> Option<Arc<dyn Decoder>> {
+    None
+}
+
+/// Returns whether the decoder is the one of UTF-8.
+#[cfg(feature = "std")]
+pub(crate) fn is_utf8(decoder: &dyn Decoder) -> bool {
+    decoder.name().eq_ignore_ascii_case("utf-8")
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_rs() {
+        let decoder = for_label("cp1257").unwrap();
+        assert_eq!(decoder.name(), "windows-1257");
+        assert_eq!(decoder.decode(b"\xc8esnakas").unwrap(), "Česnakas");
+        assert_eq!(decoder.encode("Česnakas").unwrap(), b"\xc8esnakas");
+        assert_eq!(decoder.encode("Чеснок"), None);
+        assert_eq!(utf8().decode(b"\xff"), None);
+        assert!(is_utf8(&*utf8()));
+        assert!(for_label("klingon").is_none());
+        assert_eq!(for_label("utf-16le").unwrap().encode("a"), None);
+    }
+
+    #[test]
+    fn test_for_label() {
+        let name = |label| for_label(label).map(|d| d.name().to_owned());
+        assert_eq!(name("UTF-8").unwrap(), "UTF-8");
+        assert_eq!(name(" utf8 ").unwrap(), "UTF-8");
+        assert_eq!(name("CHARSET").unwrap(), "UTF-8");
+        assert_eq!(name("ASCII").unwrap(), "UTF-8");
+        assert_eq!(name("ANSI_X3.4-1968").unwrap(), "UTF-8");
+        assert_eq!(name("ISO-8859-13").unwrap(), "ISO-8859-13");
+        assert_eq!(name("EUC-JP").unwrap(), "EUC-JP");
+        assert_eq!(name("eucJP").unwrap(), "EUC-JP");
+        assert_eq!(name("EUC_KR").unwrap(), "EUC-KR");
+        assert_eq!(name("BIG5-HKSCS").unwrap(), "Big5");
+        assert_eq!(name("BIG5HKSCS").unwrap(), "Big5");
+        assert_eq!(name("GB18030").unwrap(), "gb18030");
+        assert_eq!(name("GBK").unwrap(), "GBK");
+        assert_eq!(name("SHIFT_JIS").unwrap(), "Shift_JIS");
+        assert_eq!(name("CP932").unwrap(), "Shift_JIS");
+        assert_eq!(name("KOI8-R").unwrap(), "KOI8-R");
+        assert_eq!(name("x-klingon"), None);
+    }
+}
@@ -1,9 +1,11 @@
-use std::borrow::Cow;
-use std::error;
-use std::fmt;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::error;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::io;
 
-/// Represents an error encountered while parsing an MO file.
+/// Represents an error encountered while parsing an MO or PO file.
 #[derive(Debug)]
 pub enum Error {
     /// An incorrect magic number has been encountered
@@ -13,6 +15,7 @@ pub enum Error {
     /// An unexpected EOF occured
     Eof,
     /// An I/O error occured
+    #[cfg(feature = "std")]
     Io(io::Error),
     /// Incorrect syntax encountered while parsing the meta information
     MalformedMetadata,
@@ -20,14 +23,31 @@ pub enum Error {
     MisplacedMetadata,
     /// Invalid Plural-Forms metadata
     PluralParsing,
+    /// Incorrect syntax encountered on the given line of a PO file
+    PoSyntax(usize),
     /// An unknown encoding was specified in the metadata
     UnknownEncoding,
+    /// Incorrect XML syntax encountered on the given line of an XML based document,
+    /// e.g. an XLIFF file
+    XmlSyntax(usize),
+    /// A document in another format does not have the expected structure
+    InvalidDocument(String),
+    /// A character can not be represented in the encoding a catalog is written in
+    UnrepresentableCharacter {
+        /// The character.
+        character: char,
+        /// Where the character is, e.g. `msgid "Garlic" (src/main.rs:42)`.
+        location: String,
+        /// The name of the encoding.
+        encoding: String,
+    },
 }
 use self::Error::*;
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
+            #[cfg(feature = "std")]
             Io(ref err) => Some(err),
             _ => None,
         }
@@ -40,15 +60,29 @@ impl fmt::Display for Error {
             BadMagic => write!(fmt, "bad magic number"),
             DecodingError => write!(fmt, "invalid byte sequence in a string"),
             Eof => write!(fmt, "unxpected end of file"),
+            #[cfg(feature = "std")]
             Io(ref err) => err.fmt(fmt),
             MalformedMetadata => write!(fmt, "metadata syntax error"),
             MisplacedMetadata => write!(fmt, "misplaced metadata"),
             UnknownEncoding => write!(fmt, "unknown encoding specified"),
             PluralParsing => write!(fmt, "invalid plural expression"),
+            PoSyntax(line) => write!(fmt, "PO syntax error on line {}", line),
+            XmlSyntax(line) => write!(fmt, "XML syntax error on line {}", line),
+            InvalidDocument(ref reason) => write!(fmt, "invalid document: {}", reason),
+            UnrepresentableCharacter {
+                character,
+                ref location,
+                ref encoding,
+            } => write!(
+                fmt,
+                "{:?} in {} can not be represented in {}",
+                character, location, encoding
+            ),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for Error {
     fn from(inner: io::Error) -> Error {
         Io(inner)
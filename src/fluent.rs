@@ -0,0 +1,540 @@
+//! Conversion between catalogs and [Fluent](https://projectfluent.org/) (`.ftl`) resources,
+//! for migrating translations to Fluent one message at a time.
+//!
+//! A message is written with an identifier made of its context and original string,
+//! see `message_id`. Messages with a plural form become select expressions
+//! on the `$count` variable, with a variant for the CLDR category of each plural form
+//! derived from the catalog's plural formula and language, and `other` as the default.
+//!
+//! When parsing, only simple messages are converted: messages whose value is text,
+//! or a single select expression whose variants are text, are kept
+//! and other messages, terms and attributes are left out.
+//! Like Android resources, see the `android` module, the messages are keyed by their
+//! identifier, or, if the resource of the source language is given, have the identifier
+//! as their context and the source value as their original string.
+//!
+//! Placeholders are not converted in either direction,
+//! and placeables other than string literals are kept as they are written.
+//!
+//! ```
+//! use gettext::fluent;
+//!
+//! let catalog = fluent::parse(&b"open-file = Atverti\n"[..], "lt").unwrap();
+//! assert_eq!(catalog.gettext("open-file"), "Atverti");
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
+
+use crate::android::{build_catalog, Resource};
+use crate::Error::{self, DecodingError, InvalidDocument};
+use crate::{cldr, Catalog};
+
+/// Returns the Fluent identifier of the message with the given context and original string:
+/// the lowercased ASCII letters and digits of both, with the other characters replaced by `-`,
+/// e.g. `menu-open-file` for `Open file…` in the `menu` context.
+///
+/// When writing a catalog, a number is appended to identifiers used by more than one message,
+/// as in `open-file-2`.
+pub fn message_id(context: Option<&str>, id: &str) -> String {
+    let mut message_id = String::new();
+    for c in context
+        .into_iter()
+        .chain(Some(id))
+        .flat_map(|s| s.chars().chain(Some('-')))
+    {
+        if c.is_ascii_alphanumeric() {
+            message_id.push(c.to_ascii_lowercase());
+        } else if !message_id.is_empty() && !message_id.ends_with('-') {
+            message_id.push('-');
+        }
+    }
+    let message_id = message_id.trim_end_matches('-');
+    match message_id.chars().next() {
+        None => "message".to_owned(),
+        Some(c) if c.is_ascii_digit() => format!("m-{}", message_id),
+        Some(_) => message_id.to_owned(),
+    }
+}
+
+/// Writes a line of a pattern, escaping what Fluent would otherwise interpret.
+fn escape_line(out: &mut String, line: &str, block: bool) {
+    if line.is_empty() {
+        out.push_str("{\"\"}");
+        return;
+    }
+    let content = line.trim_matches(' ');
+    let leading = &line[..line.len() - line.trim_start_matches(' ').len()];
+    let trailing = &line[leading.len() + content.len()..];
+    if !leading.is_empty() {
+        out.push_str(&format!("{{\"{}\"}}", leading));
+    }
+    for (i, c) in content.chars().enumerate() {
+        match c {
+            '{' | '}' => out.push_str(&format!("{{\"{}\"}}", c)),
+            '[' | '*' | '.' if i == 0 && block && leading.is_empty() => {
+                out.push_str(&format!("{{\"{}\"}}", c))
+            }
+            c => out.push(c),
+        }
+    }
+    if !trailing.is_empty() {
+        out.push_str(&format!("{{\"{}\"}}", trailing));
+    }
+}
+
+/// Writes a pattern on the same line if it has one line, or else as an indented block.
+fn write_pattern(out: &mut String, text: &str, indent: usize) {
+    if !text.contains('\n') {
+        out.push(' ');
+        escape_line(out, text, false);
+        return;
+    }
+    for line in text.split('\n') {
+        out.push('\n');
+        out.push_str(&" ".repeat(indent));
+        escape_line(out, line, true);
+    }
+}
+
+/// Writes the translations of the catalog as a Fluent resource.
+///
+/// Untranslated messages are left out, and the messages are sorted by context and original string.
+pub fn write<W: Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let categories = catalog.plural_categories();
+    let mut messages = catalog
+        .messages()
+        .filter(|m| !m.id.is_empty() || m.context.is_some())
+        .filter(|m| m.translated.iter().any(|t| !t.is_empty()))
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+
+    let mut ids = HashSet::new();
+    let mut out = String::new();
+    for message in messages {
+        let base = message_id(message.context.as_deref(), &message.id);
+        let mut id = base.clone();
+        let mut n = 1;
+        while !ids.insert(id.clone()) {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+
+        out.push_str(&id);
+        out.push_str(" =");
+        if message.plural.is_none() {
+            write_pattern(&mut out, &message.translated[0], 4);
+        } else {
+            out.push_str("\n    { $count ->");
+            for (category, translated) in cldr::variants(&message.translated, &categories) {
+                let marker = if category == "other" { "   *" } else { "    " };
+                out.push_str(&format!("\n    {}[{}]", marker, category));
+                write_pattern(&mut out, translated, 12);
+            }
+            out.push_str("\n    }");
+        }
+        out.push('\n');
+    }
+    writer.write_all(out.as_bytes())?;
+    Ok(())
+}
+
+/// A part of a pattern.
+enum Element {
+    Text(String),
+    Select(Vec<(String, String)>),
+}
+
+/// Returns the position of the `}` closing the placeable starting at `start`.
+fn placeable_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut previous = None;
+    let mut chars = text[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + i);
+                }
+            }
+            // String literals are placeables of their own; other quotes are text.
+            '"' if previous == Some('{') => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+        if !c.is_whitespace() {
+            previous = Some(c);
+        }
+    }
+    None
+}
+
+/// Returns the depth of nested placeables at the end of the line,
+/// given the depth at its start.
+fn brace_depth(line: &str, mut depth: usize) -> usize {
+    let mut in_string = false;
+    let mut previous = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' if in_string => in_string = false,
+            '"' if previous == Some('{') => in_string = true,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        if !c.is_whitespace() {
+            previous = Some(c);
+        }
+    }
+    depth
+}
+
+/// Unescapes a string literal, without its quotes.
+fn unescape_literal(s: &str) -> Option<String> {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                c @ ('u' | 'U') => {
+                    let len = if c == 'u' { 4 } else { 6 };
+                    let hex = chars.by_ref().take(len).collect::<String>();
+                    unescaped.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                c => unescaped.push(c),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+/// Parses the variants of a select expression, after the `->`.
+fn parse_variants(s: &str) -> Option<Vec<(String, String)>> {
+    let mut variants: Vec<(String, String)> = vec![];
+    for line in s.lines() {
+        let line = line.trim_start();
+        let key_line = line.strip_prefix('*').unwrap_or(line);
+        if let Some(rest) = key_line.strip_prefix('[') {
+            let (key, value) = rest.split_once(']')?;
+            variants.push((key.trim().to_owned(), value.trim_start().to_owned()));
+        } else if let Some((_, value)) = variants.last_mut() {
+            if !value.is_empty() || !line.is_empty() {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line);
+            }
+        } else if !line.is_empty() {
+            return None;
+        }
+    }
+    variants
+        .into_iter()
+        .map(|(key, value)| match parse_pattern(value.trim_end())? {
+            Resource::String(value) => Some((key, value)),
+            Resource::Plurals(_) => None,
+        })
+        .collect()
+}
+
+/// Parses a dedented pattern, if it is a simple one.
+fn parse_pattern(text: &str) -> Option<Resource> {
+    let mut elements = vec![];
+    let mut text_element = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        text_element.push_str(&rest[..start]);
+        let end = placeable_end(rest, start)?;
+        let placeable = &rest[start..=end];
+        let content = placeable[1..placeable.len() - 1].trim();
+        if content.len() >= 2 && content.starts_with('"') && content.ends_with('"') {
+            text_element.push_str(&unescape_literal(&content[1..content.len() - 1])?);
+        } else if let Some((_, variants)) = content.split_once("->") {
+            if !text_element.is_empty() {
+                elements.push(Element::Text(std::mem::take(&mut text_element)));
+            }
+            elements.push(Element::Select(parse_variants(variants)?));
+        } else {
+            text_element.push_str(placeable);
+        }
+        rest = &rest[end + 1..];
+    }
+    text_element.push_str(rest);
+    if !text_element.is_empty() {
+        elements.push(Element::Text(text_element));
+    }
+
+    match elements.len() {
+        0 => Some(Resource::String(String::new())),
+        1 => Some(match elements.pop()? {
+            Element::Text(text) => Resource::String(text),
+            Element::Select(variants) => Resource::Plurals(variants),
+        }),
+        _ => None,
+    }
+}
+
+fn read_messages<R: Read>(mut reader: R) -> Result<Vec<(String, Resource)>, Error> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    let contents = String::from_utf8(contents).map_err(|_| DecodingError)?;
+    let lines = contents
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .collect::<Vec<_>>();
+
+    let mut messages = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        // Comments, terms and the lines of skipped entries.
+        if !line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let (id, value) = line
+            .split_once('=')
+            .ok_or_else(|| InvalidDocument(format!("expected `=` on line {}", i)))?;
+        let id = id.trim_end();
+        if !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(InvalidDocument(format!(
+                "invalid identifier `{}` on line {}",
+                id, i
+            )));
+        }
+
+        // The lines of the value are indented, except within placeables.
+        let mut block = vec![];
+        let mut depth = brace_depth(value, 0);
+        while i < lines.len()
+            && (depth > 0 || lines[i].trim().is_empty() || lines[i].starts_with(' '))
+        {
+            if depth == 0 && lines[i].trim_start().starts_with('.') {
+                break;
+            }
+            block.push((depth, lines[i]));
+            depth = brace_depth(lines[i], depth);
+            i += 1;
+        }
+        while i < lines.len() && (lines[i].trim().is_empty() || lines[i].starts_with(' ')) {
+            // The attributes.
+            i += 1;
+        }
+        let indent = block
+            .iter()
+            .filter(|(depth, l)| *depth == 0 && !l.trim().is_empty())
+            .map(|(_, l)| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let mut text = value.trim_start().to_owned();
+        for (_, line) in block {
+            let line = line.get(indent..).unwrap_or_default();
+            if !text.is_empty() || !line.trim().is_empty() {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(line);
+            }
+        }
+        let text = text.trim_end();
+        if text.is_empty() {
+            // A message with only attributes.
+            continue;
+        }
+        if let Some(resource) = parse_pattern(text) {
+            messages.push((id.to_owned(), resource));
+        }
+    }
+    Ok(messages)
+}
+
+/// Parses a Fluent resource of the given language, e.g. `lt` or `pt-BR`,
+/// into a catalog with the message identifiers as the original strings.
+///
+/// The plural formula is the one of the language, if it is known,
+/// and the forms of plural messages are taken from the variants of their category,
+/// falling back to `other`. The plural original string is the identifier as well.
+pub fn parse<R: Read>(reader: R, language: &str) -> Result<Catalog, Error> {
+    build_catalog(read_messages(reader)?, None, language)
+}
+
+/// Parses a Fluent resource of the given language into a catalog,
+/// with the message identifiers as the contexts and the values of the messages
+/// of the resource of the source language as the original strings.
+///
+/// The original plural strings are the `other` variants of the source messages,
+/// and the singular ones the `one` variants, if any.
+/// Messages missing from the source are keyed by their identifier, like `parse` does.
+pub fn parse_with_source<R: Read, S: Read>(
+    reader: R,
+    source: S,
+    language: &str,
+) -> Result<Catalog, Error> {
+    let source: BTreeMap<_, _> = read_messages(source)?.into_iter().collect();
+    build_catalog(read_messages(reader)?, Some(&source), language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);\n"
+
+msgid "Open {file}…"
+msgstr "Atverti {file}…"
+
+msgctxt "menu"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d failas"
+msgstr[1] "%d failai"
+msgstr[2] "%d failų"
+
+msgid "Notes"
+msgstr "  Pastabos:\n* pirma\n\n"
+
+msgid "notes"
+msgstr "pastabos"
+"#;
+
+    #[test]
+    fn test_message_id() {
+        assert_eq!(message_id(None, "Open file…"), "open-file");
+        assert_eq!(message_id(Some("menu"), "Open file…"), "menu-open-file");
+        assert_eq!(message_id(None, "404: not found"), "m-404-not-found");
+        assert_eq!(message_id(None, "…"), "message");
+    }
+
+    #[test]
+    fn test_write() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"notes =
+    {"  "}Pastabos:
+    {"*"} pirma
+    {""}
+    {""}
+open-file = Atverti {"{"}file{"}"}…
+notes-2 = pastabos
+menu-d-file =
+    { $count ->
+        [one] %d failas
+        [few] %d failai
+       *[other] %d failų
+    }
+"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+
+        let parsed = parse(&output[..], "lt").unwrap();
+        assert_eq!(parsed.gettext("notes"), "  Pastabos:\n* pirma\n\n");
+        assert_eq!(parsed.gettext("notes-2"), "pastabos");
+        assert_eq!(parsed.gettext("open-file"), "Atverti {file}…");
+        for n in 0..30 {
+            assert_eq!(
+                parsed.ngettext("menu-d-file", "menu-d-file", n),
+                catalog.npgettext("menu", "%d file", "%d files", n)
+            );
+        }
+
+        let source = r#"
+open-file = Open {"{"}file{"}"}…
+menu-d-file = { $count ->
+    [one] %d file
+   *[other] %d files
+}
+"#;
+        let parsed = parse_with_source(&output[..], source.as_bytes(), "lt").unwrap();
+        assert_eq!(
+            parsed.pgettext("open-file", "Open {file}…"),
+            "Atverti {file}…"
+        );
+        assert_eq!(parsed.gettext("notes-2"), "pastabos");
+        for n in 0..30 {
+            assert_eq!(
+                parsed.npgettext("menu-d-file", "%d file", "%d files", n),
+                catalog.npgettext("menu", "%d file", "%d files", n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let catalog = parse(
+            r#"### Resource comment
+
+# Message comment
+hello = Hello, { $name }!
+    How are you?
+    .title = Greeting
+-brand = Firefox
+about = About { -brand }
+unread =
+    You have { $count ->
+        [one] one message
+       *[other] { $count } messages
+    }
+emails = { $count ->
+    [one] One "email"
+    [few]
+        A few
+        emails
+   *[other] { $count } emails
+}
+only-attributes =
+    .label = Label
+escaped = {"A"} \ {"\"quoted\""}
+"#
+            .as_bytes(),
+            "ru",
+        )
+        .unwrap();
+        assert_eq!(catalog.gettext("hello"), "Hello, { $name }!\nHow are you?");
+        assert_eq!(catalog.gettext("about"), "About { -brand }");
+        assert_eq!(catalog.gettext("unread"), "unread");
+        assert_eq!(catalog.ngettext("emails", "emails", 1), "One \"email\"");
+        assert_eq!(catalog.ngettext("emails", "emails", 3), "A few\nemails");
+        assert_eq!(catalog.ngettext("emails", "emails", 5), "{ $count } emails");
+        assert_eq!(catalog.gettext("only-attributes"), "only-attributes");
+        assert_eq!(catalog.gettext("escaped"), "A \\ \"quoted\"");
+        assert_eq!(catalog.gettext("-brand"), "-brand");
+
+        assert!(matches!(
+            parse(&b"hello\n"[..], "en"),
+            Err(InvalidDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+//! Substitution of arguments into translated strings.
+//!
+//! Two placeholder syntaxes are understood, and may be mixed:
+//!
+//! * Rust-style `{}` (the next positional argument), `{0}` (the first one)
+//!   and `{name}` (a named argument), with `{{` and `}}` standing for literal braces;
+//! * C-style `%s` (the next positional argument) and `%1$s` (the first one),
+//!   with `%%` standing for a literal percent sign.
+//!   The conversion, flags and width of C directives are ignored,
+//!   every argument is formatted using its `Display` implementation.
+//!
+//! Only these forms of Rust-style placeholders are recognised: format specs,
+//! e.g. `{0:>5}` or `{:?}`, are not supported, and such placeholders are left
+//! in the output as literal text, as is any other text in braces.
+//!
+//! Positional placeholders let translators reorder the arguments,
+//! e.g. `"{0} of {1}"` may be translated as `"{1}: {0}"`.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Write};
+
+/// FormatArgs holds the arguments to substitute into a string.
+///
+/// ```
+/// use gettext::format::{format, FormatArgs};
+///
+/// let count = 3;
+/// let args = FormatArgs::new().arg(&count).named("name", &"Alice");
+/// assert_eq!(format("{name} has {} files", &args), "Alice has 3 files");
+/// assert_eq!(format("%2$s: %1$d", &args), "%2$s: 3");
+/// ```
+#[derive(Default)]
+pub struct FormatArgs<'a> {
+    positional: Vec<&'a dyn Display>,
+    named: Vec<(&'a str, &'a dyn Display)>,
+}
+
+impl<'a> FormatArgs<'a> {
+    /// Creates an empty set of arguments.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the next positional argument.
+    pub fn arg(mut self, value: &'a dyn Display) -> Self {
+        self.positional.push(value);
+        self
+    }
+
+    /// Adds a named argument.
+    pub fn named(mut self, name: &'a str, value: &'a dyn Display) -> Self {
+        self.named.push((name, value));
+        self
+    }
+
+    fn get(&self, key: &Key) -> Option<&'a dyn Display> {
+        match *key {
+            Key::Index(i) => self.positional.get(i).cloned(),
+            Key::Name(name) => self
+                .named
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| *value),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for FormatArgs<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let positional = self
+            .positional
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        let named = self
+            .named
+            .iter()
+            .map(|(n, v)| (*n, v.to_string()))
+            .collect::<Vec<_>>();
+        f.debug_struct("FormatArgs")
+            .field("positional", &positional)
+            .field("named", &named)
+            .finish()
+    }
+}
+
+/// What a placeholder refers to.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key<'a> {
+    /// A positional argument, counted from 0.
+    Index(usize),
+    /// A named argument.
+    Name(&'a str),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Piece<'a> {
+    Text(&'a str),
+    Placeholder(Key<'a>, &'a str),
+}
+
+/// Splits a string into literal text and placeholders.
+pub(crate) fn parse(s: &str) -> Vec<Piece<'_>> {
+    let mut pieces = vec![];
+    let mut next_index = 0;
+    let mut text_start = 0;
+    let mut i = 0;
+    let bytes = s.as_bytes();
+    while i < bytes.len() {
+        let found = match bytes[i] {
+            b'{' | b'}' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                pieces.push(Piece::Text(&s[text_start..=i]));
+                i += 2;
+                text_start = i;
+                continue;
+            }
+            b'{' => parse_brace(&s[i..], &mut next_index),
+            b'%' if bytes.get(i + 1) == Some(&b'%') => {
+                pieces.push(Piece::Text(&s[text_start..=i]));
+                i += 2;
+                text_start = i;
+                continue;
+            }
+            b'%' => parse_percent(&s[i..], &mut next_index),
+            _ => None,
+        };
+        match found {
+            Some((key, len)) => {
+                if text_start < i {
+                    pieces.push(Piece::Text(&s[text_start..i]));
+                }
+                pieces.push(Piece::Placeholder(key, &s[i..i + len]));
+                i += len;
+                text_start = i;
+            }
+            None => i += 1,
+        }
+    }
+    if text_start < s.len() {
+        pieces.push(Piece::Text(&s[text_start..]));
+    }
+    pieces
+}
+
+/// Parses a `{}`, `{0}` or `{name}` placeholder at the start of `s`.
+fn parse_brace<'a>(s: &'a str, next_index: &mut usize) -> Option<(Key<'a>, usize)> {
+    let end = s.find('}')?;
+    let inner = &s[1..end];
+    let key = if inner.is_empty() {
+        *next_index += 1;
+        Key::Index(*next_index - 1)
+    } else if inner.bytes().all(|b| b.is_ascii_digit()) {
+        Key::Index(inner.parse().ok()?)
+    } else if is_identifier(inner) {
+        Key::Name(inner)
+    } else {
+        return None;
+    };
+    Some((key, end + 1))
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parses a C directive like `%s`, `%-5d` or `%2$s` at the start of `s`.
+fn parse_percent<'a>(s: &'a str, next_index: &mut usize) -> Option<(Key<'a>, usize)> {
+    let directive = RUNTIME.parse(&s[1..])?;
+    let key = match directive.position {
+        Some(p) => Key::Index(p),
+        None => {
+            *next_index += 1;
+            Key::Index(*next_index - 1)
+        }
+    };
+    Some((key, directive.len + 1))
+}
+
+/// The characters `%` directives are made of, which differ between their users.
+pub(crate) struct PercentSyntax {
+    /// Whether an argument position may be given, e.g. `2$` in `%2$s`.
+    positions: bool,
+    /// The flags, e.g. `-` in `%-5d`.
+    flags: &'static [u8],
+    /// The conversions, e.g. `d` in `%-5d`.
+    conversions: &'static [u8],
+}
+
+/// The conversions of C directives.
+const C_CONVERSIONS: &[u8] = b"diouxXeEfFgGaAcsp";
+
+/// The C directives substituted by `format`.
+/// The space and `'` flags are left out, so that e.g. `100% done` stays literal.
+pub(crate) const RUNTIME: PercentSyntax = PercentSyntax {
+    positions: true,
+    flags: b"-+0#",
+    conversions: C_CONVERSIONS,
+};
+
+/// The C directives as `printf` reads them, which `c-format` messages are checked for.
+#[cfg(feature = "std")]
+pub(crate) const PRINTF: PercentSyntax = PercentSyntax {
+    positions: true,
+    flags: b"-+0# '",
+    conversions: C_CONVERSIONS,
+};
+
+/// The `%` directives of Python, after the mapping key, e.g. `(name)` in `%(name)s`.
+#[cfg(feature = "std")]
+pub(crate) const PYTHON: PercentSyntax = PercentSyntax {
+    positions: false,
+    flags: b"-+0# ",
+    conversions: b"diouxXeEfFgGcrsa",
+};
+
+/// A `%` directive, as parsed by `PercentSyntax::parse`.
+pub(crate) struct PercentDirective<'a> {
+    /// The argument position given as e.g. `2$`, counted from 0.
+    pub(crate) position: Option<usize>,
+    /// The length modifier and the conversion, e.g. `ld` for `%-5ld`.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) conversion: &'a str,
+    /// The length of the directive, not counting its `%`.
+    pub(crate) len: usize,
+}
+
+impl PercentSyntax {
+    /// Parses the directive `s` starts with, following its `%`.
+    /// `%%` is not a directive.
+    pub(crate) fn parse<'a>(&self, s: &'a str) -> Option<PercentDirective<'a>> {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        let digits = |i: usize| bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+
+        // An argument position, e.g. `2$`.
+        let n = digits(i);
+        let position = if self.positions && n > 0 && bytes.get(i + n) == Some(&b'$') {
+            let position = s[i..i + n].parse::<usize>().ok().filter(|p| *p > 0)?;
+            i += n + 1;
+            Some(position - 1)
+        } else {
+            None
+        };
+        while bytes.get(i).is_some_and(|b| self.flags.contains(b)) {
+            i += 1;
+        }
+        i += digits(i);
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            i += digits(i);
+        }
+        let modifier = i;
+        while bytes.get(i).is_some_and(|b| b"hlLqjzt".contains(b)) {
+            i += 1;
+        }
+        if !bytes.get(i).is_some_and(|b| self.conversions.contains(b)) {
+            return None;
+        }
+        Some(PercentDirective {
+            position,
+            conversion: &s[modifier..=i],
+            len: i + 1,
+        })
+    }
+}
+
+/// Returns the distinct placeholders, sorted.
+pub(crate) fn keys<'a>(pieces: &[Piece<'a>]) -> Vec<Key<'a>> {
+    let mut keys = pieces
+        .iter()
+        .filter_map(|p| match *p {
+            Piece::Placeholder(key, _) => Some(key),
+            Piece::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn substitute(pieces: &[Piece<'_>], args: &FormatArgs) -> String {
+    let mut result = String::new();
+    for piece in pieces {
+        match *piece {
+            Piece::Text(text) => result.push_str(text),
+            Piece::Placeholder(key, raw) => match args.get(&key) {
+                Some(value) => write!(result, "{}", value).unwrap(),
+                None => result.push_str(raw),
+            },
+        }
+    }
+    result
+}
+
+/// Substitutes the arguments into the placeholders of `template`.
+///
+/// Placeholders referring to missing arguments are left as they are.
+pub fn format(template: &str, args: &FormatArgs) -> String {
+    substitute(&parse(template), args)
+}
+
+/// Substitutes the arguments into a translation of one of the `originals`.
+///
+/// If the translation has a placeholder none of the original strings has
+/// or one which refers to a missing argument,
+/// `fallback` is formatted instead.
+pub(crate) fn format_translation(
+    translated: &str,
+    originals: &[&str],
+    fallback: &str,
+    args: &FormatArgs,
+) -> String {
+    let pieces = parse(translated);
+    let allowed = originals
+        .iter()
+        .flat_map(|o| keys(&parse(o)))
+        .collect::<Vec<_>>();
+    let matches = keys(&pieces)
+        .iter()
+        .all(|key| allowed.contains(key) && args.get(key).is_some());
+    if matches {
+        substitute(&pieces, args)
+    } else {
+        format(fallback, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("{} {{x}} {name} {1}"),
+            [
+                Piece::Placeholder(Key::Index(0), "{}"),
+                Piece::Text(" {"),
+                Piece::Text("x}"),
+                Piece::Text(" "),
+                Piece::Placeholder(Key::Name("name"), "{name}"),
+                Piece::Text(" "),
+                Piece::Placeholder(Key::Index(1), "{1}"),
+            ]
+        );
+        assert_eq!(
+            parse("%s %% %-5.2lf %2$s"),
+            [
+                Piece::Placeholder(Key::Index(0), "%s"),
+                Piece::Text(" %"),
+                Piece::Text(" "),
+                Piece::Placeholder(Key::Index(1), "%-5.2lf"),
+                Piece::Text(" "),
+                Piece::Placeholder(Key::Index(1), "%2$s"),
+            ]
+        );
+        assert_eq!(
+            parse("100% done {not valid} {"),
+            [Piece::Text("100% done {not valid} {")]
+        );
+        assert_eq!(parse("%0$s"), [Piece::Text("%0$s")]);
+    }
+
+    #[test]
+    fn test_format() {
+        let args = FormatArgs::new().arg(&1).arg(&"two").named("name", &'x');
+        assert_eq!(format("{} {} {name}", &args), "1 two x");
+        assert_eq!(format("{1} {0}", &args), "two 1");
+        assert_eq!(format("%2$s %1$d %%", &args), "two 1 %");
+        assert_eq!(format("{{}} {missing} {2}", &args), "{} {missing} {2}");
+        // Format specs are not supported.
+        assert_eq!(format("{0:>5} {:?} {name}", &args), "{0:>5} {:?} x");
+        assert_eq!(
+            format!("{:?}", args),
+            r#"FormatArgs { positional: ["1", "two"], named: [("name", "x")] }"#
+        );
+    }
+
+    #[test]
+    fn test_format_translation() {
+        let args = FormatArgs::new().arg(&3).named("name", &"Jonas");
+        assert_eq!(
+            format_translation("{name} turi {}", &["{name} has {}"], "{name} has {}", &args),
+            "Jonas turi 3"
+        );
+        // A placeholder the original does not have.
+        assert_eq!(
+            format_translation("{user} turi {}", &["{name} has {}"], "{name} has {}", &args),
+            "Jonas has 3"
+        );
+        // A plural form may leave out the number.
+        assert_eq!(
+            format_translation(
+                "Vienas failas",
+                &["One file", "{} files"],
+                "One file",
+                &args
+            ),
+            "Vienas failas"
+        );
+        assert_eq!(
+            format_translation("%2$s", &["%s %s"], "%s %s", &args),
+            "3 %s"
+        );
+    }
+}
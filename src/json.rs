@@ -0,0 +1,300 @@
+//! Export of catalogs to the JSON formats of JavaScript translation libraries:
+//! [Jed](https://messageformat.github.io/Jed/) 1.x,
+//! [i18next](https://www.i18next.com/misc/json-format) (JSON v4)
+//! and [gettext.js](https://github.com/guillaumepotier/gettext.js).
+//!
+//! The messages are written sorted by context and original string,
+//! so that exporting the same catalog always gives the same output.
+//!
+//! ```
+//! use gettext::{json, Catalog};
+//!
+//! let catalog = Catalog::empty();
+//! let mut output = vec![];
+//! json::write_jed(&catalog, "messages", &mut output).unwrap();
+//! assert!(String::from_utf8(output).unwrap().contains(r#""domain": "messages""#));
+//! ```
+
+use std::io::Write;
+
+use crate::{cldr, Catalog, Error, Message};
+
+/// A JSON value, as far as the exporters need one.
+enum Value {
+    String(String),
+    Array(Vec<String>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn write<W: Write>(&self, writer: &mut W, indent: usize) -> Result<(), Error> {
+        let pad = "  ".repeat(indent + 1);
+        match *self {
+            Value::String(ref s) => write_string(writer, s)?,
+            Value::Array(ref items) => {
+                writer.write_all(b"[")?;
+                for (i, item) in items.iter().enumerate() {
+                    writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+                    writer.write_all(pad.as_bytes())?;
+                    write_string(writer, item)?;
+                }
+                write!(writer, "\n{}]", &pad[2..])?;
+            }
+            Value::Object(ref fields) => {
+                writer.write_all(b"{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+                    writer.write_all(pad.as_bytes())?;
+                    write_string(writer, key)?;
+                    writer.write_all(b": ")?;
+                    value.write(writer, indent + 1)?;
+                }
+                if !fields.is_empty() {
+                    write!(writer, "\n{}", &pad[2..])?;
+                }
+                writer.write_all(b"}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, s: &str) -> Result<(), Error> {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    writer.write_all(escaped.as_bytes())?;
+    Ok(())
+}
+
+fn write_document<W: Write>(mut writer: W, value: &Value) -> Result<(), Error> {
+    value.write(&mut writer, 0)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Returns the translated messages of the catalog, without the header,
+/// sorted by context and original string.
+fn messages(catalog: &Catalog) -> Vec<&Message> {
+    let mut messages = catalog
+        .messages()
+        .filter(|m| !m.id.is_empty() || m.context.is_some())
+        .filter(|m| m.translated.iter().any(|t| !t.is_empty()))
+        .collect::<Vec<_>>();
+    messages.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+    messages
+}
+
+/// Returns the key of a message with a context, joined by `\u0004` like in MO files.
+fn context_key(message: &Message) -> String {
+    match message.context {
+        Some(ref ctxt) => crate::key_with_context(ctxt, &message.id),
+        None => message.id.clone(),
+    }
+}
+
+/// Writes the catalog in the format of Jed 1.x, as `{"domain": ..., "locale_data": ...}`,
+/// with the messages of the given domain.
+///
+/// Each message maps to the array of its translated strings,
+/// and the `""` entry holds the domain, the language and the plural forms.
+pub fn write_jed<W: Write>(catalog: &Catalog, domain: &str, writer: W) -> Result<(), Error> {
+    let (_, plural_forms) = catalog.plural_forms();
+    let header = Value::Object(vec![
+        ("domain".to_owned(), Value::String(domain.to_owned())),
+        (
+            "lang".to_owned(),
+            Value::String(catalog.metadata_field("Language").unwrap_or("").to_owned()),
+        ),
+        ("plural_forms".to_owned(), Value::String(plural_forms)),
+    ]);
+    let mut entries = vec![(String::new(), header)];
+    for message in messages(catalog) {
+        entries.push((
+            context_key(message),
+            Value::Array(message.translated.clone()),
+        ));
+    }
+    let document = Value::Object(vec![
+        ("domain".to_owned(), Value::String(domain.to_owned())),
+        (
+            "locale_data".to_owned(),
+            Value::Object(vec![(domain.to_owned(), Value::Object(entries))]),
+        ),
+    ]);
+    write_document(writer, &document)
+}
+
+/// Writes the catalog in the format of gettext.js.
+///
+/// Each message maps to its translated string, or to the array of them if it has a plural,
+/// and the `""` entry holds the language and the plural forms.
+pub fn write_gettext_js<W: Write>(catalog: &Catalog, writer: W) -> Result<(), Error> {
+    let (_, plural_forms) = catalog.plural_forms();
+    let mut header = vec![];
+    if let Some(language) = catalog.metadata_field("Language") {
+        header.push(("language".to_owned(), Value::String(language.to_owned())));
+    }
+    header.push(("plural-forms".to_owned(), Value::String(plural_forms)));
+    let mut entries = vec![(String::new(), Value::Object(header))];
+    for message in messages(catalog) {
+        let value = match message.plural {
+            Some(_) => Value::Array(message.translated.clone()),
+            None => Value::String(message.translated[0].clone()),
+        };
+        entries.push((context_key(message), value));
+    }
+    write_document(writer, &Value::Object(entries))
+}
+
+/// Writes the catalog in the JSON v4 format of i18next, with the original strings as keys.
+///
+/// Messages with a context get the key `{id}_{context}`,
+/// and plural forms are suffixed with their CLDR category, e.g. `{id}_one` and `{id}_few`,
+/// derived from the plural formula and the `Language` header.
+/// The last form also gets the `_other` suffix if no form has that category,
+/// as i18next falls back to it.
+///
+/// Original strings are used as keys verbatim, so i18next should be configured
+/// with `keySeparator: false` and `nsSeparator: false` if they may contain `.` or `:`.
+/// Placeholders are not converted to the `{{name}}` syntax of i18next.
+pub fn write_i18next<W: Write>(catalog: &Catalog, writer: W) -> Result<(), Error> {
+    let categories = catalog.plural_categories();
+    let mut entries = vec![];
+    for message in messages(catalog) {
+        let key = match message.context {
+            Some(ref ctxt) => format!("{}_{}", message.id, ctxt),
+            None => message.id.clone(),
+        };
+        if message.plural.is_none() {
+            entries.push((key, Value::String(message.translated[0].clone())));
+            continue;
+        }
+        for (category, translated) in cldr::variants(&message.translated, &categories) {
+            entries.push((
+                format!("{}_{}", key, category),
+                Value::String(translated.to_owned()),
+            ));
+        }
+    }
+    write_document(writer, &Value::Object(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2);\n"
+
+msgid "Open"
+msgstr "Atverti \"failą\""
+
+msgctxt "menu"
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failai"
+msgstr[2] "Failų"
+
+msgid "Untranslated"
+msgstr ""
+"#;
+
+    fn export(write: fn(&Catalog, &mut Vec<u8>) -> Result<(), Error>) -> String {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const PLURAL_FORMS: &str = "nplurals=3; plural=(n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2);";
+
+    #[test]
+    fn test_write_jed() {
+        assert_eq!(
+            export(|c, w| write_jed(c, "app", w)),
+            format!(
+                r#"{{
+  "domain": "app",
+  "locale_data": {{
+    "app": {{
+      "": {{
+        "domain": "app",
+        "lang": "lt",
+        "plural_forms": "{}"
+      }},
+      "Open": [
+        "Atverti \"failą\""
+      ],
+      "menu\u0004File": [
+        "Failas",
+        "Failai",
+        "Failų"
+      ]
+    }}
+  }}
+}}
+"#,
+                PLURAL_FORMS
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_gettext_js() {
+        assert_eq!(
+            export(|c, w| write_gettext_js(c, w)),
+            format!(
+                r#"{{
+  "": {{
+    "language": "lt",
+    "plural-forms": "{}"
+  }},
+  "Open": "Atverti \"failą\"",
+  "menu\u0004File": [
+    "Failas",
+    "Failai",
+    "Failų"
+  ]
+}}
+"#,
+                PLURAL_FORMS
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_i18next() {
+        assert_eq!(
+            export(|c, w| write_i18next(c, w)),
+            r#"{
+  "Open": "Atverti \"failą\"",
+  "File_menu_one": "Failas",
+  "File_menu_few": "Failai",
+  "File_menu_other": "Failų"
+}
+"#
+        );
+
+        let catalog = Catalog::empty();
+        let mut output = vec![];
+        write_i18next(&catalog, &mut output).unwrap();
+        assert_eq!(output, b"{}\n");
+    }
+}
@@ -17,6 +17,20 @@
 //! For the exact feature parity see the roadmap in the
 //! [README](https://github.com/justinas/gettext#readme).
 //!
+//! # Embedding catalogs
+//!
+//! A catalog can be parsed at compile time and embedded into the program
+//! as a `StaticCatalog` by `gettext_macros::include_catalog!`.
+//! The macro lives in the `gettext-macros` crate, which depends on this one,
+//! so it is not available as `gettext::include_catalog!`.
+//!
+//! # `no_std`
+//!
+//! Without the default `std` feature, the crate only needs `alloc`.
+//! Catalogs can then be looked up, and parsed from MO files in a byte slice
+//! (see `Catalog::parse_bytes`) encoded in UTF-8 or with a given `Decoder`,
+//! while PO files, writing and the conversions to other formats are unavailable.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -35,6 +49,7 @@
 //! }
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(clippy::all)]
 // https://pascalhertleif.de/artikel/good-practices-for-writing-rust-libraries/
 #![deny(
@@ -45,18 +60,113 @@
     unused_import_braces
 )]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod android;
+#[cfg(feature = "std")]
+pub mod apple;
+#[cfg(feature = "std")]
+mod check;
+#[cfg(feature = "std")]
+mod cldr;
+#[cfg(feature = "std")]
+pub mod csv;
+mod decoder;
 mod error;
-mod metadata;
+#[cfg(feature = "std")]
+pub mod fluent;
+pub mod format;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+mod merge;
+pub mod metadata;
 mod parser;
 mod plurals;
+#[cfg(feature = "std")]
+pub mod po;
+#[cfg(feature = "std")]
+pub mod qt;
+#[cfg(feature = "std")]
+mod reload;
+#[cfg(feature = "serde")]
+mod serialize;
+mod static_catalog;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub mod xliff;
+#[cfg(feature = "std")]
+mod xml;
 
-use std::collections::HashMap;
-use std::io::Read;
-use std::ops::Deref;
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
 
 use crate::parser::default_resolver;
 use crate::plurals::*;
-pub use crate::{error::Error, parser::ParseOptions};
+#[cfg(feature = "std")]
+use crate::po::PoFile;
+#[cfg(feature = "std")]
+pub use crate::{
+    check::{FormatMismatch, FormatSyntax},
+    reload::ReloadableCatalog,
+    stats::{Counts, Stats},
+    writer::WriteOptions,
+};
+pub use crate::{
+    decoder::Decoder, error::Error, format::FormatArgs, parser::ParseOptions,
+    static_catalog::StaticCatalog,
+};
+
+/// The map the messages of a catalog are stored in;
+/// hash maps are only available with the standard library.
+#[cfg(feature = "std")]
+type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// Items used by the code generated in `gettext-macros`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::format::Key as Placeholder;
+    pub use crate::plurals::{Ast, Operator};
+    pub use crate::static_catalog::{build_phf, PhfTable, StaticMessage};
+    use crate::{Catalog, Message, Resolver};
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    /// Returns the parsed plural formula of the catalog,
+    /// or `None` if the default formula is used.
+    pub fn plural_ast(catalog: &Catalog) -> Option<&Ast> {
+        match catalog.resolver {
+            Resolver::Expr(ref ast) => Some(ast),
+            Resolver::Function(_) => None,
+        }
+    }
+
+    /// Returns the distinct placeholders of a format string, sorted.
+    pub fn placeholders(s: &str) -> Vec<Placeholder<'_>> {
+        crate::format::keys(&crate::format::parse(s))
+    }
+
+    /// Returns the key the message is looked up by.
+    pub fn message_key(message: &Message) -> String {
+        match message.context {
+            Some(ref ctxt) => crate::key_with_context(ctxt, &message.id),
+            None => message.id.clone(),
+        }
+    }
+}
 
 fn key_with_context(context: &str, key: &str) -> String {
     let mut result = context.to_owned();
@@ -66,11 +176,41 @@ fn key_with_context(context: &str, key: &str) -> String {
 }
 
 /// Catalog represents a set of translation strings
-/// parsed out of one MO file.
+/// parsed out of one MO or PO file.
+///
+/// # Serialization
+///
+/// With the `serde` feature enabled, catalogs implement `Serialize` and `Deserialize`
+/// as a map of the following shape, shown here in JSON:
+///
+/// ```json
+/// {
+///   "plural": "(n != 1)",
+///   "messages": [
+///     {"id": "", "context": null, "id_plural": null, "translations": ["Content-Type: ..."]},
+///     {"id": "File", "context": "menu", "id_plural": "Files", "translations": ["Failas", "Failai"]}
+///   ],
+///   "fuzzy": [{"context": null, "id": "Open"}, {"context": null, "id": "%d file", "plural": true}]
+/// }
+/// ```
+///
+/// * `plural` is the plural formula, in the syntax of the `Plural-Forms` header,
+///   or `null` for the default `n != 1`.
+///   A function given to `ParseOptions::force_plural` can not be serialized
+///   and is replaced by the default one.
+/// * `messages` are sorted by context and original string, with the metadata
+///   as a message with an empty id. `context` and `id_plural` may be left out
+///   when deserializing.
+/// * `fuzzy` lists the messages which were marked as fuzzy, see `Catalog::is_fuzzy`,
+///   with `plural` set for the ones with a plural form.
+///   It may be left out when deserializing, and so may `context` and `plural`.
 #[derive(Clone, Debug)]
 pub struct Catalog {
-    strings: HashMap<String, Message>,
+    strings: Map<String, Message>,
     resolver: Resolver,
+    /// The context and id of the entries marked as fuzzy in the source PO file,
+    /// mapped to whether they have a plural form.
+    fuzzy: BTreeMap<(Option<String>, String), bool>,
 }
 
 impl Catalog {
@@ -84,8 +224,9 @@ impl Catalog {
     /// Creates a new, empty gettext catalog.
     fn new() -> Self {
         Catalog {
-            strings: HashMap::new(),
+            strings: Map::new(),
             resolver: Resolver::Function(default_resolver),
+            fuzzy: BTreeMap::new(),
         }
     }
 
@@ -105,10 +246,99 @@ impl Catalog {
     /// let file = File::open("french.mo").unwrap();
     /// let catalog = Catalog::parse(file).unwrap();
     /// ```
+    #[cfg(feature = "std")]
     pub fn parse<R: Read>(reader: R) -> Result<Self, Error> {
         ParseOptions::new().parse(reader)
     }
 
+    /// Parses a gettext catalog from the given binary MO file in a byte slice.
+    ///
+    /// Calling this method is equivalent to calling
+    /// `ParseOptions::new().parse_bytes(bytes)`.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        ParseOptions::new().parse_bytes(bytes)
+    }
+
+    /// Parses a gettext catalog from the given textual PO file.
+    /// Returns the `Err` variant upon encountering invalid syntax
+    /// or invalid byte sequence in strings.
+    ///
+    /// Untranslated messages, including plural ones missing the translation of any form,
+    /// and the ones marked as fuzzy are left out of the catalog,
+    /// just like `msgfmt` does when compiling an MO file.
+    /// See `ParseOptions::include_fuzzy` to keep the fuzzy ones.
+    ///
+    /// Calling this method is equivalent to calling
+    /// `ParseOptions::new().parse_po(reader)`.
+    #[cfg(feature = "std")]
+    pub fn parse_po<R: Read>(reader: R) -> Result<Self, Error> {
+        ParseOptions::new().parse_po(reader)
+    }
+
+    /// Writes the catalog out as a binary MO file.
+    ///
+    /// The strings are encoded in UTF-8
+    /// and the `Content-Type` header field is updated accordingly.
+    /// See `WriteOptions` for writing them in another encoding.
+    #[cfg(feature = "std")]
+    pub fn write_mo<W: Write>(&self, writer: W) -> Result<(), Error> {
+        WriteOptions::new().write_mo(self, writer)
+    }
+
+    /// Returns an iterator over all the messages in this catalog, in no particular order.
+    ///
+    /// The metadata entry, if present, is yielded as a message with an empty id.
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.strings.values()
+    }
+
+    /// Returns whether the message was marked as fuzzy in the PO file it was parsed from,
+    /// regardless of whether its translation was included in the catalog.
+    /// Entries missing the translation of any of their forms are untranslated
+    /// rather than fuzzy, like `Stats` counts them.
+    pub fn is_fuzzy(&self, msg_context: Option<&str>, msg_id: &str) -> bool {
+        self.fuzzy
+            .contains_key(&(msg_context.map(str::to_owned), msg_id.to_owned()))
+    }
+
+    /// Returns the context and id of every message
+    /// which was marked as fuzzy in the PO file the catalog was parsed from,
+    /// sorted by context and id.
+    pub fn fuzzy_messages(&self) -> impl Iterator<Item = (Option<&str>, &str)> {
+        self.fuzzy
+            .keys()
+            .map(|(ctxt, id)| (ctxt.as_deref(), id.as_str()))
+    }
+
+    /// Returns the number of translated and fuzzy messages in this catalog.
+    ///
+    /// The fuzzy messages left out when parsing a PO file are counted as well,
+    /// but other untranslated ones are unknown to the catalog;
+    /// see `stats_against` for the coverage of a template.
+    #[cfg(feature = "std")]
+    pub fn stats(&self) -> Stats {
+        stats::catalog_stats(self)
+    }
+
+    /// Returns the coverage of the messages of the given template by this catalog.
+    ///
+    /// The messages of the catalog which are not in the template are counted as obsolete.
+    #[cfg(feature = "std")]
+    pub fn stats_against(&self, template: &PoFile) -> Stats {
+        stats::catalog_stats_against(self, template)
+    }
+
+    /// Checks that the translations use the same format directives
+    /// as the original strings, treating every message as having the given syntax.
+    ///
+    /// A catalog does not know the format flags of its messages;
+    /// see `PoFile::check_format` for checking each entry according to its flag.
+    /// The mismatches are sorted by context and original string.
+    #[cfg(feature = "std")]
+    pub fn check_format(&self, syntax: FormatSyntax) -> Vec<FormatMismatch> {
+        check::check_catalog(self, syntax)
+    }
+
     fn insert(&mut self, msg: Message) {
         let key = match msg.context {
             Some(ref ctxt) => key_with_context(ctxt, &msg.id),
@@ -117,6 +347,50 @@ impl Catalog {
         self.strings.insert(key, msg);
     }
 
+    #[cfg(feature = "std")]
+    fn metadata(&self) -> Option<metadata::MetadataMap<'_>> {
+        let header = self.strings.get("")?.translated.first()?;
+        metadata::parse_metadata(header).ok()
+    }
+
+    /// Returns the value of the given field of the catalog header, if any.
+    #[cfg(feature = "std")]
+    pub(crate) fn metadata_field(&self, field: &str) -> Option<&str> {
+        self.metadata()?.get(field).copied()
+    }
+
+    /// Returns the number of plural forms and the `Plural-Forms` header value,
+    /// falling back to the plural formula of the catalog or the default one.
+    #[cfg(feature = "std")]
+    pub(crate) fn plural_forms(&self) -> (usize, String) {
+        if let Some(map) = self.metadata() {
+            if let (Some(nplurals), Some(_)) = map.plural_forms() {
+                return (nplurals, map["Plural-Forms"].to_owned());
+            }
+        }
+        match self.resolver {
+            Resolver::Expr(ref ast) => {
+                let nplurals = (0..1000)
+                    .map(|n| self.resolver.resolve(n))
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                (nplurals, format!("nplurals={}; plural={};", nplurals, ast))
+            }
+            Resolver::Function(_) => (2, "nplurals=2; plural=(n != 1);".to_owned()),
+        }
+    }
+
+    /// Returns the CLDR plural category of each plural form of the catalog,
+    /// e.g. `["one", "few", "other"]`.
+    #[cfg(feature = "std")]
+    pub(crate) fn plural_categories(&self) -> Vec<&'static str> {
+        let (nplurals, _) = self.plural_forms();
+        cldr::plural_categories(self.metadata_field("Language"), nplurals, &|n| {
+            self.resolver.resolve(n)
+        })
+    }
+
     /// Returns the singular translation of `msg_id` from the given catalog
     /// or `msg_id` itself if a translation does not exist.
     pub fn gettext<'a>(&'a self, msg_id: &'a str) -> &'a str {
@@ -146,7 +420,7 @@ impl Catalog {
     /// or `msg_id` itself if a translation does not exist.
     // TODO: DRY gettext/pgettext
     pub fn pgettext<'a>(&'a self, msg_context: &str, msg_id: &'a str) -> &'a str {
-        let key = key_with_context(msg_context, &msg_id);
+        let key = key_with_context(msg_context, msg_id);
         self.strings
             .get(&key)
             .and_then(|msg| msg.get_translated(0))
@@ -166,7 +440,7 @@ impl Catalog {
         msg_id_plural: &'a str,
         n: u64,
     ) -> &'a str {
-        let key = key_with_context(msg_context, &msg_id);
+        let key = key_with_context(msg_context, msg_id);
         let form_no = self.resolver.resolve(n);
         let message = self.strings.get(&key);
         match message.and_then(|m| m.get_translated(form_no)) {
@@ -176,12 +450,70 @@ impl Catalog {
             _ => unreachable!(),
         }
     }
+
+    /// Returns the singular translation of `msg_id`
+    /// with the arguments substituted into its placeholders.
+    ///
+    /// If the translation has placeholders which `msg_id` does not have
+    /// or which refer to missing arguments, `msg_id` is formatted instead.
+    /// See the `format` module for the supported placeholders.
+    ///
+    /// ```ignore
+    /// let args = FormatArgs::new().named("name", &user);
+    /// let greeting = catalog.format("Hello, {name}!", &args);
+    /// ```
+    pub fn format(&self, msg_id: &str, args: &FormatArgs) -> String {
+        format::format_translation(self.gettext(msg_id), &[msg_id], msg_id, args)
+    }
+
+    /// Returns the plural translation of `msg_id` for `n` objects
+    /// with the arguments substituted into its placeholders.
+    ///
+    /// Falls back to the untranslated form like `format` does;
+    /// any form may leave out some of the placeholders, e.g. the number.
+    pub fn nformat(&self, msg_id: &str, msg_id_plural: &str, n: u64, args: &FormatArgs) -> String {
+        let translated = self.ngettext(msg_id, msg_id_plural, n);
+        let fallback = if n == 1 { msg_id } else { msg_id_plural };
+        format::format_translation(translated, &[msg_id, msg_id_plural], fallback, args)
+    }
+
+    /// Returns the singular translation of `msg_id` in the context `msg_context`
+    /// with the arguments substituted into its placeholders,
+    /// falling back to the untranslated string like `format` does.
+    pub fn pformat(&self, msg_context: &str, msg_id: &str, args: &FormatArgs) -> String {
+        let translated = self.pgettext(msg_context, msg_id);
+        format::format_translation(translated, &[msg_id], msg_id, args)
+    }
+
+    /// Returns the plural translation of `msg_id` in the context `msg_context`
+    /// for `n` objects with the arguments substituted into its placeholders,
+    /// falling back to the untranslated form like `nformat` does.
+    pub fn npformat(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        msg_id_plural: &str,
+        n: u64,
+        args: &FormatArgs,
+    ) -> String {
+        let translated = self.npgettext(msg_context, msg_id, msg_id_plural, n);
+        let fallback = if n == 1 { msg_id } else { msg_id_plural };
+        format::format_translation(translated, &[msg_id, msg_id_plural], fallback, args)
+    }
 }
 
+/// Message represents a single entry of a catalog:
+/// the original string with its optional context and plural form,
+/// and the translated strings.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct Message {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Message {
     id: String,
+    #[cfg_attr(feature = "serde", serde(default))]
     context: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default, rename = "id_plural"))]
+    plural: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "translations"))]
     translated: Vec<String>,
 }
 
@@ -190,10 +522,37 @@ impl Message {
         Message {
             id: id.into(),
             context: context.map(Into::into),
+            plural: None,
             translated: translated.into_iter().map(Into::into).collect(),
         }
     }
 
+    fn with_plural<T: Into<String>>(mut self, plural: Option<T>) -> Self {
+        self.plural = plural.map(Into::into);
+        self
+    }
+
+    /// Returns the original string (msgid) of this message.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the context (msgctxt) of this message, if any.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// Returns the original plural string (msgid_plural) of this message, if any.
+    pub fn id_plural(&self) -> Option<&str> {
+        self.plural.as_deref()
+    }
+
+    /// Returns the translated strings of this message,
+    /// one for each plural form.
+    pub fn translations(&self) -> &[String] {
+        &self.translated
+    }
+
     fn get_translated(&self, form_no: usize) -> Option<&str> {
         self.translated.get(form_no).map(|s| s.deref())
     }
@@ -201,7 +560,7 @@ impl Message {
 
 #[test]
 fn catalog_impls_send_sync() {
-    fn check<T: Send + Sync>(_: T) {};
+    fn check<T: Send + Sync>(_: T) {}
     check(Catalog::new());
 }
 
@@ -310,3 +669,38 @@ fn catalog_npgettext() {
         "Texts"
     );
 }
+
+#[test]
+fn catalog_format() {
+    let mut cat = Catalog::new();
+    cat.insert(Message::new("Hello, {name}!", None, vec!["Labas, {name}!"]));
+    cat.insert(Message::new("{0} of {1}", None, vec!["{1}: {0}"]));
+    cat.insert(Message::new("Broken {}", None, vec!["Sugadintas {user}"]));
+    cat.insert(
+        Message::new("%d file", None, vec!["%d failas", "%d failai"]).with_plural(Some("%d files")),
+    );
+    cat.insert(
+        Message::new("One item", Some("cart"), vec!["Viena prekė", "{} prekės"])
+            .with_plural(Some("{} items")),
+    );
+
+    let args = FormatArgs::new().named("name", &"Jonas");
+    assert_eq!(cat.format("Hello, {name}!", &args), "Labas, Jonas!");
+    assert_eq!(cat.format("Bye, {name}!", &args), "Bye, Jonas!");
+    let args = FormatArgs::new().arg(&1).arg(&2);
+    assert_eq!(cat.format("{0} of {1}", &args), "2: 1");
+    assert_eq!(cat.format("Broken {}", &args), "Broken 1");
+
+    let args = FormatArgs::new().arg(&5);
+    assert_eq!(cat.nformat("%d file", "%d files", 5, &args), "5 failai");
+    assert_eq!(cat.nformat("%d dir", "%d dirs", 5, &args), "5 dirs");
+    assert_eq!(
+        cat.npformat("cart", "One item", "{} items", 1, &args),
+        "Viena prekė"
+    );
+    assert_eq!(
+        cat.npformat("cart", "One item", "{} items", 5, &args),
+        "5 prekės"
+    );
+    assert_eq!(cat.pformat("cart", "Total: {}", &args), "Total: 5");
+}
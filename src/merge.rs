@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use crate::po::{PoEntry, PoFile};
+
+/// The similarity two original strings need
+/// for the translation of one to be proposed for the other, as in `msgmerge`.
+const FUZZY_THRESHOLD: f64 = 0.6;
+
+/// MergeOptions allows configuring how translations
+/// are merged into a new template.
+///
+/// ```
+/// use gettext::po::{MergeOptions, PoFile};
+///
+/// let translations = PoFile::new();
+/// let template = PoFile::new();
+/// let merged = MergeOptions::new()
+///     .fuzzy_matching(false)
+///     .merge(&translations, &template);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MergeOptions {
+    fuzzy_matching: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            fuzzy_matching: true,
+        }
+    }
+}
+
+impl MergeOptions {
+    /// Returns a new instance of MergeOptions with default options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether the translations of similar original strings should be carried over
+    /// to new messages, marked as fuzzy. Enabled by default.
+    pub fn fuzzy_matching(mut self, fuzzy_matching: bool) -> Self {
+        self.fuzzy_matching = fuzzy_matching;
+        self
+    }
+
+    /// Updates the translations in `translations` to the messages of `template`,
+    /// the way `msgmerge` does.
+    ///
+    /// Messages are matched by their context and original string,
+    /// taking the comments, references and flags from the template
+    /// and the translations and translator comments from the existing file.
+    /// A message that has no exact match gets the translation of the most similar
+    /// original string, if fuzzy matching is enabled, and is marked as fuzzy,
+    /// with the string the translation was made for kept in the `previous_*` fields.
+    /// Translated messages which are no longer in the template are made obsolete.
+    pub fn merge(&self, translations: &PoFile, template: &PoFile) -> PoFile {
+        let mut index = HashMap::new();
+        for (i, entry) in translations.entries.iter().enumerate() {
+            if !entry.is_header() {
+                // Prefer active entries to obsolete ones with the same key.
+                index
+                    .entry((&entry.context, &entry.id))
+                    .and_modify(|j: &mut usize| {
+                        if translations.entries[*j].obsolete {
+                            *j = i;
+                        }
+                    })
+                    .or_insert(i);
+            }
+        }
+        let candidates = translations
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_header() && !e.obsolete && is_translated(e))
+            .collect::<Vec<_>>();
+
+        let mut used = vec![false; translations.entries.len()];
+        let mut entries = vec![];
+        if let Some(header) = merge_header(translations.header(), template.header()) {
+            entries.push(header);
+        }
+        for new in template
+            .entries
+            .iter()
+            .filter(|e| !e.is_header() && !e.obsolete)
+        {
+            let mut entry = PoEntry {
+                obsolete: false,
+                translations: vec![],
+                translator_comments: vec![],
+                ..new.clone()
+            };
+            if let Some(&i) = index.get(&(&new.context, &new.id)) {
+                used[i] = true;
+                let old = &translations.entries[i];
+                entry.translator_comments = old.translator_comments.clone();
+                entry.translations = old.translations.clone();
+                entry.previous_context = old.previous_context.clone();
+                entry.previous_id = old.previous_id.clone();
+                entry.previous_id_plural = old.previous_id_plural.clone();
+                if old.has_flag("fuzzy") || old.id_plural != new.id_plural {
+                    set_fuzzy(&mut entry);
+                    if old.id_plural != new.id_plural && entry.previous_id.is_none() {
+                        entry.previous_id = Some(old.id.clone());
+                        entry.previous_id_plural = old.id_plural.clone();
+                    }
+                }
+            } else if let Some((i, old)) = self.find_similar(new, &candidates) {
+                used[i] = true;
+                entry.translations = old.translations.clone();
+                if old.context != new.context {
+                    entry.previous_context = old.context.clone();
+                }
+                entry.previous_id = Some(old.id.clone());
+                entry.previous_id_plural = old.id_plural.clone();
+                set_fuzzy(&mut entry);
+            }
+            if entry.id_plural.is_some() && entry.translations.len() == 1 {
+                // A singular translation is the starting point for every plural form.
+                let translated = entry.translations[0].clone();
+                entry.translations.push(translated);
+            } else if entry.id_plural.is_none() {
+                entry.translations.truncate(1);
+            }
+            entries.push(entry);
+        }
+
+        for (i, old) in translations.entries.iter().enumerate() {
+            if used[i] || old.is_header() || !is_translated(old) {
+                continue;
+            }
+            entries.push(PoEntry {
+                extracted_comments: vec![],
+                references: vec![],
+                obsolete: true,
+                ..old.clone()
+            });
+        }
+        let mut merged = PoFile::new();
+        merged.entries = entries;
+        merged
+    }
+
+    /// Finds the translated entry with the original string most similar to the new one.
+    fn find_similar<'a>(
+        &self,
+        new: &PoEntry,
+        candidates: &[(usize, &'a PoEntry)],
+    ) -> Option<(usize, &'a PoEntry)> {
+        if !self.fuzzy_matching || new.id.is_empty() {
+            return None;
+        }
+        let new_id = new.id.chars().collect::<Vec<_>>();
+        let mut best = None;
+        let mut best_similarity = FUZZY_THRESHOLD;
+        for &(i, old) in candidates {
+            let old_id = old.id.chars().collect::<Vec<_>>();
+            // The longest common subsequence can not be longer than the shorter string.
+            let bound =
+                2.0 * new_id.len().min(old_id.len()) as f64 / (new_id.len() + old_id.len()) as f64;
+            if bound < best_similarity {
+                continue;
+            }
+            let mut similarity = similarity(&new_id, &old_id);
+            if old.context != new.context {
+                // Matches within the same context are preferred.
+                similarity *= 0.99;
+            }
+            if similarity >= best_similarity {
+                best_similarity = similarity;
+                best = Some((i, old));
+            }
+        }
+        best
+    }
+}
+
+impl PoFile {
+    /// Updates the translations in this file to the messages of `template`,
+    /// with the default options. See `MergeOptions::merge` for details.
+    pub fn merge(&self, template: &PoFile) -> PoFile {
+        MergeOptions::new().merge(self, template)
+    }
+}
+
+fn is_translated(entry: &PoEntry) -> bool {
+    entry.translations.iter().any(|t| !t.is_empty())
+}
+
+fn set_fuzzy(entry: &mut PoEntry) {
+    if !entry.has_flag("fuzzy") {
+        entry.flags.insert(0, "fuzzy".to_owned());
+    }
+}
+
+/// Keeps the existing header, updating the `POT-Creation-Date` field from the template.
+fn merge_header(old: Option<&PoEntry>, new: Option<&PoEntry>) -> Option<PoEntry> {
+    let mut header = match (old, new) {
+        (Some(old), _) => old.clone(),
+        (None, Some(new)) => new.clone(),
+        (None, None) => return None,
+    };
+    let date = new
+        .and_then(|n| n.translations.first())
+        .and_then(|t| t.lines().find(|l| l.starts_with("POT-Creation-Date:")));
+    if let (Some(date), Some(text)) = (date, header.translations.first_mut()) {
+        *text = text
+            .split_inclusive('\n')
+            .map(|line| {
+                if line.starts_with("POT-Creation-Date:") {
+                    format!("{}\n", date)
+                } else {
+                    line.to_owned()
+                }
+            })
+            .collect();
+    }
+    Some(header)
+}
+
+/// Returns the ratio of the characters which the strings have in common,
+/// from 0 (nothing in common) to 1 (equal).
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    // The length of the longest common subsequence, computed row by row.
+    let mut row = vec![0; b.len() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    2.0 * row[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(po: &str) -> PoFile {
+        PoFile::parse(po.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_similarity() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(similarity(&chars("abc"), &chars("abc")), 1.0);
+        assert_eq!(similarity(&chars("abc"), &chars("xyz")), 0.0);
+        assert_eq!(similarity(&chars("abcd"), &chars("abxd")), 0.75);
+        assert_eq!(similarity(&chars(""), &chars("")), 1.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let translations = parse(
+            r#"# Lithuanian translation.
+msgid ""
+msgstr ""
+"POT-Creation-Date: 2020-01-01 00:00+0000\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+# kept comment
+#: src/old.rs:1
+msgid "Open"
+msgstr "Atidaryti"
+
+msgctxt "menu"
+msgid "Save the file"
+msgstr "Išsaugoti failą"
+
+msgid "File"
+msgstr "Failas"
+
+msgid "Removed"
+msgstr "Pašalinta"
+
+msgid "Untranslated"
+msgstr ""
+
+#~ msgid "Revived"
+#~ msgstr "Atgaivinta"
+"#,
+        );
+        let template = parse(
+            r#"msgid ""
+msgstr ""
+"POT-Creation-Date: 2021-02-03 04:05+0000\n"
+"Content-Type: text/plain; charset=CHARSET\n"
+
+#: src/new.rs:2
+#, c-format
+msgid "Open"
+msgstr ""
+
+msgctxt "menu"
+msgid "Save the files"
+msgstr ""
+
+msgid "File"
+msgid_plural "Files"
+msgstr[0] ""
+msgstr[1] ""
+
+msgid "Revived"
+msgstr ""
+
+msgid "Something else entirely"
+msgstr ""
+"#,
+        );
+
+        let merged = translations.merge(&template);
+        let mut written = vec![];
+        merged.write(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            r#"# Lithuanian translation.
+msgid ""
+msgstr ""
+"POT-Creation-Date: 2021-02-03 04:05+0000\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+# kept comment
+#: src/new.rs:2
+#, c-format
+msgid "Open"
+msgstr "Atidaryti"
+
+#, fuzzy
+#| msgid "Save the file"
+msgctxt "menu"
+msgid "Save the files"
+msgstr "Išsaugoti failą"
+
+#, fuzzy
+#| msgid "File"
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failas"
+
+msgid "Revived"
+msgstr "Atgaivinta"
+
+msgid "Something else entirely"
+msgstr ""
+
+#~ msgid "Removed"
+#~ msgstr "Pašalinta"
+"#
+        );
+
+        let merged = MergeOptions::new()
+            .fuzzy_matching(false)
+            .merge(&translations, &template);
+        let save = &merged.entries[2];
+        assert_eq!(save.id, "Save the files");
+        assert!(save.translations.is_empty());
+        assert!(!save.has_flag("fuzzy"));
+        assert!(merged
+            .entries
+            .iter()
+            .any(|e| e.obsolete && e.id == "Save the file"));
+    }
+}
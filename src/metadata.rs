@@ -1,17 +1,27 @@
-use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
+//! Parsing of the catalog metadata stored in the header entry.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
 
 use super::Error;
 use crate::Error::MalformedMetadata;
 
+/// MetadataMap holds the fields of a catalog header,
+/// e.g. `Content-Type` or `Plural-Forms`, mapped to their values.
+///
+/// It dereferences to a `BTreeMap` with or without the `std` feature,
+/// so that the fields are ordered by name.
 #[derive(Debug)]
-pub struct MetadataMap<'a>(HashMap<&'a str, &'a str>);
+pub struct MetadataMap<'a>(BTreeMap<&'a str, &'a str>);
 
 impl<'a> MetadataMap<'a> {
-    /// Returns a string that indicates the character set.
+    /// Returns a string that indicates the character set,
+    /// i.e. the `charset` parameter of the `Content-Type` field,
+    /// e.g. `UTF-8` for `text/plain; charset=UTF-8`.
     pub fn charset(&self) -> Option<&'a str> {
         self.get("Content-Type")
-            .and_then(|x| x.split("charset=").nth(1))
+            .and_then(|x| mime_parameter(x, "charset"))
     }
 
     /// Returns the number of different plurals and the boolean
@@ -28,7 +38,7 @@ impl<'a> MetadataMap<'a> {
                             let (name, value) = prop.split_at(index);
                             let value = value[1..value.len()].trim();
                             match name.trim() {
-                                "n_plurals" => (usize::from_str_radix(value, 10).ok(), pl),
+                                "nplurals" | "n_plurals" => (value.parse::<usize>().ok(), pl),
                                 "plural" => (n_pl, Some(value)),
                                 _ => (n_pl, pl),
                             }
@@ -42,7 +52,7 @@ impl<'a> MetadataMap<'a> {
 }
 
 impl<'a> Deref for MetadataMap<'a> {
-    type Target = HashMap<&'a str, &'a str>;
+    type Target = BTreeMap<&'a str, &'a str>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
@@ -54,8 +64,46 @@ impl<'a> DerefMut for MetadataMap<'a> {
     }
 }
 
-pub fn parse_metadata(blob: &str) -> Result<MetadataMap, Error> {
-    let mut map = MetadataMap(HashMap::new());
+/// Returns the value of the parameter with the given name of a MIME type,
+/// without the quotes around it if it is quoted.
+/// Parameter names are matched case-insensitively.
+fn mime_parameter<'a>(mime: &'a str, name: &str) -> Option<&'a str> {
+    let mut params = Vec::new();
+    let mut start = 0;
+    let (mut quoted, mut escaped) = (false, false);
+    for (i, c) in mime.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(&mime[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&mime[start..]);
+
+    // The first item is the type itself, unless it is missing.
+    params
+        .into_iter()
+        .filter_map(|param| {
+            let pos = param.find('=')?;
+            Some((param[..pos].trim(), param[pos + 1..].trim()))
+        })
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| match value.strip_prefix('"') {
+            Some(value) => value.strip_suffix('"').unwrap_or(value),
+            None => value,
+        })
+        .filter(|value| !value.is_empty())
+}
+
+/// Parses the header of a catalog, i.e. the translation of the empty string.
+/// Returns the `Err` variant if a non-empty line lacks a `:` separator.
+pub fn parse_metadata(blob: &str) -> Result<MetadataMap<'_>, Error> {
+    let mut map = MetadataMap(BTreeMap::new());
     for line in blob.split('\n').filter(|s| s != &"") {
         let pos = match line.bytes().position(|b| b == b':') {
             Some(p) => p,
@@ -66,10 +114,21 @@ pub fn parse_metadata(blob: &str) -> Result<MetadataMap, Error> {
     Ok(map)
 }
 
+#[test]
+fn test_metadatamap_ordered() {
+    // The same type with or without the `std` feature.
+    let map: &BTreeMap<&str, &str> =
+        &parse_metadata("Language: lt\nContent-Type: text/plain\n").unwrap();
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        [&"Content-Type", &"Language"]
+    );
+}
+
 #[test]
 fn test_metadatamap_charset() {
     {
-        let mut map = MetadataMap(HashMap::new());
+        let mut map = MetadataMap(BTreeMap::new());
         assert!(map.charset().is_none());
         map.insert("Content-Type", "");
         assert!(map.charset().is_none());
@@ -77,13 +136,35 @@ fn test_metadatamap_charset() {
         assert!(map.charset().is_none());
         map.insert("Content-Type", "text/plain; charset=utf-42");
         assert_eq!(map.charset().unwrap(), "utf-42");
+        map.insert("Content-Type", "text/plain; charset=");
+        assert!(map.charset().is_none());
+        // trailing parameters and whitespace
+        map.insert(
+            "Content-Type",
+            "text/plain; charset=ISO-8859-13 ; format=flowed",
+        );
+        assert_eq!(map.charset().unwrap(), "ISO-8859-13");
+        // quoted value and case-insensitive name
+        map.insert("Content-Type", "text/plain;CharSet=\"EUC-JP\"");
+        assert_eq!(map.charset().unwrap(), "EUC-JP");
+        map.insert(
+            "Content-Type",
+            "text/plain; x-note=\"a; charset=x\"; charset=GB18030",
+        );
+        assert_eq!(map.charset().unwrap(), "GB18030");
+        // a missing type
+        map.insert("Content-Type", "charset=UTF-8");
+        assert_eq!(map.charset().unwrap(), "UTF-8");
+        // only parameters are matched
+        map.insert("Content-Type", "charset; format=flowed");
+        assert!(map.charset().is_none());
     }
 }
 
 #[test]
 fn test_metadatamap_plural() {
     {
-        let mut map = MetadataMap(HashMap::new());
+        let mut map = MetadataMap(BTreeMap::new());
         assert_eq!(map.plural_forms(), (None, None));
 
         map.insert("Plural-Forms", "");
@@ -1,34 +1,33 @@
-use std::default::Default;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::default::Default;
+#[cfg(feature = "std")]
 use std::io;
 
-use byteorder::{BigEndian, ByteOrder, LittleEndian};
-use encoding::label::encoding_from_whatwg_label;
-use encoding::types::DecoderTrap::Strict;
-use encoding::types::EncodingRef;
-
-use crate::metadata::parse_metadata;
+use crate::decoder::{self, Decoder};
+use crate::metadata::{parse_metadata, MetadataMap};
 use crate::plurals::{Ast, Resolver};
 use crate::Error::{self, *};
 use crate::{Catalog, Message};
-
-#[allow(non_upper_case_globals)]
-static utf8_encoding: EncodingRef = &encoding::codec::utf_8::UTF8Encoding;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 
 /// ParseOptions allows setting options for parsing MO catalogs.
 ///
 /// # Examples
 /// ```ignore
 /// use std::fs::File;
-/// use encoding::all::ISO_8859_1;
+/// use encoding_rs::WINDOWS_1252;
 ///
 /// let file = File::open("french.mo").unwrap();
-/// let catalog = ParseOptions::new().force_encoding(ISO_8859_1).parse(file).unwrap();
+/// let catalog = ParseOptions::new().force_encoding(WINDOWS_1252).parse(file).unwrap();
 /// ```
 #[allow(missing_debug_implementations)]
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct ParseOptions {
-    force_encoding: Option<EncodingRef>,
-    force_plural: Option<fn(u64) -> usize>,
+    pub(crate) force_encoding: Option<Arc<dyn Decoder>>,
+    pub(crate) force_plural: Option<fn(u64) -> usize>,
+    pub(crate) include_fuzzy: bool,
 }
 
 impl ParseOptions {
@@ -38,17 +37,34 @@ impl ParseOptions {
     }
 
     /// Tries to parse the catalog from the given reader using the specified options.
+    #[cfg(feature = "std")]
     pub fn parse<R: io::Read>(self, reader: R) -> Result<Catalog, Error> {
         parse_catalog(reader, self)
     }
 
+    /// Tries to parse the catalog from the given MO file in a byte slice
+    /// using the specified options.
+    pub fn parse_bytes(self, bytes: &[u8]) -> Result<Catalog, Error> {
+        parse_catalog_bytes(bytes, self)
+    }
+
+    /// Tries to parse the catalog from the given reader of a PO file
+    /// using the specified options.
+    #[cfg(feature = "std")]
+    pub fn parse_po<R: io::Read>(self, reader: R) -> Result<Catalog, Error> {
+        crate::po::parse_po(reader, self)
+    }
+
     /// Forces a use of a specific encoding
     /// when parsing strings from a catalog.
     /// If this option is not enabled,
     /// the parser tries to use the encoding specified in the metadata
     /// or UTF-8 if metadata is non-existent.
-    pub fn force_encoding(mut self, encoding: EncodingRef) -> Self {
-        self.force_encoding = Some(encoding);
+    ///
+    /// The encoding can be one of `encoding_rs`, e.g. `encoding_rs::WINDOWS_1257`,
+    /// or any other implementation of `Decoder`.
+    pub fn force_encoding<D: Decoder + 'static>(mut self, encoding: D) -> Self {
+        self.force_encoding = Some(Arc::new(encoding));
         self
     }
 
@@ -61,6 +77,16 @@ impl ParseOptions {
         self.force_plural = Some(plural);
         self
     }
+
+    /// Whether the translations marked as fuzzy in a PO file
+    /// should be included in the catalog.
+    /// By default they are left out, like `msgfmt` does,
+    /// as they have not been reviewed by a translator.
+    /// Either way, `Catalog::is_fuzzy` tells which messages were fuzzy.
+    pub fn include_fuzzy(mut self, include_fuzzy: bool) -> Self {
+        self.include_fuzzy = include_fuzzy;
+        self
+    }
 }
 
 /// According to the given magic number of a MO file,
@@ -75,9 +101,15 @@ fn get_read_u32_fn(magic: &[u8]) -> Option<fn(&[u8]) -> u32> {
     }
 }
 
+#[cfg(feature = "std")]
 pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Catalog, Error> {
     let mut contents = vec![];
-    let n = file.read_to_end(&mut contents)?;
+    file.read_to_end(&mut contents)?;
+    parse_catalog_bytes(&contents, opts)
+}
+
+fn parse_catalog_bytes(contents: &[u8], opts: ParseOptions) -> Result<Catalog, Error> {
+    let n = contents.len();
     if n < 28 {
         return Err(Eof);
     }
@@ -92,11 +124,8 @@ pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Cat
         return Err(Eof);
     }
 
-    let mut catalog = Catalog::new();
-    if let Some(f) = opts.force_plural {
-        catalog.resolver = Resolver::Function(f);
-    }
-    let mut encoding = opts.force_encoding.unwrap_or(utf8_encoding);
+    let mut catalog = new_catalog(&opts);
+    let mut encoding = initial_encoding(&opts);
 
     for i in 0..num_strings {
         // Parse the original string
@@ -115,20 +144,20 @@ pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Cat
             Some(idx) => {
                 let ctx = &original[..idx];
                 original = &original[idx + 1..];
-                Some(encoding.decode(ctx, Strict)?)
+                Some(decode(&*encoding, ctx)?)
             }
             None => None,
         };
-        // extract msg_id singular, ignoring the plural
-        let id = match original
-            .iter()
-            .position(|x| *x == 0)
-            .map(|i| &original[..i])
-        {
-            Some(b) => encoding.decode(b, Strict)?,
+        // extract msg_id singular and the optional plural
+        let (id, plural) = match original.iter().position(|x| *x == 0) {
+            Some(i) if i + 1 < original.len() => (
+                decode(&*encoding, &original[..i])?,
+                Some(decode(&*encoding, &original[i + 1..original.len() - 1])?),
+            ),
+            Some(i) => (decode(&*encoding, &original[..i])?, None),
             None => return Err(Eof),
         };
-        if id == "" && i != 0 {
+        if id.is_empty() && i != 0 {
             return Err(MisplacedMetadata);
         }
 
@@ -144,21 +173,17 @@ pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Cat
         }
         let translated = contents[off..off + len]
             .split(|x| *x == 0)
-            .map(|b| encoding.decode(b, Strict))
+            .map(|b| decode(&*encoding, b))
             .collect::<Result<Vec<_>, _>>()?;
-        if id == "" {
-            let map = parse_metadata(&*translated[0])?;
-            if let (Some(c), None) = (map.charset(), opts.force_encoding) {
-                encoding = encoding_from_whatwg_label(c).ok_or(UnknownEncoding)?;
-            }
-            if opts.force_plural.is_none() {
-                if let Some(p) = map.plural_forms().1 {
-                    catalog.resolver = Ast::parse(p).map(Resolver::Expr)?;
-                }
+        if id.is_empty() {
+            let map = parse_metadata(&translated[0])?;
+            if let Some(e) = metadata_encoding(&map, &opts)? {
+                encoding = e;
             }
+            apply_metadata(&mut catalog, &map, &opts)?;
         }
 
-        catalog.insert(Message::new(id, context, translated));
+        catalog.insert(Message::new(id, context, translated).with_plural(plural));
 
         off_otable += 8;
         off_ttable += 8;
@@ -167,6 +192,51 @@ pub fn parse_catalog<R: io::Read>(mut file: R, opts: ParseOptions) -> Result<Cat
     Ok(catalog)
 }
 
+/// Creates the catalog to be filled by a parser, honoring the forced plural formula.
+pub(crate) fn new_catalog(opts: &ParseOptions) -> Catalog {
+    let mut catalog = Catalog::new();
+    if let Some(f) = opts.force_plural {
+        catalog.resolver = Resolver::Function(f);
+    }
+    catalog
+}
+
+/// Returns the encoding to use for strings preceding the metadata.
+pub(crate) fn initial_encoding(opts: &ParseOptions) -> Arc<dyn Decoder> {
+    opts.force_encoding.clone().unwrap_or_else(decoder::utf8)
+}
+
+/// Decodes a string of the catalog with the given encoding.
+pub(crate) fn decode(encoding: &dyn Decoder, bytes: &[u8]) -> Result<String, Error> {
+    encoding.decode(bytes).ok_or(DecodingError)
+}
+
+/// Applies the metadata found in the catalog header to the catalog being parsed.
+pub(crate) fn apply_metadata(
+    catalog: &mut Catalog,
+    map: &MetadataMap,
+    opts: &ParseOptions,
+) -> Result<(), Error> {
+    if opts.force_plural.is_none() {
+        if let Some(p) = map.plural_forms().1 {
+            catalog.resolver = Ast::parse(p).map(Resolver::Expr)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the encoding specified in the metadata,
+/// unless a specific encoding was forced.
+pub(crate) fn metadata_encoding(
+    map: &MetadataMap,
+    opts: &ParseOptions,
+) -> Result<Option<Arc<dyn Decoder>>, Error> {
+    match (map.charset(), &opts.force_encoding) {
+        (Some(c), None) => decoder::for_label(c).map(Some).ok_or(UnknownEncoding),
+        _ => Ok(None),
+    }
+}
+
 /// The default plural resolver.
 ///
 /// It will be used if not `Plural-Forms` header is found in the .mo file, and if
@@ -201,6 +271,7 @@ fn test_get_read_u32_fn() {
 }
 
 #[test]
+#[cfg(feature = "std")]
 fn test_parse_catalog() {
     macro_rules! assert_variant {
         ($value:expr, $variant:path) => {
@@ -246,6 +317,7 @@ fn test_parse_catalog() {
         assert_eq!(
             catalog.strings["this is context\x04Text"],
             Message::new("Text", Some("this is context"), vec!["Tekstas", "Tekstai"])
+                .with_plural(Some("Texts"))
         );
     }
 
@@ -256,6 +328,7 @@ fn test_parse_catalog() {
         assert_eq!(
             catalog.strings["Image"],
             Message::new("Image", None, vec!["Nuotrauka", "Nuotraukos"])
+                .with_plural(Some("Images"))
         );
     }
 
@@ -265,3 +338,20 @@ fn test_parse_catalog() {
         assert_variant!(err, DecodingError);
     }
 }
+
+#[test]
+fn test_parse_catalog_bytes() {
+    let bytes = include_bytes!("../test_cases/1.mo");
+    let catalog = parse_catalog_bytes(bytes, ParseOptions::new()).unwrap();
+    assert_eq!(catalog.pgettext("this is context", "Text"), "Tekstas");
+
+    assert!(matches!(
+        parse_catalog_bytes(&bytes[..27], ParseOptions::new()),
+        Err(Eof)
+    ));
+    let bytes = include_bytes!("../test_cases/invalid_utf8.mo");
+    assert!(matches!(
+        parse_catalog_bytes(bytes, ParseOptions::new()),
+        Err(DecodingError)
+    ));
+}
@@ -1,3 +1,6 @@
+use alloc::boxed::Box;
+use core::fmt;
+
 use crate::Error;
 
 use self::Resolver::*;
@@ -43,6 +46,7 @@ fn index_of(src: &str, pat: &str) -> Option<usize> {
 }
 
 use self::Ast::*;
+/// A parsed plural formula.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Ast {
     /// A ternary expression
@@ -60,55 +64,82 @@ pub enum Ast {
     Not(Box<Ast>),
 }
 
+/// A binary operator of a plural formula.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Operator {
+    /// `==`
     Equal,
+    /// `!=`
     NotEqual,
+    /// `>=`
     GreaterOrEqual,
+    /// `<=`
     SmallerOrEqual,
+    /// `>`
     Greater,
+    /// `<`
     Smaller,
+    /// `&&`
     And,
+    /// `||`
     Or,
+    /// `%`
     Modulo,
 }
 
 impl Ast {
+    /// Returns the plural form for `n`, or 0 if the formula divides by zero.
     fn resolve(&self, n: u64) -> usize {
-        match *self {
+        self.checked_resolve(n).unwrap_or(0)
+    }
+
+    /// Returns the plural form for `n`, or `None` if the formula divides by zero.
+    fn checked_resolve(&self, n: u64) -> Option<usize> {
+        Some(match *self {
             Ternary(ref cond, ref ok, ref nok) => {
-                if cond.resolve(n) == 0 {
-                    nok.resolve(n)
+                if cond.checked_resolve(n)? == 0 {
+                    nok.checked_resolve(n)?
                 } else {
-                    ok.resolve(n)
+                    ok.checked_resolve(n)?
                 }
             }
             N => n as usize,
             Integer(x) => x as usize,
-            Op(ref op, ref lhs, ref rhs) => match *op {
-                Operator::Equal => (lhs.resolve(n) == rhs.resolve(n)) as usize,
-                Operator::NotEqual => (lhs.resolve(n) != rhs.resolve(n)) as usize,
-                Operator::GreaterOrEqual => (lhs.resolve(n) >= rhs.resolve(n)) as usize,
-                Operator::SmallerOrEqual => (lhs.resolve(n) <= rhs.resolve(n)) as usize,
-                Operator::Greater => (lhs.resolve(n) > rhs.resolve(n)) as usize,
-                Operator::Smaller => (lhs.resolve(n) < rhs.resolve(n)) as usize,
-                Operator::And => (lhs.resolve(n) != 0 && rhs.resolve(n) != 0) as usize,
-                Operator::Or => (lhs.resolve(n) != 0 || rhs.resolve(n) != 0) as usize,
-                Operator::Modulo => lhs.resolve(n) % rhs.resolve(n),
-            },
-            Not(ref val) => match val.resolve(n) {
+            Op(ref op, ref lhs, ref rhs) => {
+                let (lhs, rhs) = (lhs.checked_resolve(n)?, rhs.checked_resolve(n)?);
+                match *op {
+                    Operator::Equal => (lhs == rhs) as usize,
+                    Operator::NotEqual => (lhs != rhs) as usize,
+                    Operator::GreaterOrEqual => (lhs >= rhs) as usize,
+                    Operator::SmallerOrEqual => (lhs <= rhs) as usize,
+                    Operator::Greater => (lhs > rhs) as usize,
+                    Operator::Smaller => (lhs < rhs) as usize,
+                    Operator::And => (lhs != 0 && rhs != 0) as usize,
+                    Operator::Or => (lhs != 0 || rhs != 0) as usize,
+                    Operator::Modulo => lhs.checked_rem(rhs)?,
+                }
+            }
+            Not(ref val) => match val.checked_resolve(n)? {
                 0 => 1,
                 _ => 0,
             },
-        }
+        })
+    }
+
+    /// Returns whether the formula divides by zero for any `n` below 1000,
+    /// which `msgfmt` rejects such formulas for.
+    #[cfg(any(feature = "serde", test))]
+    pub(crate) fn divides_by_zero(&self) -> bool {
+        (0..1000).any(|n| self.checked_resolve(n).is_none())
     }
 
+    /// Parses a plural formula, as found in the `Plural-Forms` header.
     pub fn parse(src: &str) -> Result<Ast, Error> {
         Self::parse_parens(src.trim())
     }
 
     fn parse_parens(src: &str) -> Result<Ast, Error> {
-        if src.starts_with('(') {
+        if src.len() > 1 && src.starts_with('(') {
             let end = src[1..src.len() - 1]
                 .chars()
                 .fold((1, 2), |(level, index), ch| match (level, ch) {
@@ -261,7 +292,7 @@ impl Ast {
     }
 
     fn parse_int(src: &str) -> Result<Ast, Error> {
-        if let Ok(x) = u64::from_str_radix(src, 10) {
+        if let Ok(x) = src.parse::<u64>() {
             Ok(Ast::Integer(x))
         } else {
             Self::parse_n(src.trim())
@@ -277,6 +308,33 @@ impl Ast {
     }
 }
 
+/// Formats the formula in the syntax of the `Plural-Forms` header,
+/// with every operation parenthesized, so that it parses back to the same `Ast`.
+impl fmt::Display for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ternary(ref cond, ref ok, ref nok) => write!(f, "({} ? {} : {})", cond, ok, nok),
+            N => f.write_str("n"),
+            Integer(x) => write!(f, "{}", x),
+            Op(ref op, ref lhs, ref rhs) => {
+                let op = match *op {
+                    Operator::Equal => "==",
+                    Operator::NotEqual => "!=",
+                    Operator::GreaterOrEqual => ">=",
+                    Operator::SmallerOrEqual => "<=",
+                    Operator::Greater => ">",
+                    Operator::Smaller => "<",
+                    Operator::And => "&&",
+                    Operator::Or => "||",
+                    Operator::Modulo => "%",
+                };
+                write!(f, "({} {} {})", lhs, op, rhs)
+            }
+            Not(ref val) => write!(f, "!{}", val),
+        }
+    }
+}
+
 impl Resolver {
     /// Returns the number of the correct plural form
     /// for `n` objects, as defined by the rule contained in this resolver.
@@ -345,5 +403,32 @@ mod tests {
 
         let ru_plural = "((n%10==1 && n%100!=11) ? 0 : ((n%10 >= 2 && n%10 <=4 && (n%100 < 12 || n%100 > 14)) ? 1 : ((n%10 == 0 || (n%10 >= 5 && n%10 <=9)) || (n%100 >= 11 && n%100 <= 14)) ? 2 : 3))";
         assert!(Ast::parse(ru_plural).is_ok());
+
+        assert!(Ast::parse("(").is_err());
+        assert!(Ast::parse("()").is_err());
+    }
+
+    #[test]
+    fn test_divides_by_zero() {
+        let ast = Ast::parse("n % 0").unwrap();
+        assert!(ast.divides_by_zero());
+        assert_eq!(ast.resolve(5), 0);
+        assert!(Ast::parse("n % (n % 2)").unwrap().divides_by_zero());
+        assert!(!Ast::parse("n % 10 == 1 ? 0 : 1").unwrap().divides_by_zero());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Ast::parse("n != 1").unwrap().to_string(), "(n != 1)");
+        for formula in &[
+            "0",
+            "n > 1",
+            "!(n % 10)",
+            "(n == 1 || n == 2) ? 0 : 1",
+            "((n%10==1 && n%100!=11) ? 0 : ((n%10 >= 2 && n%10 <=4 && (n%100 < 12 || n%100 > 14)) ? 1 : ((n%10 == 0 || (n%10 >= 5 && n%10 <=9)) || (n%100 >= 11 && n%100 <= 14)) ? 2 : 3))",
+        ] {
+            let ast = Ast::parse(formula).unwrap();
+            assert_eq!(Ast::parse(&ast.to_string()).unwrap(), ast, "{}", ast);
+        }
     }
 }
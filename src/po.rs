@@ -0,0 +1,1138 @@
+//! Reading and writing textual PO and POT files.
+//!
+//! Besides parsing PO files straight into a `Catalog`
+//! (see `Catalog::parse_po`),
+//! this module provides a document model of a PO file
+//! used by the tools that produce or edit them.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::sync::Arc;
+
+pub use crate::merge::MergeOptions;
+
+use crate::decoder::{self, Decoder};
+use crate::metadata::parse_metadata;
+use crate::parser::{
+    apply_metadata, decode, initial_encoding, metadata_encoding, new_catalog, ParseOptions,
+};
+use crate::writer::{encode, message_location, utf8_header, with_charset};
+use crate::Error::{self, *};
+use crate::{check, cldr, stats, Catalog, FormatMismatch, FormatSyntax, Message, Stats};
+
+/// PoFile represents the contents of a PO or POT file.
+///
+/// The header is stored as an entry with an empty `id`,
+/// normally the first one.
+///
+/// A parsed file remembers its original text,
+/// so that the entries which have not been modified are written out exactly as they were,
+/// and writing an unmodified file reproduces it byte for byte.
+#[derive(Clone, Default)]
+pub struct PoFile {
+    /// The entries of the file, in order.
+    pub entries: Vec<PoEntry>,
+    original: Option<Original>,
+}
+
+/// The text of a parsed PO file.
+#[derive(Clone)]
+struct Original {
+    /// Each entry as it was parsed, with the text it was parsed from,
+    /// including the blank lines preceding it.
+    entries: Vec<(PoEntry, Vec<u8>)>,
+    /// The text following the last entry.
+    trailer: Vec<u8>,
+    encoding: Arc<dyn Decoder>,
+}
+
+/// PoEntry represents a single message of a PO file
+/// along with the comments attached to it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoEntry {
+    /// Comments by translators (`# `).
+    pub translator_comments: Vec<String>,
+    /// Comments extracted from the source code (`#.`).
+    pub extracted_comments: Vec<String>,
+    /// Source code references (`#:`), e.g. `src/main.rs:42`.
+    pub references: Vec<String>,
+    /// Flags (`#,`), e.g. `fuzzy` or `c-format`.
+    pub flags: Vec<String>,
+    /// The context the translation was made for (`#| msgctxt`),
+    /// if it has been carried over from a different message.
+    pub previous_context: Option<String>,
+    /// The original string the translation was made for (`#| msgid`),
+    /// if it has been carried over from a different message.
+    pub previous_id: Option<String>,
+    /// The original plural string the translation was made for (`#| msgid_plural`).
+    pub previous_id_plural: Option<String>,
+    /// Whether the message is no longer used by the program (`#~`).
+    pub obsolete: bool,
+    /// The context of the message (`msgctxt`).
+    pub context: Option<String>,
+    /// The original string (`msgid`).
+    pub id: String,
+    /// The original plural string (`msgid_plural`).
+    pub id_plural: Option<String>,
+    /// The translated strings (`msgstr` or `msgstr[N]`).
+    pub translations: Vec<String>,
+}
+
+impl PoFile {
+    /// Creates an empty PO file.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Parses a PO file from the given reader.
+    ///
+    /// The strings are decoded using the charset specified in the header,
+    /// or UTF-8 if the header is non-existent.
+    pub fn parse<R: io::Read>(reader: R) -> Result<Self, Error> {
+        parse_po_file(reader, &ParseOptions::new())
+    }
+
+    /// Creates a PO file with the messages of a catalog, e.g. one parsed from an MO file.
+    ///
+    /// The header entry comes first, with its charset changed to UTF-8,
+    /// and the rest of the entries are sorted by context and then by original string.
+    pub fn from_catalog(catalog: &Catalog) -> Self {
+        let mut entries = catalog
+            .messages()
+            .map(|msg| {
+                let mut entry = PoEntry::new(msg.id());
+                entry.context = msg.context().map(str::to_owned);
+                entry.id_plural = msg.id_plural().map(str::to_owned);
+                entry.translations = msg.translations().to_vec();
+                if entry.id.is_empty() && entry.context.is_none() {
+                    entry.translations =
+                        entry.translations.iter().map(|t| utf8_header(t)).collect();
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+        PoFile {
+            entries,
+            original: None,
+        }
+    }
+
+    /// Returns the header entry, if the file has one.
+    pub fn header(&self) -> Option<&PoEntry> {
+        self.entries.iter().find(|e| e.is_header())
+    }
+
+    /// Returns the number of translated, fuzzy, untranslated and obsolete entries.
+    pub fn stats(&self) -> Stats {
+        stats::po_stats(self)
+    }
+
+    /// Checks that the translations of the entries flagged with a known format
+    /// (e.g. `c-format` or `rust-format`) use the same directives as the original strings,
+    /// like `msgfmt --check-format` does.
+    ///
+    /// The translations of plural entries are compared against the plural original.
+    /// Only the forms which the `Plural-Forms` of the header use for a single number
+    /// may leave out directives, e.g. the number in the form for `n == 1`.
+    pub fn check_format(&self) -> Vec<FormatMismatch> {
+        check::check_po(self)
+    }
+
+    /// Converts the file into a catalog.
+    ///
+    /// Just like `msgfmt`, the untranslated and fuzzy entries are left out.
+    /// Returns the `Err` variant upon an invalid header.
+    pub fn to_catalog(&self) -> Result<Catalog, Error> {
+        self.build_catalog(&ParseOptions::new())
+    }
+
+    /// Converts the file into a catalog using the specified options.
+    ///
+    /// The encoding option is ignored, as the strings have been decoded already.
+    pub fn to_catalog_with_options(&self, opts: &ParseOptions) -> Result<Catalog, Error> {
+        self.build_catalog(opts)
+    }
+
+    /// Writes the file out in the PO syntax.
+    ///
+    /// The entries which are unchanged since the file was parsed
+    /// are written out in their original form.
+    /// Strings of the other entries are split into lines after each embedded newline,
+    /// but long lines are not wrapped,
+    /// and they are encoded in the charset the file was parsed with, or UTF-8.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_encoded(writer, None).map_err(|e| match e {
+            Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    /// Writes the file out in the PO syntax, with the strings in the given encoding
+    /// or else in the one the file was parsed with.
+    ///
+    /// If the encoding changes, so does the charset in the header,
+    /// and the original form of the unchanged entries is converted to it.
+    pub(crate) fn write_encoded<W: Write>(
+        &self,
+        mut writer: W,
+        encoding: Option<&dyn Decoder>,
+    ) -> Result<(), Error> {
+        let utf8 = decoder::utf8();
+        let source = self.original.as_ref().map_or(&*utf8, |o| &*o.encoding);
+        let target = encoding.unwrap_or(source);
+        let convert = !source.name().eq_ignore_ascii_case(target.name());
+
+        let mut converted;
+        let mut entries = &self.entries;
+        if convert {
+            converted = self.entries.clone();
+            match converted.iter_mut().find(|e| e.is_header()) {
+                Some(header) => {
+                    for t in &mut header.translations {
+                        *t = with_charset(t, target.name());
+                    }
+                }
+                None if !decoder::is_utf8(target) => {
+                    let mut header = PoEntry::new("");
+                    header.translations = vec![with_charset("", target.name())];
+                    converted.insert(0, header);
+                }
+                None => {}
+            }
+            entries = &converted;
+        }
+        // Converts a text of the original file to the target encoding.
+        let convert_text = |text: &[u8], location: &dyn Fn() -> String| {
+            if convert {
+                encode(target, &decode(source, text)?, location)
+            } else {
+                Ok(text.to_vec())
+            }
+        };
+
+        let mut unused = match self.original {
+            Some(ref original) => {
+                let mut unused = HashMap::new();
+                for (i, (entry, _)) in original.entries.iter().enumerate() {
+                    unused
+                        .entry((&entry.context, &entry.id, entry.obsolete))
+                        .or_insert_with(Vec::new)
+                        .push(i);
+                }
+                unused
+            }
+            None => HashMap::new(),
+        };
+        // The index of the original entry written last, if any.
+        let mut previous = None;
+
+        for (i, entry) in entries.iter().enumerate() {
+            let reused = self.original.as_ref().and_then(|original| {
+                let indices = unused.get_mut(&(&entry.context, &entry.id, entry.obsolete))?;
+                let pos = indices
+                    .iter()
+                    .position(|&j| original.entries[j].0 == *entry)?;
+                let j = indices.remove(pos);
+                Some((j, &original.entries[j].1))
+            });
+            match reused {
+                Some((j, text)) => {
+                    let in_order = j == 0 && i == 0 || j != 0 && previous == Some(j - 1);
+                    if i != 0 && !in_order && !text.starts_with(b"\n") {
+                        writeln!(writer)?;
+                    }
+                    writer.write_all(&convert_text(text, &|| entry.location())?)?;
+                    previous = Some(j);
+                }
+                None => {
+                    let mut text = vec![];
+                    if i != 0 {
+                        writeln!(text)?;
+                    }
+                    entry.write(&mut text)?;
+                    let text = String::from_utf8(text).unwrap();
+                    writer.write_all(&encode(target, &text, || entry.location())?)?;
+                    previous = None;
+                }
+            }
+        }
+        if let Some(ref original) = self.original {
+            let location = || "the end of the file".to_owned();
+            writer.write_all(&convert_text(&original.trailer, &location)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PoFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoFile")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+/// Files are equal if their entries are, regardless of their original text.
+impl PartialEq for PoFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl PoEntry {
+    /// Creates an entry for the given original string, without a translation.
+    pub fn new<T: Into<String>>(id: T) -> Self {
+        PoEntry {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a header entry for files converted from formats without one,
+    /// with the UTF-8 charset and the plural forms of the language, if known.
+    pub(crate) fn header_for(language: Option<&str>) -> Self {
+        let mut header = String::new();
+        if let Some(language) = language {
+            header.push_str(&format!("Language: {}\n", language));
+        }
+        header.push_str("Content-Type: text/plain; charset=UTF-8\n");
+        if let Some(plural_forms) = language.and_then(cldr::plural_forms) {
+            header.push_str(&format!("Plural-Forms: {}\n", plural_forms));
+        }
+        let mut entry = PoEntry::new("");
+        entry.translations = vec![header];
+        entry
+    }
+
+    /// Describes where the entry is for error messages,
+    /// e.g. `msgid "Garlic" (src/main.rs:42)`.
+    fn location(&self) -> String {
+        let location = message_location(self.context.as_deref(), &self.id);
+        match self
+            .references
+            .iter()
+            .flat_map(|r| r.split_whitespace())
+            .next()
+        {
+            Some(reference) => format!("{} ({})", location, reference),
+            None => location,
+        }
+    }
+
+    /// Returns whether this is the header entry,
+    /// i.e. a message with an empty original string and no context.
+    pub fn is_header(&self) -> bool {
+        self.id.is_empty() && self.context.is_none() && !self.obsolete
+    }
+
+    /// Returns whether the entry has the given flag.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Returns the syntax of the format directives in this entry,
+    /// as given by a flag like `c-format`.
+    pub fn format_syntax(&self) -> Option<FormatSyntax> {
+        check::entry_syntax(self)
+    }
+
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for comment in &self.translator_comments {
+            if comment.is_empty() {
+                writeln!(w, "#")?;
+            } else {
+                writeln!(w, "# {}", comment)?;
+            }
+        }
+        for comment in &self.extracted_comments {
+            writeln!(w, "#. {}", comment)?;
+        }
+        write_references(w, &self.references)?;
+        if !self.flags.is_empty() {
+            writeln!(w, "#, {}", self.flags.join(", "))?;
+        }
+
+        let (prefix, previous) = if self.obsolete {
+            ("#~ ", "#~| ")
+        } else {
+            ("", "#| ")
+        };
+        if let Some(ref context) = self.previous_context {
+            write_keyword(w, previous, "msgctxt", context)?;
+        }
+        if let Some(ref id) = self.previous_id {
+            write_keyword(w, previous, "msgid", id)?;
+        }
+        if let Some(ref plural) = self.previous_id_plural {
+            write_keyword(w, previous, "msgid_plural", plural)?;
+        }
+
+        if let Some(ref context) = self.context {
+            write_keyword(w, prefix, "msgctxt", context)?;
+        }
+        write_keyword(w, prefix, "msgid", &self.id)?;
+        match self.id_plural {
+            Some(ref plural) => {
+                write_keyword(w, prefix, "msgid_plural", plural)?;
+                let forms = self.translations.len().max(2);
+                for i in 0..forms {
+                    let translated = self.translations.get(i).map_or("", |s| s.as_str());
+                    write_keyword(w, prefix, &format!("msgstr[{}]", i), translated)?;
+                }
+            }
+            None => {
+                let translated = self.translations.first().map_or("", |s| s.as_str());
+                write_keyword(w, prefix, "msgstr", translated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the references, wrapping them at 79 columns like xgettext does.
+fn write_references<W: Write>(w: &mut W, references: &[String]) -> io::Result<()> {
+    let mut line = String::new();
+    for reference in references {
+        if !line.is_empty() && line.len() + 1 + reference.len() > 79 {
+            writeln!(w, "{}", line)?;
+            line.clear();
+        }
+        line.push_str(if line.is_empty() { "#: " } else { " " });
+        line.push_str(reference);
+    }
+    if !line.is_empty() {
+        writeln!(w, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Writes a keyword line, with every line prefixed by `prefix`,
+/// e.g. `#~ ` for obsolete entries.
+fn write_keyword<W: Write>(w: &mut W, prefix: &str, keyword: &str, value: &str) -> io::Result<()> {
+    let lines = value.split_inclusive('\n').collect::<Vec<_>>();
+    if lines.len() > 1 {
+        writeln!(w, "{}{} \"\"", prefix, keyword)?;
+        for line in lines {
+            writeln!(w, "{}\"{}\"", prefix, escape(line))?;
+        }
+        Ok(())
+    } else {
+        writeln!(w, "{}{} \"{}\"", prefix, keyword, escape(value))
+    }
+}
+
+/// Escapes a string for use inside a C-style quoted string.
+fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\x07' => result.push_str("\\a"),
+            '\x08' => result.push_str("\\b"),
+            '\x0b' => result.push_str("\\v"),
+            '\x0c' => result.push_str("\\f"),
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_ascii_control() => result.push_str(&format!("\\{:03o}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// A single entry of a PO file, with its strings unescaped but not yet decoded.
+#[derive(Debug, Default)]
+struct RawEntry {
+    translator_comments: Vec<Vec<u8>>,
+    extracted_comments: Vec<Vec<u8>>,
+    references: Vec<Vec<u8>>,
+    flags: Vec<Vec<u8>>,
+    previous_context: Option<Vec<u8>>,
+    previous_id: Option<Vec<u8>>,
+    previous_plural: Option<Vec<u8>>,
+    obsolete: bool,
+    context: Option<Vec<u8>>,
+    id: Option<Vec<u8>>,
+    plural: Option<Vec<u8>>,
+    translated: Vec<Vec<u8>>,
+    /// The offset of the end of the last line belonging to the entry.
+    end: usize,
+}
+
+impl RawEntry {
+    fn is_empty(&self) -> bool {
+        self.context.is_none() && self.id.is_none()
+    }
+
+    fn is_header(&self) -> bool {
+        self.context.is_none() && self.id.as_ref().is_some_and(|id| id.is_empty()) && !self.obsolete
+    }
+}
+
+/// The string which continuation lines are appended to.
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    PreviousContext,
+    PreviousId,
+    PreviousPlural,
+    Context,
+    Id,
+    Plural,
+    Translated(usize),
+}
+
+pub(crate) fn parse_po<R: io::Read>(reader: R, opts: ParseOptions) -> Result<Catalog, Error> {
+    parse_po_file(reader, &opts)?.build_catalog(&opts)
+}
+
+fn parse_po_file<R: io::Read>(mut reader: R, opts: &ParseOptions) -> Result<PoFile, Error> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    let entries = parse_entries(&contents)?;
+
+    let mut encoding = initial_encoding(opts);
+    if let Some(header) = entries.iter().find(|e| e.is_header()) {
+        let header = decode(&*encoding, &header.translated[0])?;
+        if let Some(e) = metadata_encoding(&parse_metadata(&header)?, opts)? {
+            encoding = e;
+        }
+    }
+
+    let decode = |b: &[u8]| decode(&*encoding, b);
+    let decode_all = |v: &[Vec<u8>]| v.iter().map(|b| decode(b)).collect::<Result<Vec<_>, _>>();
+    let parsed = entries
+        .iter()
+        .map(|e| {
+            Ok(PoEntry {
+                translator_comments: decode_all(&e.translator_comments)?,
+                extracted_comments: decode_all(&e.extracted_comments)?,
+                references: decode_all(&e.references)?,
+                flags: decode_all(&e.flags)?,
+                previous_context: e.previous_context.as_deref().map(decode).transpose()?,
+                previous_id: e.previous_id.as_deref().map(decode).transpose()?,
+                previous_id_plural: e.previous_plural.as_deref().map(decode).transpose()?,
+                obsolete: e.obsolete,
+                context: e.context.as_deref().map(decode).transpose()?,
+                id: decode(e.id.as_deref().unwrap_or_default())?,
+                id_plural: e.plural.as_deref().map(decode).transpose()?,
+                translations: decode_all(&e.translated)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut start = 0;
+    let mut original = Original {
+        entries: Vec::with_capacity(parsed.len()),
+        trailer: vec![],
+        encoding,
+    };
+    for (entry, raw) in parsed.iter().zip(&entries) {
+        original
+            .entries
+            .push((entry.clone(), contents[start..raw.end].to_vec()));
+        start = raw.end;
+    }
+    original.trailer = contents[start..].to_vec();
+    Ok(PoFile {
+        entries: parsed,
+        original: Some(original),
+    })
+}
+
+impl PoFile {
+    fn build_catalog(&self, opts: &ParseOptions) -> Result<Catalog, Error> {
+        let mut catalog = new_catalog(opts);
+        if let Some(header) = self.header() {
+            let header = header.translations.first().map_or("", |s| s.as_str());
+            apply_metadata(&mut catalog, &parse_metadata(header)?, opts)?;
+        }
+
+        for entry in &self.entries {
+            // Untranslated messages, including plurals missing any of their forms
+            // as `Stats` counts them, are left out so that lookups fall back
+            // to the original strings, and the fuzzy flag means nothing for them.
+            if entry.obsolete
+                || entry.translations.is_empty()
+                || entry.translations.iter().any(|t| t.is_empty())
+            {
+                continue;
+            }
+            // The header is kept even if it is fuzzy, like msgfmt does.
+            let fuzzy = entry.has_flag("fuzzy") && !entry.is_header();
+            if fuzzy {
+                catalog.fuzzy.insert(
+                    (entry.context.clone(), entry.id.clone()),
+                    entry.id_plural.is_some(),
+                );
+                if !opts.include_fuzzy {
+                    continue;
+                }
+            }
+            let message = Message::new(
+                entry.id.clone(),
+                entry.context.clone(),
+                entry.translations.clone(),
+            );
+            catalog.insert(message.with_plural(entry.id_plural.clone()));
+        }
+        Ok(catalog)
+    }
+}
+
+/// Splits the contents of a PO file into entries.
+fn parse_entries(contents: &[u8]) -> Result<Vec<RawEntry>, Error> {
+    let mut entries = vec![];
+    let mut entry = RawEntry::default();
+    let mut field = None;
+    let mut line_end = 0;
+
+    for (i, line) in contents.split(|b| *b == b'\n').enumerate() {
+        let line_no = i + 1;
+        line_end = (line_end + line.len() + 1).min(contents.len());
+        let mut line = trim(line);
+        if line.is_empty() {
+            field = None;
+            continue;
+        }
+
+        // Obsolete entries have their keyword lines commented out with `#~`,
+        // and previous strings of fuzzy entries with `#|` (or `#~|`).
+        let obsolete = line.starts_with(b"#~");
+        if obsolete {
+            line = trim(&line[2..]);
+        }
+        let previous = line.starts_with(b"#|") || obsolete && line.starts_with(b"|");
+        if previous {
+            line = trim(&line[if obsolete { 1 } else { 2 }..]);
+        }
+        if line.starts_with(b"#") || (obsolete || previous) && line.is_empty() {
+            field = None;
+            if !entry.translated.is_empty() {
+                entries.push(mem::take(&mut entry));
+            }
+            parse_comment(&mut entry, line);
+            entry.end = line_end;
+            continue;
+        }
+        if previous && !entry.translated.is_empty() {
+            entries.push(mem::take(&mut entry));
+        }
+
+        let pos = line
+            .iter()
+            .position(|b| b.is_ascii_whitespace() || *b == b'"')
+            .unwrap_or(line.len());
+        let (keyword, value) = line.split_at(pos);
+        let value = unquote(trim(value)).ok_or(PoSyntax(line_no))?;
+
+        match keyword {
+            b"msgctxt" | b"msgid" if !previous && !entry.translated.is_empty() => {
+                entries.push(mem::take(&mut entry));
+            }
+            _ => (),
+        }
+        if obsolete && !previous {
+            entry.obsolete = true;
+        }
+        field = match keyword {
+            b"msgctxt" if previous && entry.previous_context.is_none() && entry.is_empty() => {
+                entry.previous_context = Some(value);
+                Some(Field::PreviousContext)
+            }
+            b"msgid" if previous && entry.previous_id.is_none() && entry.is_empty() => {
+                entry.previous_id = Some(value);
+                Some(Field::PreviousId)
+            }
+            b"msgid_plural" if previous && entry.previous_plural.is_none() && entry.is_empty() => {
+                entry.previous_plural = Some(value);
+                Some(Field::PreviousPlural)
+            }
+            _ if previous && !keyword.is_empty() => return Err(PoSyntax(line_no)),
+            b"msgctxt" if entry.is_empty() => {
+                entry.context = Some(value);
+                Some(Field::Context)
+            }
+            b"msgid" if entry.id.is_none() => {
+                entry.id = Some(value);
+                Some(Field::Id)
+            }
+            b"msgid_plural"
+                if entry.id.is_some() && entry.plural.is_none() && entry.translated.is_empty() =>
+            {
+                entry.plural = Some(value);
+                Some(Field::Plural)
+            }
+            b"msgstr" if entry.id.is_some() && entry.translated.is_empty() => {
+                entry.translated.push(value);
+                Some(Field::Translated(0))
+            }
+            b"" => {
+                let target = match field {
+                    Some(Field::PreviousContext) => entry.previous_context.as_mut(),
+                    Some(Field::PreviousId) => entry.previous_id.as_mut(),
+                    Some(Field::PreviousPlural) => entry.previous_plural.as_mut(),
+                    Some(Field::Context) => entry.context.as_mut(),
+                    Some(Field::Id) => entry.id.as_mut(),
+                    Some(Field::Plural) => entry.plural.as_mut(),
+                    Some(Field::Translated(n)) => entry.translated.get_mut(n),
+                    None => None,
+                };
+                target.ok_or(PoSyntax(line_no))?.extend(value);
+                field
+            }
+            k if k.starts_with(b"msgstr[") && k.ends_with(b"]") => {
+                let index = std::str::from_utf8(&k[7..k.len() - 1])
+                    .ok()
+                    .and_then(|s| s.parse::<usize>().ok());
+                match index {
+                    Some(n) if entry.plural.is_some() && n == entry.translated.len() => {
+                        entry.translated.push(value);
+                        Some(Field::Translated(n))
+                    }
+                    _ => return Err(PoSyntax(line_no)),
+                }
+            }
+            _ => return Err(PoSyntax(line_no)),
+        };
+        entry.end = line_end;
+    }
+
+    if !entry.is_empty() {
+        if entry.translated.is_empty() {
+            return Err(PoSyntax(contents.split(|b| *b == b'\n').count()));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Attaches the comment on the given line to the entry.
+fn parse_comment(entry: &mut RawEntry, line: &[u8]) {
+    let text = trim(&line[2.min(line.len())..]);
+    match line.get(1) {
+        None if line.is_empty() => (),
+        Some(b'.') => entry.extracted_comments.push(text.to_vec()),
+        Some(b':') => entry.references.extend(
+            text.split(|b| b.is_ascii_whitespace())
+                .filter(|r| !r.is_empty())
+                .map(|r| r.to_vec()),
+        ),
+        Some(b',') => entry.flags.extend(
+            text.split(|b| *b == b',')
+                .map(trim)
+                .filter(|f| !f.is_empty())
+                .map(|f| f.to_vec()),
+        ),
+        _ => {
+            let text = &line[1..];
+            let text = text.strip_prefix(b" ").unwrap_or(text);
+            entry.translator_comments.push(text.to_vec());
+        }
+    }
+}
+
+fn trim(mut s: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = s {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        s = rest;
+    }
+    while let [rest @ .., last] = s {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        s = rest;
+    }
+    s
+}
+
+/// Parses a C-style quoted string, resolving the escape sequences.
+fn unquote(s: &[u8]) -> Option<Vec<u8>> {
+    if s.len() < 2 || s[0] != b'"' || s[s.len() - 1] != b'"' {
+        return None;
+    }
+    let s = &s[1..s.len() - 1];
+    let mut result = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'"' => return None,
+            b'\\' => {
+                i += 1;
+                let unescaped = match *s.get(i)? {
+                    b'n' => b'\n',
+                    b't' => b'\t',
+                    b'r' => b'\r',
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'v' => 0x0b,
+                    c @ b'\\' | c @ b'"' | c @ b'\'' | c @ b'?' => c,
+                    b'0'..=b'7' => {
+                        let len = s[i..]
+                            .iter()
+                            .take(3)
+                            .take_while(|b| (b'0'..=b'7').contains(*b))
+                            .count();
+                        let value = parse_radix(&s[i..i + len], 8)?;
+                        i += len - 1;
+                        value
+                    }
+                    b'x' => {
+                        let len = s[i + 1..]
+                            .iter()
+                            .take_while(|b| b.is_ascii_hexdigit())
+                            .count();
+                        let value = parse_radix(&s[i + 1..i + 1 + len], 16)?;
+                        i += len;
+                        value
+                    }
+                    _ => return None,
+                };
+                result.push(unescaped);
+            }
+            c => result.push(c),
+        }
+        i += 1;
+    }
+    Some(result)
+}
+
+fn parse_radix(digits: &[u8], radix: u32) -> Option<u8> {
+    let digits = std::str::from_utf8(digits).ok()?;
+    u8::from_str_radix(digits, radix).ok()
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape("abc"), "abc");
+    assert_eq!(escape("a\n\t\"\\b"), r#"a\n\t\"\\b"#);
+    assert_eq!(escape("\x01\x07"), r#"\001\a"#);
+    assert_eq!(
+        unquote(format!("\"{}\"", escape("\x01\x07\x7f")).as_bytes()),
+        Some(b"\x01\x07\x7f".to_vec())
+    );
+}
+
+#[test]
+fn test_write_po() {
+    let header = PoEntry {
+        flags: vec!["fuzzy".to_owned()],
+        translations: vec!["Language: lt\nContent-Type: text/plain; charset=UTF-8\n".to_owned()],
+        ..PoEntry::new("")
+    };
+    let plural = PoEntry {
+        translator_comments: vec!["translator".to_owned(), "".to_owned()],
+        extracted_comments: vec!["extracted".to_owned()],
+        references: (0..12).map(|i| format!("src/file.rs:{}", i)).collect(),
+        flags: vec!["c-format".to_owned(), "no-wrap".to_owned()],
+        context: Some("ctx".to_owned()),
+        id_plural: Some("%d files".to_owned()),
+        ..PoEntry::new("%d file")
+    };
+    let translated = PoEntry {
+        translations: vec!["Sveiki, \"pasauli\"\n".to_owned()],
+        ..PoEntry::new("Hello, \"world\"\n")
+    };
+    let file = PoFile {
+        entries: vec![header, plural, translated],
+        original: None,
+    };
+    assert!(file.header().is_some());
+
+    let mut written = vec![];
+    file.write(&mut written).unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        r#"#, fuzzy
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+# translator
+#
+#. extracted
+#: src/file.rs:0 src/file.rs:1 src/file.rs:2 src/file.rs:3 src/file.rs:4
+#: src/file.rs:5 src/file.rs:6 src/file.rs:7 src/file.rs:8 src/file.rs:9
+#: src/file.rs:10 src/file.rs:11
+#, c-format, no-wrap
+msgctxt "ctx"
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] ""
+msgstr[1] ""
+
+msgid "Hello, \"world\"\n"
+msgstr "Sveiki, \"pasauli\"\n"
+"#
+    );
+}
+
+#[test]
+fn test_parse_po_file() {
+    let po = r#"# Lithuanian translation.
+#
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2));\n"
+
+#. TRANSLATORS: a vegetable
+#: src/main.rs:1 src/main.rs:2
+#: src/lib.rs:3
+#, fuzzy, c-format
+msgid "Garlic"
+msgid_plural "Garlics"
+msgstr[0] "Česnakas"
+msgstr[1] "Česnakai"
+msgstr[2] "Česnakų"
+
+msgid "Onion"
+msgstr ""
+
+msgid "%d leek"
+msgid_plural "%d leeks"
+msgstr[0] "%d poras"
+msgstr[1] ""
+msgstr[2] "%d porų"
+"#;
+    let file = PoFile::parse(po.as_bytes()).unwrap();
+    assert_eq!(file.entries.len(), 4);
+    assert_eq!(
+        file.header().unwrap().translator_comments,
+        ["Lithuanian translation.", ""]
+    );
+
+    let garlic = &file.entries[1];
+    assert_eq!(garlic.extracted_comments, ["TRANSLATORS: a vegetable"]);
+    assert_eq!(
+        garlic.references,
+        ["src/main.rs:1", "src/main.rs:2", "src/lib.rs:3"]
+    );
+    assert_eq!(garlic.flags, ["fuzzy", "c-format"]);
+    assert!(garlic.has_flag("fuzzy"));
+    assert_eq!(garlic.id_plural.as_deref(), Some("Garlics"));
+    assert_eq!(garlic.translations, ["Česnakas", "Česnakai", "Česnakų"]);
+
+    let catalog = file.to_catalog().unwrap();
+    assert_eq!(catalog.ngettext("Garlic", "Garlics", 21), "Garlics");
+    assert!(catalog.is_fuzzy(None, "Garlic"));
+    let catalog = file
+        .to_catalog_with_options(&ParseOptions::new().include_fuzzy(true))
+        .unwrap();
+    assert_eq!(catalog.ngettext("Garlic", "Garlics", 21), "Česnakas");
+    assert_eq!(catalog.ngettext("Garlic", "Garlics", 12), "Česnakų");
+    assert_eq!(catalog.gettext("Onion"), "Onion");
+    // Plurals missing any form are untranslated, in every form.
+    assert_eq!(catalog.ngettext("%d leek", "%d leeks", 1), "%d leek");
+    assert_eq!(catalog.ngettext("%d leek", "%d leeks", 5), "%d leeks");
+
+    let mut written = vec![];
+    file.write(&mut written).unwrap();
+    assert_eq!(PoFile::parse(&written[..]).unwrap(), file);
+}
+
+#[test]
+fn test_unquote() {
+    assert_eq!(unquote(br#""""#), Some(vec![]));
+    assert_eq!(unquote(br#""abc""#), Some(b"abc".to_vec()));
+    assert_eq!(
+        unquote(br#""a\n\t\"\\\'\?""#),
+        Some(b"a\n\t\"\\'?".to_vec())
+    );
+    assert_eq!(unquote(br#""\xFF\101\0""#), Some(vec![0xff, b'A', 0]));
+    assert_eq!(unquote(br#""abc"#), None);
+    assert_eq!(unquote(br#"abc""#), None);
+    assert_eq!(unquote(br#""a"b""#), None);
+    assert_eq!(unquote(br#""abc\""#), None);
+    assert_eq!(unquote(br#""\x""#), None);
+    assert_eq!(unquote(br#""\q""#), None);
+}
+
+#[test]
+fn test_parse_entries() {
+    let po = br#"
+# translator comment
+#, fuzzy
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#: src/main.rs:1
+msgctxt "ctx"
+msgid "a"
+"b"
+msgid_plural "abs"
+msgstr[0] "x"
+msgstr[1] ""
+"y"
+#, fuzzy
+#| msgid "c"
+#| "c"
+msgid "c"
+msgstr "d"
+
+# obsolete
+#~ msgid "obsolete"
+#~ msgstr ""
+#~ "pasenes"
+"#;
+    let entries = parse_entries(po).unwrap();
+    assert_eq!(entries.len(), 4);
+    assert!(entries[0].is_header());
+    assert_eq!(
+        entries[0].translator_comments,
+        vec![b"translator comment".to_vec()]
+    );
+    assert_eq!(entries[0].flags, vec![b"fuzzy".to_vec()]);
+    assert_eq!(entries[1].references, vec![b"src/main.rs:1".to_vec()]);
+    assert_eq!(
+        entries[0].translated,
+        vec![b"Content-Type: text/plain; charset=UTF-8\n".to_vec()]
+    );
+    assert_eq!(entries[1].context, Some(b"ctx".to_vec()));
+    assert_eq!(entries[1].id, Some(b"ab".to_vec()));
+    assert_eq!(entries[1].plural, Some(b"abs".to_vec()));
+    assert_eq!(entries[1].translated, vec![b"x".to_vec(), b"y".to_vec()]);
+    assert_eq!(entries[2].id, Some(b"c".to_vec()));
+    assert_eq!(entries[2].previous_id, Some(b"cc".to_vec()));
+    assert_eq!(entries[2].translated, vec![b"d".to_vec()]);
+    assert!(!entries[2].obsolete);
+    assert!(entries[3].obsolete);
+    assert_eq!(entries[3].translator_comments, vec![b"obsolete".to_vec()]);
+    assert_eq!(entries[3].translated, vec![b"pasenes".to_vec()]);
+}
+
+#[test]
+fn test_parse_entries_errors() {
+    fn error_line(po: &[u8]) -> usize {
+        match parse_entries(po) {
+            Err(PoSyntax(line)) => line,
+            other => panic!("Expected PoSyntax, got {:?}", other),
+        }
+    }
+
+    assert_eq!(error_line(b"msgid \"a\"\nmsgstr \"b\nmsgid \"c\""), 2);
+    assert_eq!(error_line(b"\"dangling\""), 1);
+    assert_eq!(error_line(b"msgstr \"b\""), 1);
+    assert_eq!(error_line(b"msgid \"a\"\nmsgstr[0] \"b\""), 2);
+    assert_eq!(
+        error_line(b"msgid \"a\"\nmsgid_plural \"as\"\nmsgstr[1] \"b\""),
+        3
+    );
+    assert_eq!(error_line(b"msgid \"a\"\nfoo \"b\""), 2);
+    assert_eq!(error_line(b"msgid \"a\"\n"), 2);
+    assert_eq!(error_line(b"#| msgstr \"a\"\n"), 1);
+    assert_eq!(error_line(b"msgid \"a\"\n#| msgid \"b\"\nmsgstr \"c\""), 2);
+}
+
+#[test]
+fn test_write_po_unmodified() {
+    let po = "\n\n# Header comment\nmsgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n\
+#: src/main.rs:1\n#,fuzzy,c-format\n#| msgid \"Old\"\nmsgid   \"%d Long \"\n\"wrapped\"\nmsgstr \"\"\n\"%d Ilgas\"\r\n\
+msgid \"Next\"\nmsgstr \"Kitas\"\n\n\n#~ msgid \"Gone\"\n#~ msgstr \"Nebėra\"\n\n# dangling comment\n";
+    let file = PoFile::parse(po.as_bytes()).unwrap();
+    assert_eq!(file.entries.len(), 4);
+    let write = |file: &PoFile| {
+        let mut written = vec![];
+        file.write(&mut written).unwrap();
+        String::from_utf8(written).unwrap()
+    };
+    assert_eq!(write(&file), po);
+
+    // Only the modified entry is rewritten.
+    let mut modified = file.clone();
+    modified.entries[2].translations[0] = "Toliau".to_owned();
+    assert_eq!(
+        write(&modified),
+        po.replace(
+            "msgid \"Next\"\nmsgstr \"Kitas\"\n",
+            "\nmsgid \"Next\"\nmsgstr \"Toliau\"\n"
+        )
+    );
+
+    // Reordered entries stay separated.
+    let mut reordered = file.clone();
+    reordered.entries.swap(1, 2);
+    let written = write(&reordered);
+    assert!(
+        written.contains("charset=UTF-8\\n\"\n\nmsgid \"Next\""),
+        "{}",
+        written
+    );
+    assert!(
+        written.contains("msgstr \"Kitas\"\n\n#: src/main.rs:1"),
+        "{}",
+        written
+    );
+    assert_eq!(PoFile::parse(written.as_bytes()).unwrap(), reordered);
+}
+
+#[test]
+fn test_parse_po_charset() {
+    // Fresh templates have a placeholder.
+    let pot = b"msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=CHARSET\\n\"\n\nmsgid \"Japan\"\nmsgstr \"\"\n";
+    assert_eq!(PoFile::parse(&pot[..]).unwrap().entries.len(), 2);
+
+    let po = b"msgid \"\"\nmsgstr \"Content-Type: text/plain; CHARSET=\\\"EUC-JP\\\"; format=flowed\\n\"\n\nmsgid \"Japan\"\nmsgstr \"\xc6\xfc\xcb\xdc\"\n";
+    let catalog = Catalog::parse_po(&po[..]).unwrap();
+    assert_eq!(catalog.gettext("Japan"), "日本");
+}
+
+#[test]
+fn test_write_po_encoding() {
+    use crate::WriteOptions;
+
+    let po =
+        b"# Lithuanian\nmsgid \"\"\nmsgstr \"Content-Type: text/plain; charset=cp1257\\n\"\n\n\
+#: src/main.rs:1\nmsgid \"Garlic\"\nmsgstr \"\xc8esnakas\"\n";
+    let file = PoFile::parse(&po[..]).unwrap();
+    let write = |options: WriteOptions, file: &PoFile| {
+        let mut written = vec![];
+        options.write_po(file, &mut written).map(|_| written)
+    };
+
+    // To UTF-8, by default.
+    let utf8 = write(WriteOptions::new(), &file).unwrap();
+    assert_eq!(
+        String::from_utf8(utf8.clone()).unwrap(),
+        "# Lithuanian\nmsgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n\
+#: src/main.rs:1\nmsgid \"Garlic\"\nmsgstr \"Česnakas\"\n"
+    );
+    let utf8 = PoFile::parse(&utf8[..]).unwrap();
+    let mut unchanged = vec![];
+    utf8.write(&mut unchanged).unwrap();
+    assert_eq!(write(WriteOptions::new(), &utf8).unwrap(), unchanged);
+
+    // And back.
+    let cp1257 = WriteOptions::new().encoding(encoding_rs::WINDOWS_1257);
+    let written = write(cp1257.clone(), &utf8).unwrap();
+    let written = PoFile::parse(&written[..]).unwrap();
+    assert_eq!(
+        written.header().unwrap().translations,
+        ["Content-Type: text/plain; charset=windows-1257\n"]
+    );
+    assert_eq!(written.entries[1], file.entries[1]);
+
+    let mut modified = utf8.clone();
+    modified.entries[1].translations[0] = "Чеснок".to_owned();
+    let err = write(cp1257, &modified).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "'Ч' in msgid \"Garlic\" (src/main.rs:1) can not be represented in windows-1257"
+    );
+    // The file is written in its own encoding otherwise.
+    let mut modified = file.clone();
+    modified.entries[1].translations[0] = "Чеснок".to_owned();
+    assert_eq!(
+        modified.write(&mut vec![]).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
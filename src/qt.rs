@@ -0,0 +1,285 @@
+//! Conversion between catalogs and the `.ts` files of [Qt Linguist](https://doc.qt.io/qt-6/linguist-ts-file-format.html).
+//!
+//! The contexts of Qt messages are the contexts (`msgctxt`) of gettext messages;
+//! messages without a context are written to a context with an empty name.
+//! Numerus messages are messages with a plural form, with a `numerusform` for each
+//! of the catalog's plural forms. As Qt has one original string for all the forms,
+//! the plural one is kept in an `extra-po-msgid_plural` element like `lconvert` does.
+//!
+//! Placeholders like `%n` are not converted, and disambiguating comments are not supported.
+//!
+//! ```
+//! use gettext::{qt, Catalog};
+//!
+//! let catalog = qt::parse(&br#"<TS version="2.1" language="lt">
+//!   <context>
+//!     <name>MainWindow</name>
+//!     <message><source>Open</source><translation>Atverti</translation></message>
+//!   </context>
+//! </TS>"#[..]).unwrap();
+//! assert_eq!(catalog.pgettext("MainWindow", "Open"), "Atverti");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::po::{PoEntry, PoFile};
+use crate::xml::{self, Element};
+use crate::Error::{self, InvalidDocument};
+use crate::{Catalog, Message};
+
+/// The element keeping the original plural string of numerus messages.
+const PLURAL_ELEMENT: &str = "extra-po-msgid_plural";
+
+/// Writes the catalog as a Qt Linguist `.ts` file, with the language of its `Language` header.
+///
+/// The messages are grouped by context, and sorted by context and original string.
+/// Messages marked as fuzzy are written as `unfinished`.
+pub fn write<W: Write>(catalog: &Catalog, mut writer: W) -> Result<(), Error> {
+    let mut contexts = BTreeMap::<&str, Vec<&Message>>::new();
+    for message in catalog.messages() {
+        if message.id.is_empty() && message.context.is_none() {
+            continue;
+        }
+        let context = message.context.as_deref().unwrap_or_default();
+        contexts.entry(context).or_default().push(message);
+    }
+
+    let mut root = Element::new("TS").attr("version", "2.1");
+    if let Some(language) = catalog.metadata_field("Language") {
+        root = root.attr("language", language);
+    }
+    for (name, mut messages) in contexts {
+        messages.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut context = Element::new("context").child(Element::new("name").text(name));
+        for message in messages {
+            context = context.child(message_element(catalog, message));
+        }
+        root = root.child(context);
+    }
+
+    writer.write_all(root.to_document(Some("<!DOCTYPE TS>")).as_bytes())?;
+    Ok(())
+}
+
+fn message_element(catalog: &Catalog, message: &Message) -> Element {
+    let mut element = Element::new("message");
+    if message.plural.is_some() {
+        element = element.attr("numerus", "yes");
+    }
+    element = element.child(Element::new("source").text(&message.id));
+    if let Some(ref plural) = message.plural {
+        element = element.child(Element::new(PLURAL_ELEMENT).text(plural));
+    }
+
+    let mut translation = Element::new("translation");
+    if message.translated.iter().all(|t| t.is_empty())
+        || catalog.is_fuzzy(message.context.as_deref(), &message.id)
+    {
+        translation = translation.attr("type", "unfinished");
+    }
+    translation = match message.plural {
+        Some(_) => message.translated.iter().fold(translation, |t, form| {
+            t.child(Element::new("numerusform").text(form))
+        }),
+        None => translation.text(message.translated.first().map_or("", String::as_str)),
+    };
+    element.child(translation)
+}
+
+/// Parses a Qt Linguist `.ts` file into a catalog.
+///
+/// The plural formula is the one of the file's `language`, e.g. `lt_LT`, if it is known.
+/// Like for PO files, unfinished translations are left out of the catalog
+/// unless they are fuzzy, i.e. not empty, in which case `Catalog::is_fuzzy` tells them apart.
+/// Obsolete and vanished messages are ignored.
+pub fn parse<R: Read>(reader: R) -> Result<Catalog, Error> {
+    let root = xml::read(reader)?;
+    if root.name != "TS" {
+        return Err(InvalidDocument(format!(
+            "expected a `TS` element, found `{}`",
+            root.name
+        )));
+    }
+
+    let mut po = PoFile::new();
+    po.entries.push(PoEntry::header_for(root.get("language")));
+    for context in root.all("context") {
+        let name = context
+            .first("name")
+            .map(Element::content)
+            .unwrap_or_default();
+        for message in context.all("message") {
+            let source = message.first("source").ok_or_else(|| {
+                InvalidDocument(format!("message without a source in context `{}`", name))
+            })?;
+            let translation = message.first("translation");
+            let kind = translation.and_then(|t| t.get("type"));
+            if kind == Some("obsolete") || kind == Some("vanished") {
+                continue;
+            }
+
+            let mut entry = PoEntry::new(source.content());
+            if !name.is_empty() {
+                entry.context = Some(name.clone());
+            }
+            if message.get("numerus") == Some("yes") {
+                let plural = message.first(PLURAL_ELEMENT).unwrap_or(source);
+                entry.id_plural = Some(plural.content());
+                entry.translations = translation
+                    .into_iter()
+                    .flat_map(|t| t.all("numerusform"))
+                    .map(Element::content)
+                    .collect();
+            } else {
+                entry.translations = vec![translation.map(Element::content).unwrap_or_default()];
+            }
+            let translated = entry.translations.iter().any(|t| !t.is_empty());
+            if kind == Some("unfinished") && translated {
+                entry.flags.push("fuzzy".to_owned());
+            }
+            po.entries.push(entry);
+        }
+    }
+    po.to_catalog()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=((n%10==1) && ((n%100<11) || (n%100>19))) ? 0 : (((n%10>=2) && ((n%100<11) || (n%100>19))) ? 1 : 2);\n"
+
+msgid "Open <file>"
+msgstr "Atverti <failą>"
+
+msgctxt "MainWindow"
+msgid "%n file"
+msgid_plural "%n files"
+msgstr[0] "%n failas"
+msgstr[1] "%n failai"
+msgstr[2] "%n failų"
+
+#, fuzzy
+msgctxt "MainWindow"
+msgid "Close"
+msgstr "Uždaryti"
+"#;
+
+    #[test]
+    fn test_write() {
+        let catalog = ParseOptions::new()
+            .include_fuzzy(true)
+            .parse_po(PO.as_bytes())
+            .unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE TS>
+<TS version="2.1" language="lt">
+  <context>
+    <name/>
+    <message>
+      <source>Open &lt;file&gt;</source>
+      <translation>Atverti &lt;failą&gt;</translation>
+    </message>
+  </context>
+  <context>
+    <name>MainWindow</name>
+    <message numerus="yes">
+      <source>%n file</source>
+      <extra-po-msgid_plural>%n files</extra-po-msgid_plural>
+      <translation>
+        <numerusform>%n failas</numerusform>
+        <numerusform>%n failai</numerusform>
+        <numerusform>%n failų</numerusform>
+      </translation>
+    </message>
+    <message>
+      <source>Close</source>
+      <translation type="unfinished">Uždaryti</translation>
+    </message>
+  </context>
+</TS>
+"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let catalog = ParseOptions::new()
+            .include_fuzzy(true)
+            .parse_po(PO.as_bytes())
+            .unwrap();
+        let mut output = vec![];
+        write(&catalog, &mut output).unwrap();
+        let parsed = parse(&output[..]).unwrap();
+        assert_eq!(parsed.gettext("Open <file>"), "Atverti <failą>");
+        for n in 0..30 {
+            assert_eq!(
+                parsed.npgettext("MainWindow", "%n file", "%n files", n),
+                catalog.npgettext("MainWindow", "%n file", "%n files", n)
+            );
+        }
+        // Fuzzy translations are left out, like when parsing a PO file.
+        assert_eq!(parsed.pgettext("MainWindow", "Close"), "Close");
+        assert!(parsed.is_fuzzy(Some("MainWindow"), "Close"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let catalog = parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<TS version="2.1" language="ru_RU" sourcelanguage="en">
+<context>
+    <name>Dialog</name>
+    <message numerus="yes">
+        <location filename="dialog.cpp" line="12"/>
+        <source>%n item(s)</source>
+        <translation><numerusform>%n элемент</numerusform><numerusform>%n элемента</numerusform><numerusform>%n элементов</numerusform></translation>
+    </message>
+    <message>
+        <source>Gone</source>
+        <translation type="vanished">Нет</translation>
+    </message>
+    <message>
+        <source>Later</source>
+        <translation type="unfinished"></translation>
+    </message>
+</context>
+</TS>
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.npgettext("Dialog", "%n item(s)", "", 1),
+            "%n элемент"
+        );
+        assert_eq!(
+            catalog.npgettext("Dialog", "%n item(s)", "", 3),
+            "%n элемента"
+        );
+        assert_eq!(
+            catalog.npgettext("Dialog", "%n item(s)", "", 11),
+            "%n элементов"
+        );
+        assert_eq!(catalog.pgettext("Dialog", "Gone"), "Gone");
+        assert!(catalog.messages().all(|m| m.id != "Later"));
+        assert!(!catalog.is_fuzzy(Some("Dialog"), "Later"));
+
+        assert!(matches!(
+            parse(&b"<resources/>"[..]),
+            Err(InvalidDocument(_))
+        ));
+    }
+}
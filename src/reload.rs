@@ -0,0 +1,172 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::SystemTime;
+
+use crate::{Catalog, Error, ParseOptions};
+
+/// What we know about the file the current catalog was parsed from.
+#[derive(Debug)]
+struct FileState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+/// ReloadableCatalog is a handle to a catalog backed by an MO file
+/// that can be re-parsed when the file changes on disk.
+///
+/// Readers get a snapshot of the current catalog via `catalog()`,
+/// while any thread may call `reload_if_changed()` to pick up a new version.
+/// If the new version of the file fails to parse,
+/// the previously loaded catalog keeps being served.
+///
+/// # Examples
+///
+/// ```ignore
+/// use gettext::ReloadableCatalog;
+///
+/// let handle = ReloadableCatalog::open("french.mo").unwrap();
+/// // e.g. periodically, from a background thread:
+/// if let Err(err) = handle.reload_if_changed() {
+///     eprintln!("keeping the old catalog: {}", err);
+/// }
+/// println!("{}", handle.catalog().gettext("Name"));
+/// ```
+pub struct ReloadableCatalog {
+    path: PathBuf,
+    options: ParseOptions,
+    current: RwLock<Arc<Catalog>>,
+    state: Mutex<FileState>,
+}
+
+impl ReloadableCatalog {
+    /// Parses the MO file at `path` and returns a handle to it.
+    ///
+    /// Calling this method is equivalent to calling
+    /// `ReloadableCatalog::open_with_options(path, ParseOptions::new())`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::open_with_options(path, ParseOptions::new())
+    }
+
+    /// Parses the MO file at `path` using the specified options
+    /// and returns a handle to it.
+    /// The same options are used for every subsequent reload.
+    pub fn open_with_options<P: AsRef<Path>>(
+        path: P,
+        options: ParseOptions,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref().to_owned();
+        let (catalog, state) = load(&path, &options)?;
+        Ok(ReloadableCatalog {
+            path,
+            options,
+            current: RwLock::new(Arc::new(catalog)),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Returns the path of the underlying MO file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the currently loaded catalog.
+    ///
+    /// The returned snapshot is not affected by later reloads.
+    pub fn catalog(&self) -> Arc<Catalog> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Re-parses the underlying file if its modification time, size
+    /// or contents have changed since it was last loaded.
+    ///
+    /// Returns `Ok(true)` if a new catalog has been swapped in
+    /// and `Ok(false)` if the file has not changed.
+    /// Upon an error the previously loaded catalog stays in place.
+    pub fn reload_if_changed(&self) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let meta = fs::metadata(&self.path)?;
+        if meta.modified().ok() == state.modified && meta.len() == state.len {
+            return Ok(false);
+        }
+
+        let contents = read_file(&self.path)?;
+        let hash = hash_contents(&contents);
+        if hash == state.hash {
+            // Touched, but not modified.
+            state.modified = meta.modified().ok();
+            return Ok(false);
+        }
+
+        let catalog = self.options.clone().parse(&contents[..])?;
+        self.swap(catalog);
+        *state = FileState {
+            modified: meta.modified().ok(),
+            len: contents.len() as u64,
+            hash,
+        };
+        Ok(true)
+    }
+
+    /// Unconditionally re-parses the underlying file.
+    ///
+    /// Upon an error the previously loaded catalog stays in place.
+    pub fn reload(&self) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let (catalog, new_state) = load(&self.path, &self.options)?;
+        self.swap(catalog);
+        *state = new_state;
+        Ok(())
+    }
+
+    fn swap(&self, catalog: Catalog) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(catalog);
+    }
+}
+
+impl fmt::Debug for ReloadableCatalog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ReloadableCatalog")
+            .field("path", &self.path)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut contents = vec![];
+    File::open(path)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn hash_contents(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(contents);
+    hasher.finish()
+}
+
+fn load(path: &Path, options: &ParseOptions) -> Result<(Catalog, FileState), Error> {
+    let modified = fs::metadata(path)?.modified().ok();
+    let contents = read_file(path)?;
+    let catalog = options.clone().parse(&contents[..])?;
+    let state = FileState {
+        modified,
+        len: contents.len() as u64,
+        hash: hash_contents(&contents),
+    };
+    Ok((catalog, state))
+}
+
+#[test]
+fn reloadable_catalog_impls_send_sync() {
+    fn check<T: Send + Sync>() {}
+    check::<ReloadableCatalog>();
+}
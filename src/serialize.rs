@@ -0,0 +1,187 @@
+use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Serialize, Serializer};
+
+use crate::plurals::{Ast, Resolver};
+use crate::{Catalog, Message};
+
+/// The serialized form of a catalog, see the documentation of `Catalog`.
+#[derive(serde::Serialize)]
+struct CatalogRef<'a> {
+    plural: Option<String>,
+    messages: Vec<&'a Message>,
+    fuzzy: Vec<FuzzyRef<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct FuzzyRef<'a> {
+    context: Option<&'a str>,
+    id: &'a str,
+    #[serde(skip_serializing_if = "is_false")]
+    plural: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(serde::Deserialize)]
+struct CatalogData {
+    #[serde(default)]
+    plural: Option<String>,
+    messages: Vec<Message>,
+    #[serde(default)]
+    fuzzy: Vec<FuzzyData>,
+}
+
+#[derive(serde::Deserialize)]
+struct FuzzyData {
+    #[serde(default)]
+    context: Option<String>,
+    id: String,
+    #[serde(default)]
+    plural: bool,
+}
+
+impl Serialize for Catalog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut messages = self.strings.values().collect::<Vec<_>>();
+        // Sorted, so that equal catalogs are serialized the same way.
+        messages.sort_by(|a, b| (&a.context, &a.id).cmp(&(&b.context, &b.id)));
+        let plural = match self.resolver {
+            Resolver::Expr(ref ast) => Some(ast.to_string()),
+            Resolver::Function(_) => None,
+        };
+        CatalogRef {
+            plural,
+            messages,
+            fuzzy: self
+                .fuzzy
+                .iter()
+                .map(|((context, id), &plural)| FuzzyRef {
+                    context: context.as_deref(),
+                    id,
+                    plural,
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Catalog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = CatalogData::deserialize(deserializer)?;
+        let mut catalog = Catalog::new();
+        if let Some(plural) = data.plural {
+            let ast = Ast::parse(&plural)
+                .ok()
+                .filter(|ast| !ast.divides_by_zero())
+                .ok_or_else(|| {
+                    D::Error::custom(format_args!("invalid plural formula `{}`", plural))
+                })?;
+            catalog.resolver = Resolver::Expr(ast);
+        }
+        for message in data.messages {
+            catalog.insert(message);
+        }
+        catalog.fuzzy = data
+            .fuzzy
+            .into_iter()
+            .map(|f| ((f.context, f.id), f.plural))
+            .collect();
+        Ok(catalog)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11) ? 0 : n != 0 ? 1 : 2;\n"
+
+msgctxt "menu"
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failai"
+msgstr[2] "Failų"
+
+#, fuzzy
+msgid "Open"
+msgstr "Atverti"
+"#;
+
+    #[test]
+    fn test_serialize() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let json = serde_json::to_value(&catalog).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "plural": "((((n % 10) == 1) && ((n % 100) != 11)) ? 0 : ((n != 0) ? 1 : 2))",
+                "messages": [
+                    {
+                        "id": "",
+                        "context": null,
+                        "id_plural": null,
+                        "translations": [
+                            "Content-Type: text/plain; charset=UTF-8\n\
+                             Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11) ? 0 : n != 0 ? 1 : 2;\n"
+                        ]
+                    },
+                    {
+                        "id": "File",
+                        "context": "menu",
+                        "id_plural": "Files",
+                        "translations": ["Failas", "Failai", "Failų"]
+                    }
+                ],
+                "fuzzy": [{"context": null, "id": "Open"}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let json = serde_json::to_string(&catalog).unwrap();
+        let restored: Catalog = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        for n in 0..30 {
+            assert_eq!(
+                restored.npgettext("menu", "File", "Files", n),
+                catalog.npgettext("menu", "File", "Files", n)
+            );
+        }
+        assert!(restored.is_fuzzy(None, "Open"));
+
+        let minimal: Catalog =
+            serde_json::from_str(r#"{"messages": [{"id": "Open", "translations": ["Atverti"]}]}"#)
+                .unwrap();
+        assert_eq!(minimal.gettext("Open"), "Atverti");
+        assert_eq!(minimal.ngettext("Open", "Opens", 2), "Opens");
+
+        let err =
+            serde_json::from_str::<Catalog>(r#"{"plural": "n +", "messages": []}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid plural formula `n +`"),
+            "{}",
+            err
+        );
+        // Neither a formula which can not be parsed nor one which divides by zero
+        // may panic on untrusted input.
+        for plural in &["(", "n%0", "n % (n % 2) ? 0 : 1"] {
+            let json = serde_json::json!({"plural": plural, "messages": []});
+            let err = serde_json::from_value::<Catalog>(json).unwrap_err();
+            assert!(
+                err.to_string().contains("invalid plural formula"),
+                "{}",
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// StaticCatalog is a set of translation strings
+/// embedded into the program at compile time
+/// by the `gettext_macros::include_catalog!` macro.
+///
+/// The messages are laid out in a perfect hash table
+/// and the plural formula is compiled into a function,
+/// so using a static catalog involves no parsing at runtime.
+#[derive(Debug)]
+pub struct StaticCatalog {
+    seed: u64,
+    displacements: &'static [(u32, u32)],
+    messages: &'static [StaticMessage],
+    plural: fn(u64) -> usize,
+}
+
+/// An entry of a `StaticCatalog` table.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct StaticMessage {
+    key: &'static str,
+    translated: &'static [&'static str],
+}
+
+impl StaticMessage {
+    #[doc(hidden)]
+    pub const fn __new(key: &'static str, translated: &'static [&'static str]) -> Self {
+        StaticMessage { key, translated }
+    }
+}
+
+impl StaticCatalog {
+    #[doc(hidden)]
+    pub const fn __new(
+        seed: u64,
+        displacements: &'static [(u32, u32)],
+        messages: &'static [StaticMessage],
+        plural: fn(u64) -> usize,
+    ) -> Self {
+        StaticCatalog {
+            seed,
+            displacements,
+            messages,
+            plural,
+        }
+    }
+
+    fn get(&self, msg_context: Option<&str>, msg_id: &str) -> Option<&'static StaticMessage> {
+        if self.messages.is_empty() {
+            return None;
+        }
+        let hashes = match msg_context {
+            Some(ctxt) => hash(self.seed, &[ctxt.as_bytes(), b"\x04", msg_id.as_bytes()]),
+            None => hash(self.seed, &[msg_id.as_bytes()]),
+        };
+        let index = slot(hashes, self.displacements, self.messages.len());
+        let message = &self.messages[index];
+        let matches = match msg_context {
+            Some(ctxt) => {
+                message.key.len() == ctxt.len() + 1 + msg_id.len()
+                    && message.key.starts_with(ctxt)
+                    && message.key[ctxt.len()..].starts_with('\x04')
+                    && message.key.ends_with(msg_id)
+            }
+            None => message.key == msg_id,
+        };
+        if matches {
+            Some(message)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the singular translation of `msg_id` from the given catalog
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn gettext<'a>(&self, msg_id: &'a str) -> &'a str {
+        self.get(None, msg_id)
+            .and_then(|msg| msg.translated.first().cloned())
+            .unwrap_or(msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id` from the given catalog
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn ngettext<'a>(&self, msg_id: &'a str, msg_id_plural: &'a str, n: u64) -> &'a str {
+        self.get_plural(None, msg_id, msg_id_plural, n)
+    }
+
+    /// Returns the singular translation of `msg_id`
+    /// in the context `msg_context`
+    /// or `msg_id` itself if a translation does not exist.
+    pub fn pgettext<'a>(&self, msg_context: &str, msg_id: &'a str) -> &'a str {
+        self.get(Some(msg_context), msg_id)
+            .and_then(|msg| msg.translated.first().cloned())
+            .unwrap_or(msg_id)
+    }
+
+    /// Returns the plural translation of `msg_id`
+    /// in the context `msg_context`
+    /// with the correct plural form for the number `n` of objects.
+    /// Returns msg_id if a translation does not exist and `n == 1`,
+    /// msg_id_plural otherwise.
+    pub fn npgettext<'a>(
+        &self,
+        msg_context: &str,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        self.get_plural(Some(msg_context), msg_id, msg_id_plural, n)
+    }
+
+    fn get_plural<'a>(
+        &self,
+        msg_context: Option<&str>,
+        msg_id: &'a str,
+        msg_id_plural: &'a str,
+        n: u64,
+    ) -> &'a str {
+        let form_no = (self.plural)(n);
+        let message = self.get(msg_context, msg_id);
+        match message.and_then(|m| m.translated.get(form_no)) {
+            Some(msg) => msg,
+            None if n == 1 => msg_id,
+            None => msg_id_plural,
+        }
+    }
+}
+
+/// Hashes the concatenation of `parts`,
+/// returning the bucket hash and the two displacement hashes.
+fn hash(seed: u64, parts: &[&[u8]]) -> (u32, u32, u32) {
+    // FNV-1a, finalized with the SplitMix64 mixer.
+    let mut h = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for part in parts {
+        for b in part.iter() {
+            h ^= u64::from(*b);
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    let h1 = mix(h);
+    let h2 = mix(h1 ^ seed);
+    ((h1 >> 32) as u32, h1 as u32, h2 as u32)
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn slot((g, f1, f2): (u32, u32, u32), displacements: &[(u32, u32)], len: usize) -> usize {
+    let (d1, d2) = displacements[g as usize % displacements.len()];
+    (f2.wrapping_add(f1.wrapping_mul(d1)).wrapping_add(d2) as usize) % len
+}
+
+/// A perfect hash table layout for a set of keys.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct PhfTable {
+    /// The seed the keys were hashed with.
+    pub seed: u64,
+    /// The displacements of each bucket.
+    pub displacements: Vec<(u32, u32)>,
+    /// The index of the key placed into each slot of the table.
+    pub order: Vec<usize>,
+}
+
+/// Computes a perfect hash table for the given distinct keys
+/// using the "hash, displace and compress" algorithm.
+#[doc(hidden)]
+pub fn build_phf(keys: &[&str]) -> PhfTable {
+    const LAMBDA: usize = 5;
+
+    let len = keys.len();
+    let buckets_len = len.div_ceil(LAMBDA);
+    if len == 0 {
+        return PhfTable {
+            seed: 0,
+            displacements: vec![(0, 0)],
+            order: vec![],
+        };
+    }
+
+    'seeds: for seed in 0.. {
+        let hashes = keys
+            .iter()
+            .map(|k| hash(seed, &[k.as_bytes()]))
+            .collect::<Vec<_>>();
+        let mut buckets = vec![vec![]; buckets_len];
+        for (i, h) in hashes.iter().enumerate() {
+            buckets[h.0 as usize % buckets_len].push(i);
+        }
+        let mut bucket_order = (0..buckets_len).collect::<Vec<_>>();
+        bucket_order.sort_by_key(|&b| core::cmp::Reverse(buckets[b].len()));
+
+        let mut displacements = vec![(0, 0); buckets_len];
+        let mut order = vec![None; len];
+        let mut taken = Vec::with_capacity(LAMBDA);
+        for &b in &bucket_order {
+            let placed = (0..len as u32)
+                .flat_map(|d1| (0..len as u32).map(move |d2| (d1, d2)))
+                .find(|&(d1, d2)| {
+                    displacements[b] = (d1, d2);
+                    taken.clear();
+                    buckets[b].iter().all(|&key| {
+                        let index = slot(hashes[key], &displacements, len);
+                        if order[index].is_some() || taken.contains(&index) {
+                            false
+                        } else {
+                            taken.push(index);
+                            true
+                        }
+                    })
+                });
+            if placed.is_none() {
+                continue 'seeds;
+            }
+            for (&key, &index) in buckets[b].iter().zip(taken.iter()) {
+                order[index] = Some(key);
+            }
+        }
+
+        return PhfTable {
+            seed,
+            displacements,
+            order: order.into_iter().map(Option::unwrap).collect(),
+        };
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plural(n: u64) -> usize {
+        (n != 1) as usize
+    }
+
+    #[test]
+    fn test_build_phf() {
+        let keys = (0..1000).map(|i| format!("key {}", i)).collect::<Vec<_>>();
+        let keys = keys.iter().map(|k| k.as_str()).collect::<Vec<_>>();
+        let table = build_phf(&keys);
+        let mut seen = table.order.clone();
+        seen.sort();
+        assert_eq!(seen, (0..1000).collect::<Vec<_>>());
+        for (index, &key) in table.order.iter().enumerate() {
+            let hashes = hash(table.seed, &[keys[key].as_bytes()]);
+            assert_eq!(slot(hashes, &table.displacements, keys.len()), index);
+        }
+    }
+
+    #[test]
+    fn test_static_catalog() {
+        static DISPLACEMENTS: [(u32, u32); 1] = [(0, 0)];
+        static MESSAGES: [StaticMessage; 0] = [];
+        static EMPTY: StaticCatalog = StaticCatalog::__new(0, &DISPLACEMENTS, &MESSAGES, plural);
+        assert_eq!(EMPTY.gettext("Text"), "Text");
+        assert_eq!(EMPTY.ngettext("Text", "Texts", 2), "Texts");
+
+        let keys = ["Text", "ctx\x04Text", "Image"];
+        let table = build_phf(&keys);
+        let translations: [&'static [&'static str]; 3] = [
+            &["Tekstas", "Tekstai"],
+            &["Tekstas kontekste", "Tekstai kontekste"],
+            &["Paveikslelis"],
+        ];
+        let messages = table
+            .order
+            .iter()
+            .map(|&i| StaticMessage::__new(keys[i], translations[i]))
+            .collect::<Vec<_>>();
+        let cat = StaticCatalog::__new(
+            table.seed,
+            Box::leak(table.displacements.into_boxed_slice()),
+            Box::leak(messages.into_boxed_slice()),
+            plural,
+        );
+
+        assert_eq!(cat.gettext("Text"), "Tekstas");
+        assert_eq!(cat.gettext("Image"), "Paveikslelis");
+        assert_eq!(cat.gettext("Sound"), "Sound");
+        assert_eq!(cat.ngettext("Text", "Texts", 1), "Tekstas");
+        assert_eq!(cat.ngettext("Text", "Texts", 2), "Tekstai");
+        assert_eq!(cat.ngettext("Image", "Images", 2), "Images");
+        assert_eq!(cat.pgettext("ctx", "Text"), "Tekstas kontekste");
+        assert_eq!(cat.pgettext("other", "Text"), "Text");
+        assert_eq!(
+            cat.npgettext("ctx", "Text", "Texts", 5),
+            "Tekstai kontekste"
+        );
+        assert_eq!(cat.npgettext("ctx", "Image", "Images", 5), "Images");
+    }
+}
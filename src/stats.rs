@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::po::{PoEntry, PoFile};
+use crate::{key_with_context, Catalog, Message};
+
+/// Stats holds the translation coverage of a catalog or a PO file,
+/// as returned by `Catalog::stats`, `Catalog::stats_against` and `PoFile::stats`.
+///
+/// The header entry is not counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The counts over all the messages.
+    pub total: Counts,
+    /// The counts for each message context,
+    /// with `None` standing for the messages without a context.
+    pub contexts: BTreeMap<Option<String>, Counts>,
+}
+
+/// Counts holds the number of messages in each state of translation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Messages with all their forms translated, not marked as fuzzy.
+    pub translated: usize,
+    /// Translated messages marked as fuzzy, i.e. in need of review.
+    pub fuzzy: usize,
+    /// Messages missing a translation of any of their forms.
+    pub untranslated: usize,
+    /// Messages which are no longer used: obsolete entries of a PO file,
+    /// or messages of a catalog not present in the template it is compared against.
+    pub obsolete: usize,
+    /// Messages which are not obsolete and have a plural form,
+    /// regardless of their state of translation.
+    pub plural: usize,
+}
+
+/// The state of translation of a single message.
+enum State {
+    Translated,
+    Fuzzy,
+    Untranslated,
+    Obsolete,
+}
+
+impl Counts {
+    /// Returns the percentage of the messages in use which are translated
+    /// and not marked as fuzzy, or 100 if there are no such messages.
+    pub fn percent_complete(&self) -> f64 {
+        let total = self.translated + self.fuzzy + self.untranslated;
+        if total == 0 {
+            100.0
+        } else {
+            self.translated as f64 * 100.0 / total as f64
+        }
+    }
+
+    fn add(&mut self, state: &State, plural: bool) {
+        match *state {
+            State::Translated => self.translated += 1,
+            State::Fuzzy => self.fuzzy += 1,
+            State::Untranslated => self.untranslated += 1,
+            State::Obsolete => self.obsolete += 1,
+        }
+        if plural && !matches!(*state, State::Obsolete) {
+            self.plural += 1;
+        }
+    }
+}
+
+/// Formats the counts the way `msgfmt --statistics` does,
+/// e.g. `5 translated messages, 1 fuzzy translation, 2 untranslated messages.`
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn plural(n: usize, one: &str, many: &str) -> String {
+            format!("{} {}", n, if n == 1 { one } else { many })
+        }
+        let mut parts = vec![plural(
+            self.translated,
+            "translated message",
+            "translated messages",
+        )];
+        if self.fuzzy != 0 {
+            parts.push(plural(
+                self.fuzzy,
+                "fuzzy translation",
+                "fuzzy translations",
+            ));
+        }
+        if self.untranslated != 0 {
+            parts.push(plural(
+                self.untranslated,
+                "untranslated message",
+                "untranslated messages",
+            ));
+        }
+        write!(f, "{}.", parts.join(", "))
+    }
+}
+
+impl Stats {
+    fn add(&mut self, context: Option<&str>, state: State, plural: bool) {
+        self.total.add(&state, plural);
+        self.contexts
+            .entry(context.map(str::to_owned))
+            .or_default()
+            .add(&state, plural);
+    }
+}
+
+fn is_complete(translations: &[String]) -> bool {
+    !translations.is_empty() && translations.iter().all(|t| !t.is_empty())
+}
+
+fn is_header(message: &Message) -> bool {
+    message.id.is_empty() && message.context.is_none()
+}
+
+/// Returns the state of a message of the catalog, or of one left out of it,
+/// by the same rule as `entry_state`.
+fn message_state(
+    catalog: &Catalog,
+    message: Option<&Message>,
+    context: Option<&str>,
+    id: &str,
+) -> State {
+    match message {
+        Some(message) if !is_complete(&message.translated) => State::Untranslated,
+        _ if catalog.is_fuzzy(context, id) => State::Fuzzy,
+        Some(_) => State::Translated,
+        None => State::Untranslated,
+    }
+}
+
+pub(crate) fn catalog_stats(catalog: &Catalog) -> Stats {
+    let mut stats = Stats::default();
+    for message in catalog.strings.values().filter(|m| !is_header(m)) {
+        let state = message_state(catalog, Some(message), message.context(), message.id());
+        stats.add(message.context(), state, message.plural.is_some());
+    }
+    // Fuzzy messages left out of the catalog are still counted.
+    for ((context, id), &plural) in &catalog.fuzzy {
+        let key = match context {
+            Some(ctxt) => key_with_context(ctxt, id),
+            None => id.to_owned(),
+        };
+        if !catalog.strings.contains_key(&key) {
+            stats.add(context.as_deref(), State::Fuzzy, plural);
+        }
+    }
+    stats
+}
+
+pub(crate) fn catalog_stats_against(catalog: &Catalog, template: &PoFile) -> Stats {
+    let mut stats = Stats::default();
+    let mut used = vec![];
+    for entry in template
+        .entries
+        .iter()
+        .filter(|e| !e.is_header() && !e.obsolete)
+    {
+        let key = match entry.context {
+            Some(ref ctxt) => key_with_context(ctxt, &entry.id),
+            None => entry.id.clone(),
+        };
+        let message = catalog.strings.get(&key);
+        let state = message_state(catalog, message, entry.context.as_deref(), &entry.id);
+        stats.add(entry.context.as_deref(), state, entry.id_plural.is_some());
+        used.push(key);
+    }
+    used.sort();
+    for (key, message) in &catalog.strings {
+        if !is_header(message) && used.binary_search(key).is_err() {
+            stats.add(message.context(), State::Obsolete, message.plural.is_some());
+        }
+    }
+    stats
+}
+
+pub(crate) fn po_stats(po: &PoFile) -> Stats {
+    let mut stats = Stats::default();
+    for entry in po.entries.iter().filter(|e| !e.is_header()) {
+        stats.add(
+            entry.context.as_deref(),
+            entry_state(entry),
+            entry.id_plural.is_some(),
+        );
+    }
+    stats
+}
+
+/// Returns the state of an entry of a PO file:
+/// untranslated unless all its forms are, even if it is marked as fuzzy,
+/// like `msgfmt --statistics` counts entries with an empty translation.
+fn entry_state(entry: &PoEntry) -> State {
+    if entry.obsolete {
+        State::Obsolete
+    } else if !is_complete(&entry.translations) {
+        State::Untranslated
+    } else if entry.has_flag("fuzzy") {
+        State::Fuzzy
+    } else {
+        State::Translated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    const PO: &str = r#"
+msgid ""
+msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+msgid "Translated"
+msgstr "Isverstas"
+
+#, fuzzy
+msgctxt "menu"
+msgid "Fuzzy"
+msgstr "Neaiskus"
+
+msgctxt "menu"
+msgid "Untranslated"
+msgstr ""
+
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] ""
+
+#~ msgid "Obsolete"
+#~ msgstr "Pasenes"
+"#;
+
+    #[test]
+    fn test_po_stats() {
+        let po = PoFile::parse(PO.as_bytes()).unwrap();
+        let stats = po.stats();
+        assert_eq!(
+            stats.total,
+            Counts {
+                translated: 1,
+                fuzzy: 1,
+                untranslated: 2,
+                obsolete: 1,
+                plural: 1,
+            }
+        );
+        assert_eq!(stats.contexts[&Some("menu".to_owned())].fuzzy, 1);
+        assert_eq!(stats.contexts[&Some("menu".to_owned())].untranslated, 1);
+        assert_eq!(stats.contexts[&None].obsolete, 1);
+        assert_eq!(stats.total.percent_complete(), 25.0);
+        assert_eq!(
+            stats.total.to_string(),
+            "1 translated message, 1 fuzzy translation, 2 untranslated messages."
+        );
+        assert_eq!(Counts::default().to_string(), "0 translated messages.");
+        assert_eq!(Counts::default().percent_complete(), 100.0);
+    }
+
+    #[test]
+    fn test_catalog_stats() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let stats = catalog.stats();
+        assert_eq!(stats.total.translated, 1);
+        assert_eq!(stats.total.fuzzy, 1);
+        // Untranslated messages, including "File" with an empty form, are left out.
+        assert_eq!(stats.total.untranslated, 0);
+        assert_eq!(stats.total.plural, 0);
+
+        let catalog = ParseOptions::new()
+            .include_fuzzy(true)
+            .parse_po(PO.as_bytes())
+            .unwrap();
+        assert_eq!(catalog.stats().total.fuzzy, 1);
+        assert_eq!(catalog.stats().total.translated, 1);
+    }
+
+    #[test]
+    fn test_stats_agree() {
+        let po = r#"
+#, fuzzy
+msgid "File"
+msgid_plural "Files"
+msgstr[0] ""
+msgstr[1] ""
+
+#, fuzzy
+msgid "Folder"
+msgid_plural "Folders"
+msgstr[0] "Aplankas"
+msgstr[1] ""
+
+#, fuzzy
+msgid "Image"
+msgid_plural "Images"
+msgstr[0] "Paveikslas"
+msgstr[1] "Paveikslai"
+"#;
+        assert_eq!(
+            PoFile::parse(po.as_bytes()).unwrap().stats().total,
+            Counts {
+                fuzzy: 1,
+                untranslated: 2,
+                plural: 3,
+                ..Counts::default()
+            }
+        );
+        // Of the messages left out of the catalog, only the fuzzy ones are known to it,
+        // and the partially translated "Folder" is left out as well.
+        for include_fuzzy in [false, true] {
+            let catalog = ParseOptions::new()
+                .include_fuzzy(include_fuzzy)
+                .parse_po(po.as_bytes())
+                .unwrap();
+            assert_eq!(
+                catalog.stats().total,
+                Counts {
+                    fuzzy: 1,
+                    plural: 1,
+                    ..Counts::default()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog_stats_against() {
+        let catalog = Catalog::parse_po(PO.as_bytes()).unwrap();
+        let template = PoFile::parse(
+            &br#"
+msgctxt "menu"
+msgid "Fuzzy"
+msgstr ""
+
+msgid "File"
+msgid_plural "Files"
+msgstr[0] ""
+msgstr[1] ""
+
+msgid "New"
+msgstr ""
+"#[..],
+        )
+        .unwrap();
+        let stats = catalog.stats_against(&template);
+        assert_eq!(
+            stats.total,
+            Counts {
+                translated: 0,
+                fuzzy: 1,
+                untranslated: 2,
+                obsolete: 1,
+                plural: 1,
+            }
+        );
+        assert_eq!(stats.contexts[&None].obsolete, 1);
+    }
+}
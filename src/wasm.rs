@@ -0,0 +1,115 @@
+//! Bindings exposing catalogs to JavaScript through `wasm-bindgen`,
+//! so that a web client can use the same MO files and plural formulas as the server.
+//!
+//! Built for `wasm32-unknown-unknown` with the `wasm-bindgen` feature,
+//! the crate exports a `Catalog` class:
+//!
+//! ```js
+//! const response = await fetch("/locale/lt/LC_MESSAGES/messages.mo");
+//! const catalog = new Catalog(new Uint8Array(await response.arrayBuffer()));
+//! catalog.gettext("Open");
+//! catalog.ngettext("%d file", "%d files", 3);
+//! catalog.pgettext("menu", "Open");
+//! catalog.npgettext("menu", "%d file", "%d files", 3);
+//! catalog.free();
+//! ```
+//!
+//! The count given to `ngettext` and `npgettext` is truncated to an integer,
+//! with negative numbers counting as 0.
+
+use alloc::string::{String, ToString};
+
+use wasm_bindgen::prelude::*;
+
+use crate::Catalog;
+
+/// WasmCatalog is the `Catalog` class of JavaScript,
+/// a catalog parsed from an MO file.
+#[wasm_bindgen(js_name = Catalog)]
+#[derive(Debug)]
+pub struct WasmCatalog(Catalog);
+
+#[wasm_bindgen(js_class = Catalog)]
+impl WasmCatalog {
+    /// Parses the catalog from the bytes of an MO file, e.g. a `Uint8Array`.
+    /// Throws an `Error` upon an invalid file.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmCatalog, JsError> {
+        Catalog::parse_bytes(bytes)
+            .map(WasmCatalog)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Creates an empty catalog, which returns the original strings.
+    pub fn empty() -> WasmCatalog {
+        WasmCatalog(Catalog::empty())
+    }
+
+    /// Returns the translation of `msgId`, see `Catalog::gettext`.
+    pub fn gettext(&self, msg_id: &str) -> String {
+        self.0.gettext(msg_id).to_string()
+    }
+
+    /// Returns the plural form of the translation of `msgId` for `n`,
+    /// see `Catalog::ngettext`.
+    pub fn ngettext(&self, msg_id: &str, msg_id_plural: &str, n: f64) -> String {
+        self.0.ngettext(msg_id, msg_id_plural, count(n)).to_string()
+    }
+
+    /// Returns the translation of `msgId` in the given context,
+    /// see `Catalog::pgettext`.
+    pub fn pgettext(&self, msg_context: &str, msg_id: &str) -> String {
+        self.0.pgettext(msg_context, msg_id).to_string()
+    }
+
+    /// Returns the plural form of the translation of `msgId` in the given context for `n`,
+    /// see `Catalog::npgettext`.
+    pub fn npgettext(
+        &self,
+        msg_context: &str,
+        msg_id: &str,
+        msg_id_plural: &str,
+        n: f64,
+    ) -> String {
+        self.0
+            .npgettext(msg_context, msg_id, msg_id_plural, count(n))
+            .to_string()
+    }
+}
+
+/// Converts a JavaScript number to a count, saturating at the bounds of `u64`.
+fn count(n: f64) -> u64 {
+    n as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let catalog = WasmCatalog::new(include_bytes!("../test_cases/integration.mo")).unwrap();
+        assert_eq!(catalog.gettext("existent"), "egzistuojantis");
+        assert_eq!(
+            catalog.ngettext("a good string", "good strings", 2.0),
+            "geros eilutes"
+        );
+        assert_eq!(
+            catalog.pgettext("ctxt", "existent"),
+            "egzistuojantis kontekste"
+        );
+        assert_eq!(
+            catalog.npgettext("ctxt", "a good string", "good strings", 1.0),
+            "gera eilute kontekste"
+        );
+        assert_eq!(WasmCatalog::empty().gettext("Open"), "Open");
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(3.0), 3);
+        assert_eq!(count(2.9), 2);
+        assert_eq!(count(-1.0), 0);
+        assert_eq!(count(f64::NAN), 0);
+    }
+}
@@ -0,0 +1,307 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::decoder::{self, Decoder};
+use crate::metadata::parse_metadata;
+use crate::po::PoFile;
+use crate::{Catalog, Error};
+
+const MAGIC: u32 = 0x9504_12de;
+const HEADER_LEN: u32 = 28;
+
+/// WriteOptions allows setting options for writing out catalogs and PO files.
+///
+/// # Examples
+/// ```
+/// use gettext::{Catalog, WriteOptions};
+///
+/// let po = "msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\n\
+///           msgid \"Garlic\"\nmsgstr \"Česnakas\"\n";
+/// let catalog = Catalog::parse_po(po.as_bytes()).unwrap();
+///
+/// let mut mo = vec![];
+/// WriteOptions::new()
+///     .encoding(encoding_rs::WINDOWS_1257)
+///     .write_mo(&catalog, &mut mo)
+///     .unwrap();
+/// assert!(mo.windows(8).any(|s| s == b"\xc8esnakas"));
+///
+/// let catalog = Catalog::parse(&mo[..]).unwrap();
+/// assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    encoding: Option<Arc<dyn Decoder>>,
+}
+
+impl WriteOptions {
+    /// Returns a new instance of WriteOptions with default options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the encoding to write the strings in,
+    /// e.g. `encoding_rs::WINDOWS_1257` or any other implementation of `Decoder`.
+    /// If this option is not set, they are written in UTF-8.
+    ///
+    /// The charset in the `Content-Type` header field is updated accordingly.
+    /// Writing fails with `Error::UnrepresentableCharacter` if a string has a character
+    /// the encoding can not represent.
+    pub fn encoding<D: Decoder + 'static>(mut self, encoding: D) -> Self {
+        self.encoding = Some(Arc::new(encoding));
+        self
+    }
+
+    /// Writes the catalog out as a binary MO file.
+    pub fn write_mo<W: Write>(&self, catalog: &Catalog, writer: W) -> Result<(), Error> {
+        let utf8 = decoder::utf8();
+        write_mo(catalog, self.encoding.as_deref().unwrap_or(&*utf8), writer)
+    }
+
+    /// Writes the PO file out, converting it from the charset it was parsed with.
+    ///
+    /// Just like with `PoFile::write`, the entries which are unchanged since the file
+    /// was parsed keep their original form, except for their encoding.
+    pub fn write_po<W: Write>(&self, file: &PoFile, writer: W) -> Result<(), Error> {
+        let utf8 = decoder::utf8();
+        file.write_encoded(writer, Some(self.encoding.as_deref().unwrap_or(&*utf8)))
+    }
+}
+
+/// Writes the catalog out as a little-endian MO file with the strings in the given encoding.
+///
+/// The original strings are sorted, so that lookups can use binary search,
+/// and no hash table is written, which is allowed by the format.
+pub(crate) fn write_mo<W: Write>(
+    catalog: &Catalog,
+    encoding: &dyn Decoder,
+    mut writer: W,
+) -> Result<(), Error> {
+    let mut strings = catalog
+        .strings
+        .iter()
+        .map(|(key, msg)| {
+            let location = || message_location(msg.context.as_deref(), &msg.id);
+            let mut original = encode(encoding, key, location)?;
+            if let Some(ref plural) = msg.plural {
+                original.push(0);
+                original.extend(encode(encoding, plural, location)?);
+            }
+            let translated = msg.translated.join("\0");
+            let translated = if key.is_empty() && !declares(&translated, encoding) {
+                with_charset(&translated, encoding.name())
+            } else {
+                translated
+            };
+            Ok((original, encode(encoding, &translated, location)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    // Without a header, the strings would be read as UTF-8.
+    if !catalog.strings.contains_key("") && !decoder::is_utf8(encoding) {
+        let header = with_charset("", encoding.name());
+        strings.push((vec![], encode(encoding, &header, String::new)?));
+    }
+    strings.sort();
+
+    let n = strings.len() as u32;
+    writer.write_u32::<LittleEndian>(MAGIC)?;
+    writer.write_u32::<LittleEndian>(0)?; // revision
+    writer.write_u32::<LittleEndian>(n)?;
+    writer.write_u32::<LittleEndian>(HEADER_LEN)?; // original strings table
+    writer.write_u32::<LittleEndian>(HEADER_LEN + 8 * n)?; // translated strings table
+    writer.write_u32::<LittleEndian>(0)?; // hash table size
+    writer.write_u32::<LittleEndian>(HEADER_LEN + 16 * n)?; // hash table offset
+
+    let mut offset = HEADER_LEN + 16 * n;
+    let originals = strings.iter().map(|s| &s.0);
+    let translations = strings.iter().map(|s| &s.1);
+    for s in originals.clone().chain(translations.clone()) {
+        writer.write_u32::<LittleEndian>(s.len() as u32)?;
+        writer.write_u32::<LittleEndian>(offset)?;
+        // +1 for the ending NUL byte which is not included in length
+        offset += s.len() as u32 + 1;
+    }
+    for s in originals.chain(translations) {
+        writer.write_all(s)?;
+        writer.write_all(&[0])?;
+    }
+    Ok(())
+}
+
+/// Returns whether the charset in the header names the given encoding,
+/// e.g. `cp1257` for windows-1257, in which case the header is kept as it is.
+fn declares(header: &str, encoding: &dyn Decoder) -> bool {
+    parse_metadata(header)
+        .ok()
+        .and_then(|metadata| metadata.charset().and_then(decoder::for_label))
+        .is_some_and(|declared| declared.name() == encoding.name())
+}
+
+/// Replaces the charset in the `Content-Type` header field with UTF-8.
+pub(crate) fn utf8_header(header: &str) -> String {
+    with_charset(header, "UTF-8")
+}
+
+/// Replaces the charset in the `Content-Type` header field with the given one,
+/// adding the field if it is missing.
+pub(crate) fn with_charset(header: &str, charset: &str) -> String {
+    let content_type = format!("Content-Type: text/plain; charset={}\n", charset);
+    let mut found = false;
+    let mut replaced = header
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.starts_with("Content-Type:") {
+                found = true;
+                &content_type
+            } else {
+                line
+            }
+        })
+        .collect::<String>();
+    if !found {
+        if !replaced.is_empty() && !replaced.ends_with('\n') {
+            replaced.push('\n');
+        }
+        replaced.push_str(&content_type);
+    }
+    replaced
+}
+
+/// Describes where a message is for error messages, e.g. `msgctxt "menu" msgid "Open"`.
+pub(crate) fn message_location(context: Option<&str>, id: &str) -> String {
+    match (context, id) {
+        (None, "") => "the header".to_owned(),
+        (Some(context), _) => format!("msgctxt {:?} msgid {:?}", context, id),
+        (None, _) => format!("msgid {:?}", id),
+    }
+}
+
+/// Encodes the string in the given encoding,
+/// or returns `Error::UnrepresentableCharacter` with the given location.
+pub(crate) fn encode<F: FnOnce() -> String>(
+    encoding: &dyn Decoder,
+    s: &str,
+    location: F,
+) -> Result<Vec<u8>, Error> {
+    if decoder::is_utf8(encoding) {
+        return Ok(s.as_bytes().to_vec());
+    }
+    encoding.encode(s).ok_or_else(|| {
+        let character = s
+            .chars()
+            .find(|c| encoding.encode(c.encode_utf8(&mut [0; 4])).is_none())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        Error::UnrepresentableCharacter {
+            character,
+            location: location(),
+            encoding: encoding.name().to_owned(),
+        }
+    })
+}
+
+#[test]
+fn test_write_mo() {
+    use crate::Message;
+
+    let mut catalog = Catalog::new();
+    catalog.insert(Message::new(
+        "",
+        None,
+        vec!["Content-Type: text/plain; charset=cp1257\nPlural-Forms: nplurals=3; plural=n;\n"],
+    ));
+    catalog.insert(
+        Message::new("Text", Some("ctx"), vec!["Tekstas", "Tekstai", "Tekstų"])
+            .with_plural(Some("Texts")),
+    );
+    catalog.insert(Message::new("Image", None, vec!["Paveikslėlis"]));
+
+    let mut mo = vec![];
+    catalog.write_mo(&mut mo).unwrap();
+    let parsed = Catalog::parse(&mo[..]).unwrap();
+    assert_eq!(
+        parsed.strings[""].translated,
+        ["Content-Type: text/plain; charset=UTF-8\nPlural-Forms: nplurals=3; plural=n;\n"]
+    );
+    assert_eq!(
+        parsed.strings["ctx\x04Text"],
+        catalog.strings["ctx\x04Text"]
+    );
+    assert_eq!(parsed.strings["Image"], catalog.strings["Image"]);
+    assert_eq!(parsed.npgettext("ctx", "Text", "Texts", 2), "Tekstų");
+
+    let mut empty = vec![];
+    Catalog::new().write_mo(&mut empty).unwrap();
+    assert_eq!(empty.len(), HEADER_LEN as usize);
+    assert_eq!(Catalog::parse(&empty[..]).unwrap().strings.len(), 0);
+}
+
+#[test]
+fn test_write_mo_encoding() {
+    use crate::Message;
+
+    let cp1257 = WriteOptions::new().encoding(encoding_rs::WINDOWS_1257);
+    let mut catalog = Catalog::new();
+    catalog.insert(Message::new("Garlic", None, vec!["Česnakas"]));
+
+    // A header is added for the charset.
+    let mut mo = vec![];
+    cp1257.write_mo(&catalog, &mut mo).unwrap();
+    assert!(mo.windows(8).any(|s| s == b"\xc8esnakas"));
+    let parsed = Catalog::parse(&mo[..]).unwrap();
+    assert_eq!(
+        parsed.strings[""].translated,
+        ["Content-Type: text/plain; charset=windows-1257\n"]
+    );
+    assert_eq!(parsed.gettext("Garlic"), "Česnakas");
+
+    // A header naming the encoding by another label is kept.
+    let header = "Language: lt\nContent-Type: text/plain; charset=cp1257\n";
+    catalog.insert(Message::new("", None, vec![header]));
+    let mut mo = vec![];
+    cp1257.write_mo(&catalog, &mut mo).unwrap();
+    assert_eq!(
+        Catalog::parse(&mo[..]).unwrap().strings[""].translated,
+        [header]
+    );
+    let mut mo = vec![];
+    catalog.write_mo(&mut mo).unwrap();
+    assert_eq!(
+        Catalog::parse(&mo[..]).unwrap().strings[""].translated,
+        ["Language: lt\nContent-Type: text/plain; charset=UTF-8\n"]
+    );
+
+    catalog.insert(Message::new("Onion", Some("vegetable"), vec!["Лук"]));
+    match cp1257.write_mo(&catalog, &mut vec![]) {
+        Err(Error::UnrepresentableCharacter {
+            character,
+            location,
+            encoding,
+        }) => {
+            assert_eq!(character, 'Л');
+            assert_eq!(location, "msgctxt \"vegetable\" msgid \"Onion\"");
+            assert_eq!(encoding, "windows-1257");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_with_charset() {
+    assert_eq!(
+        with_charset(
+            "Language: lt\nContent-Type: text/plain; charset=UTF-8\n",
+            "ISO-8859-13"
+        ),
+        "Language: lt\nContent-Type: text/plain; charset=ISO-8859-13\n"
+    );
+    assert_eq!(
+        with_charset("Language: lt", "UTF-8"),
+        "Language: lt\nContent-Type: text/plain; charset=UTF-8\n"
+    );
+    assert_eq!(
+        with_charset("", "UTF-8"),
+        "Content-Type: text/plain; charset=UTF-8\n"
+    );
+}
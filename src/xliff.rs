@@ -0,0 +1,681 @@
+//! Conversion between PO files and [XLIFF](https://docs.oasis-open.org/xliff/) 1.2 and 2.0
+//! documents, as used by translation vendors.
+//!
+//! Each message becomes a translation unit, with its context as the unit's name
+//! (`resname` in XLIFF 1.2, `name` in 2.0) and its comments and references as notes.
+//! A message with a plural form becomes a group of units, one for each plural form,
+//! the first of which has the singular original string and the rest the plural one.
+//! The PO header is kept in a note of the file.
+//!
+//! Fuzzy translations are marked as `needs-review-translation` in XLIFF 1.2
+//! and as `initial` in 2.0, where a translated segment is `translated`.
+//! Flags other than `fuzzy`, previous strings and obsolete messages are not converted.
+//!
+//! A `Catalog` can be converted using `PoFile::from_catalog` and `PoFile::to_catalog`.
+//!
+//! ```
+//! use gettext::po::PoFile;
+//! use gettext::xliff::{self, Version};
+//!
+//! let po = PoFile::parse(&b"msgid \"Open\"\nmsgstr \"Atverti\"\n"[..]).unwrap();
+//! let mut document = vec![];
+//! xliff::write(&po, Version::V2_0, &mut document).unwrap();
+//! assert_eq!(xliff::parse(&document[..]).unwrap().entries[1].translations, ["Atverti"]);
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::po::{PoEntry, PoFile};
+use crate::xml::{self, Element};
+use crate::Error::{self, InvalidDocument};
+
+/// The note category holding the PO header.
+const HEADER_NOTE: &str = "x-gettext-header";
+/// The `restype` (1.2) or `type` (2.0) of the groups holding the plural forms of a message.
+const PLURAL_GROUP_1_2: &str = "x-gettext-plurals";
+const PLURAL_GROUP_2_0: &str = "gettext:plurals";
+
+/// Version is the version of XLIFF to write documents in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    /// XLIFF 1.2.
+    V1_2,
+    /// XLIFF 2.0.
+    V2_0,
+}
+
+/// The state of a translation.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Untranslated,
+    Fuzzy,
+    Translated,
+}
+
+fn entry_state(entry: &PoEntry) -> State {
+    if entry.translations.iter().all(|t| t.is_empty()) {
+        State::Untranslated
+    } else if entry.has_flag("fuzzy") {
+        State::Fuzzy
+    } else {
+        State::Translated
+    }
+}
+
+/// Splits a reference like `src/main.rs:42` into the file and the line.
+fn split_reference(reference: &str) -> (&str, Option<&str>) {
+    match reference.rfind(':') {
+        Some(i)
+            if reference[i + 1..].bytes().all(|b| b.is_ascii_digit())
+                && i + 1 < reference.len() =>
+        {
+            (&reference[..i], Some(&reference[i + 1..]))
+        }
+        _ => (reference, None),
+    }
+}
+
+/// Writes the PO file as an XLIFF document of the given version.
+///
+/// The source language is taken from the `X-Source-Language` header field, or is `en`,
+/// and the target language from the `Language` field.
+pub fn write<W: Write>(po: &PoFile, version: Version, mut writer: W) -> io::Result<()> {
+    let header = po.header().and_then(|h| h.translations.first());
+    let field = |name: &str| {
+        header.and_then(|h| {
+            h.lines()
+                .find_map(|l| l.strip_prefix(name)?.strip_prefix(':'))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        })
+    };
+    let source_language = field("X-Source-Language").unwrap_or("en");
+    let target_language = field("Language");
+    let entries = po.entries.iter().filter(|e| !e.is_header() && !e.obsolete);
+
+    let root = match version {
+        Version::V1_2 => {
+            let mut file = Element::new("file")
+                .attr("original", "messages")
+                .attr("source-language", source_language);
+            if let Some(language) = target_language {
+                file = file.attr("target-language", language);
+            }
+            file = file.attr("datatype", "po");
+            if let Some(header) = header {
+                file = file.child(
+                    Element::new("header")
+                        .child(Element::new("note").attr("from", HEADER_NOTE).text(header)),
+                );
+            }
+            let mut body = Element::new("body");
+            for (i, entry) in entries.enumerate() {
+                body = body.child(unit_1_2(entry, &(i + 1).to_string()));
+            }
+            Element::new("xliff")
+                .attr("version", "1.2")
+                .attr("xmlns", "urn:oasis:names:tc:xliff:document:1.2")
+                .child(file.child(body))
+        }
+        Version::V2_0 => {
+            let mut file = Element::new("file").attr("id", "f1");
+            if let Some(header) = header {
+                file = file.child(
+                    Element::new("notes").child(
+                        Element::new("note")
+                            .attr("category", HEADER_NOTE)
+                            .text(header),
+                    ),
+                );
+            }
+            for (i, entry) in entries.enumerate() {
+                file = file.child(unit_2_0(entry, i + 1));
+            }
+            let mut root = Element::new("xliff")
+                .attr("xmlns", "urn:oasis:names:tc:xliff:document:2.0")
+                .attr("version", "2.0")
+                .attr("srcLang", source_language);
+            if let Some(language) = target_language {
+                root = root.attr("trgLang", language);
+            }
+            root.child(file)
+        }
+    };
+    writer.write_all(root.to_document(None).as_bytes())
+}
+
+/// Returns the original string of each plural form of the entry, and its translation.
+fn forms(entry: &PoEntry) -> Vec<(&str, Option<&str>)> {
+    let count = match entry.id_plural {
+        Some(_) => entry.translations.len().max(2),
+        None => 1,
+    };
+    (0..count)
+        .map(|i| {
+            let source = match (i, &entry.id_plural) {
+                (0, _) | (_, None) => entry.id.as_str(),
+                (_, Some(ref plural)) => plural.as_str(),
+            };
+            let target = entry
+                .translations
+                .get(i)
+                .map(String::as_str)
+                .filter(|t| !t.is_empty());
+            (source, target)
+        })
+        .collect()
+}
+
+/// Returns the notes and location contexts of an XLIFF 1.2 unit or group.
+fn annotations_1_2(entry: &PoEntry) -> Vec<Element> {
+    let mut annotations = vec![];
+    for reference in &entry.references {
+        let (file, line) = split_reference(reference);
+        let mut group = Element::new("context-group")
+            .attr("purpose", "location")
+            .child(
+                Element::new("context")
+                    .attr("context-type", "sourcefile")
+                    .text(file),
+            );
+        if let Some(line) = line {
+            group = group.child(
+                Element::new("context")
+                    .attr("context-type", "linenumber")
+                    .text(line),
+            );
+        }
+        annotations.push(group);
+    }
+    for comment in &entry.extracted_comments {
+        annotations.push(Element::new("note").attr("from", "developer").text(comment));
+    }
+    for comment in &entry.translator_comments {
+        annotations.push(
+            Element::new("note")
+                .attr("from", "translator")
+                .text(comment),
+        );
+    }
+    annotations
+}
+
+fn unit_1_2(entry: &PoEntry, id: &str) -> Element {
+    let state = match entry_state(entry) {
+        State::Fuzzy => "needs-review-translation",
+        _ => "translated",
+    };
+    let trans_unit = |id: &str, source: &str, target: Option<&str>| {
+        let mut unit = Element::new("trans-unit").attr("id", id);
+        if let Some(ref context) = entry.context {
+            unit = unit.attr("resname", context);
+        }
+        unit = unit.child(Element::new("source").text(source));
+        if let Some(target) = target {
+            unit = unit.child(Element::new("target").attr("state", state).text(target));
+        }
+        unit
+    };
+    let forms = forms(entry);
+    if entry.id_plural.is_none() {
+        let mut unit = trans_unit(id, forms[0].0, forms[0].1);
+        unit.children
+            .extend(annotations_1_2(entry).into_iter().map(xml::Node::Element));
+        return unit;
+    }
+    let mut group = Element::new("group")
+        .attr("id", id)
+        .attr("restype", PLURAL_GROUP_1_2);
+    if let Some(ref context) = entry.context {
+        group = group.attr("resname", context);
+    }
+    for annotation in annotations_1_2(entry) {
+        group = group.child(annotation);
+    }
+    for (i, (source, target)) in forms.into_iter().enumerate() {
+        group = group.child(trans_unit(&format!("{}[{}]", id, i), source, target));
+    }
+    group
+}
+
+/// Returns the notes of an XLIFF 2.0 unit or group, if it has any.
+fn notes_2_0(entry: &PoEntry) -> Option<Element> {
+    let notes = entry
+        .references
+        .iter()
+        .map(|r| ("location", r))
+        .chain(entry.extracted_comments.iter().map(|c| ("developer", c)))
+        .chain(entry.translator_comments.iter().map(|c| ("translator", c)))
+        .map(|(category, text)| Element::new("note").attr("category", category).text(text))
+        .collect::<Vec<_>>();
+    if notes.is_empty() {
+        return None;
+    }
+    Some(
+        notes
+            .into_iter()
+            .fold(Element::new("notes"), Element::child),
+    )
+}
+
+fn unit_2_0(entry: &PoEntry, number: usize) -> Element {
+    let state = match entry_state(entry) {
+        State::Translated => "translated",
+        _ => "initial",
+    };
+    let unit = |id: String, source: &str, target: Option<&str>| {
+        let mut segment = Element::new("segment").attr("state", state);
+        segment = segment.child(Element::new("source").text(source));
+        if let Some(target) = target {
+            segment = segment.child(Element::new("target").text(target));
+        }
+        let mut unit = Element::new("unit").attr("id", &id);
+        if let Some(ref context) = entry.context {
+            unit = unit.attr("name", context);
+        }
+        unit.child(segment)
+    };
+    let forms = forms(entry);
+    if entry.id_plural.is_none() {
+        let mut unit = unit(format!("u{}", number), forms[0].0, forms[0].1);
+        if let Some(notes) = notes_2_0(entry) {
+            unit.children.insert(0, xml::Node::Element(notes));
+        }
+        return unit;
+    }
+    let mut group = Element::new("group")
+        .attr("id", &format!("g{}", number))
+        .attr("type", PLURAL_GROUP_2_0);
+    if let Some(ref context) = entry.context {
+        group = group.attr("name", context);
+    }
+    if let Some(notes) = notes_2_0(entry) {
+        group = group.child(notes);
+    }
+    for (i, (source, target)) in forms.into_iter().enumerate() {
+        group = group.child(unit(format!("g{}-{}", number, i), source, target));
+    }
+    group
+}
+
+/// Parses an XLIFF 1.2 or 2.0 document into a PO file.
+///
+/// The header of the PO file is taken from the note it was kept in, if any;
+/// otherwise a header with the target language, its plural forms and the UTF-8 charset
+/// is created.
+/// Units in a state starting with `needs-review` (1.2) or in the `initial` state (2.0)
+/// which have a translation are marked as fuzzy.
+/// The text of inline elements is kept, but the elements themselves are not.
+pub fn parse<R: Read>(reader: R) -> Result<PoFile, Error> {
+    let root = xml::read(reader)?;
+    if root.name != "xliff" {
+        return Err(InvalidDocument(format!(
+            "expected an `xliff` element, found `{}`",
+            root.name
+        )));
+    }
+
+    let mut header = None;
+    let mut target_language = None;
+    let mut entries = vec![];
+    match root.get("version") {
+        Some("1.2") | Some("1.1") | Some("1.0") => {
+            for file in root.all("file") {
+                target_language = target_language.or(file.get("target-language"));
+                let notes = file.first("header").into_iter().flat_map(|h| h.all("note"));
+                for note in notes {
+                    if note.get("from") == Some(HEADER_NOTE) {
+                        header = Some(note.content());
+                    }
+                }
+                if let Some(body) = file.first("body") {
+                    read_body_1_2(body, &mut entries)?;
+                }
+            }
+        }
+        Some(version) if version.starts_with("2.") => {
+            target_language = root.get("trgLang");
+            for file in root.all("file") {
+                let notes = file.first("notes").into_iter().flat_map(|n| n.all("note"));
+                for note in notes {
+                    if note.get("category") == Some(HEADER_NOTE) {
+                        header = Some(note.content());
+                    }
+                }
+                read_group_2_0(file, &mut entries)?;
+            }
+        }
+        version => {
+            return Err(InvalidDocument(format!(
+                "unsupported XLIFF version {}",
+                version.unwrap_or("(none)")
+            )))
+        }
+    }
+
+    let header_entry = match header {
+        Some(header) => {
+            let mut entry = PoEntry::new("");
+            entry.translations = vec![header];
+            entry
+        }
+        None => PoEntry::header_for(target_language),
+    };
+    entries.insert(0, header_entry);
+    let mut po = PoFile::new();
+    po.entries = entries;
+    Ok(po)
+}
+
+fn missing(what: &str, unit: &Element) -> Error {
+    InvalidDocument(format!(
+        "`{}` element without a `{}` in unit {}",
+        unit.name,
+        what,
+        unit.get("id").unwrap_or("(no id)")
+    ))
+}
+
+/// Adds the notes and location contexts of an XLIFF 1.2 unit or group to the entry.
+fn read_annotations_1_2(element: &Element, entry: &mut PoEntry) {
+    for child in element.elements() {
+        match child.name.as_str() {
+            "note" if child.get("from") == Some("translator") => {
+                entry.translator_comments.push(child.content())
+            }
+            "note" => entry.extracted_comments.push(child.content()),
+            "context-group" if child.get("purpose") == Some("location") => {
+                let context = |kind| {
+                    child
+                        .all("context")
+                        .find(|c| c.get("context-type") == Some(kind))
+                        .map(Element::content)
+                };
+                match (context("sourcefile"), context("linenumber")) {
+                    (Some(file), Some(line)) => entry.references.push(format!("{}:{}", file, line)),
+                    (Some(file), None) => entry.references.push(file),
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Reads a `trans-unit`, returning its source, its target and whether it needs review.
+fn read_unit_1_2(unit: &Element) -> Result<(String, String, bool), Error> {
+    let source = unit
+        .first("source")
+        .ok_or_else(|| missing("source", unit))?;
+    let target = unit.first("target");
+    let translated = target.map(Element::content).unwrap_or_default();
+    let review = target
+        .and_then(|t| t.get("state"))
+        .is_some_and(|s| s.starts_with("needs-review"));
+    Ok((source.content(), translated, review))
+}
+
+fn read_body_1_2(body: &Element, entries: &mut Vec<PoEntry>) -> Result<(), Error> {
+    for element in body.elements() {
+        match element.name.as_str() {
+            "trans-unit" => {
+                let (source, target, review) = read_unit_1_2(element)?;
+                let mut entry = PoEntry::new(source);
+                entry.context = element.get("resname").map(str::to_owned);
+                entry.translations = vec![target];
+                read_annotations_1_2(element, &mut entry);
+                finish_entry(&mut entry, review);
+                entries.push(entry);
+            }
+            "group" if element.get("restype") == Some(PLURAL_GROUP_1_2) => {
+                let mut entry = PoEntry::new("");
+                entry.context = element.get("resname").map(str::to_owned);
+                let mut review = false;
+                for (i, unit) in element.all("trans-unit").enumerate() {
+                    let (source, target, needs_review) = read_unit_1_2(unit)?;
+                    match i {
+                        0 => entry.id = source,
+                        1 => entry.id_plural = Some(source),
+                        _ => (),
+                    }
+                    entry.translations.push(target);
+                    review |= needs_review;
+                }
+                if entry.id_plural.is_none() {
+                    return Err(missing("second trans-unit", element));
+                }
+                read_annotations_1_2(element, &mut entry);
+                finish_entry(&mut entry, review);
+                entries.push(entry);
+            }
+            "group" => read_body_1_2(element, entries)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Reads the segments of a `unit`, returning its source, its target and its state.
+fn read_unit_2_0(unit: &Element) -> Result<(String, String, bool), Error> {
+    let mut source = String::new();
+    let mut target = String::new();
+    let mut initial = false;
+    let mut has_source = false;
+    for segment in unit
+        .elements()
+        .filter(|e| e.name == "segment" || e.name == "ignorable")
+    {
+        let segment_source = segment
+            .first("source")
+            .ok_or_else(|| missing("source", unit))?;
+        has_source = true;
+        source.push_str(&segment_source.content());
+        target.push_str(
+            &segment
+                .first("target")
+                .map(Element::content)
+                .unwrap_or_default(),
+        );
+        initial |= segment.name == "segment" && segment.get("state").is_none_or(|s| s == "initial");
+    }
+    if !has_source {
+        return Err(missing("segment", unit));
+    }
+    Ok((source, target, initial))
+}
+
+/// Adds the notes of an XLIFF 2.0 unit or group to the entry.
+fn read_notes_2_0(element: &Element, entry: &mut PoEntry) {
+    let notes = element
+        .first("notes")
+        .into_iter()
+        .flat_map(|n| n.all("note"));
+    for note in notes {
+        match note.get("category") {
+            Some("translator") => entry.translator_comments.push(note.content()),
+            Some("location") => entry.references.push(note.content()),
+            _ => entry.extracted_comments.push(note.content()),
+        }
+    }
+}
+
+fn read_group_2_0(group: &Element, entries: &mut Vec<PoEntry>) -> Result<(), Error> {
+    for element in group.elements() {
+        match element.name.as_str() {
+            "unit" => {
+                let (source, target, initial) = read_unit_2_0(element)?;
+                let mut entry = PoEntry::new(source);
+                entry.context = element.get("name").map(str::to_owned);
+                entry.translations = vec![target];
+                read_notes_2_0(element, &mut entry);
+                finish_entry(&mut entry, initial);
+                entries.push(entry);
+            }
+            "group" if element.get("type") == Some(PLURAL_GROUP_2_0) => {
+                let mut entry = PoEntry::new("");
+                entry.context = element.get("name").map(str::to_owned);
+                let mut initial = false;
+                for (i, unit) in element.all("unit").enumerate() {
+                    let (source, target, unit_initial) = read_unit_2_0(unit)?;
+                    match i {
+                        0 => entry.id = source,
+                        1 => entry.id_plural = Some(source),
+                        _ => (),
+                    }
+                    entry.translations.push(target);
+                    initial |= unit_initial;
+                }
+                if entry.id_plural.is_none() {
+                    return Err(missing("second unit", element));
+                }
+                read_notes_2_0(element, &mut entry);
+                finish_entry(&mut entry, initial);
+                entries.push(entry);
+            }
+            "group" => read_group_2_0(element, entries)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Marks a translated entry which needs review as fuzzy.
+fn finish_entry(entry: &mut PoEntry, needs_review: bool) {
+    if needs_review && entry_state(entry) != State::Untranslated {
+        entry.flags.push("fuzzy".to_owned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"msgid ""
+msgstr ""
+"Language: lt\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11) ? 0 : ((n%10>=2 && (n%100<10 || n%100>=20)) ? 1 : 2);\n"
+
+# A translator's note
+# on two lines
+#. Shown in the toolbar
+#: src/main.rs:42 src/toolbar.rs
+msgid "Open <file> & \"save\""
+msgstr "Atverti <failą> & „išsaugoti“"
+
+#, fuzzy
+msgctxt "menu"
+msgid "File"
+msgid_plural "Files"
+msgstr[0] "Failas"
+msgstr[1] "Failai"
+msgstr[2] "Failų"
+
+msgid "Line one\nline two"
+msgstr ""
+
+msgctxt "empty"
+msgid "Item"
+msgid_plural "Items"
+msgstr[0] ""
+msgstr[1] ""
+msgstr[2] ""
+"#;
+
+    fn round_trip(version: Version) -> (String, PoFile) {
+        let po = PoFile::parse(PO.as_bytes()).unwrap();
+        let mut document = vec![];
+        write(&po, version, &mut document).unwrap();
+        let document = String::from_utf8(document).unwrap();
+        let parsed = parse(document.as_bytes()).unwrap();
+        assert_eq!(parsed, po, "{}", document);
+        (document, parsed)
+    }
+
+    #[test]
+    fn test_round_trip_1_2() {
+        let (document, _) = round_trip(Version::V1_2);
+        assert!(document.contains(
+            r#"<file original="messages" source-language="en" target-language="lt" datatype="po">"#
+        ));
+        assert!(document.contains(
+            r#"<trans-unit id="1">
+        <source>Open &lt;file&gt; &amp; "save"</source>
+        <target state="translated">Atverti &lt;failą&gt; &amp; „išsaugoti“</target>
+        <context-group purpose="location">"#
+        ));
+        assert!(document.contains(r#"<note from="translator">on two lines</note>"#));
+        assert!(document.contains(r#"<group id="2" restype="x-gettext-plurals" resname="menu">"#));
+        assert!(document.contains(
+            r#"<trans-unit id="2[2]" resname="menu">
+          <source>Files</source>
+          <target state="needs-review-translation">Failų</target>"#
+        ));
+        assert!(document.contains("<source>Line one\nline two</source>\n      </trans-unit>"));
+    }
+
+    #[test]
+    fn test_round_trip_2_0() {
+        let (document, _) = round_trip(Version::V2_0);
+        assert!(document.contains(
+            r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="lt">"#
+        ));
+        assert!(document.contains(r#"<note category="location">src/toolbar.rs</note>"#));
+        assert!(document.contains(r#"<group id="g2" type="gettext:plurals" name="menu">"#));
+        assert!(document.contains(
+            r#"<segment state="initial">
+          <source>File</source>
+          <target>Failas</target>"#
+        ));
+    }
+
+    #[test]
+    fn test_parse_foreign() {
+        // A document written by another tool, without the PO header.
+        let po = parse(
+            r#"<?xml version="1.0"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="app" source-language="en" target-language="de" datatype="plaintext">
+    <body>
+      <group id="ui">
+        <trans-unit id="greeting" resname="start">
+          <source>Hello <g id="1">world</g></source>
+          <target state="needs-review-translation">Hallo Welt</target>
+          <note>Greeting</note>
+        </trans-unit>
+        <trans-unit id="bye"><source>Bye</source><target state="final">Tschüss</target></trans-unit>
+      </group>
+    </body>
+  </file>
+</xliff>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            po.entries[0].translations,
+            ["Language: de\nContent-Type: text/plain; charset=UTF-8\n\
+              Plural-Forms: nplurals=2; plural=(n != 1);\n"]
+        );
+        let greeting = &po.entries[1];
+        assert_eq!(greeting.context.as_deref(), Some("start"));
+        assert_eq!(greeting.id, "Hello world");
+        assert_eq!(greeting.translations, ["Hallo Welt"]);
+        assert_eq!(greeting.flags, ["fuzzy"]);
+        assert_eq!(greeting.extracted_comments, ["Greeting"]);
+        assert_eq!(po.entries[2].translations, ["Tschüss"]);
+        assert!(po.entries[2].flags.is_empty());
+
+        let catalog = po.to_catalog().unwrap();
+        assert_eq!(catalog.gettext("Bye"), "Tschüss");
+
+        assert!(matches!(
+            parse(&br#"<xliff version="3.0"/>"#[..]),
+            Err(InvalidDocument(_))
+        ));
+        assert!(matches!(
+            parse(&br#"<xliff version="2.0"><file id="f"><unit id="u"/></file></xliff>"#[..]),
+            Err(InvalidDocument(_))
+        ));
+        assert!(matches!(parse(&b"<xliff"[..]), Err(Error::XmlSyntax(1))));
+    }
+}
@@ -0,0 +1,418 @@
+//! A minimal XML reader and writer for the XML based translation formats.
+//!
+//! Documents are read into a tree of elements and text;
+//! the DTD, comments and processing instructions are skipped,
+//! and namespaces are ignored apart from stripping the prefixes of names.
+
+use std::fmt::Write as _;
+use std::io::Read;
+
+use crate::Error::{self, DecodingError, XmlSyntax};
+
+/// A node of an element's content.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// An element with its attributes and content.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Element {
+    /// The local name, without a namespace prefix.
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<Node>,
+}
+
+impl Element {
+    pub(crate) fn new(name: &str) -> Self {
+        Element {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds an attribute, for building documents.
+    pub(crate) fn attr(mut self, name: &str, value: &str) -> Self {
+        self.attributes.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Adds a child element, for building documents.
+    pub(crate) fn child(mut self, child: Element) -> Self {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    /// Adds text content, for building documents.
+    pub(crate) fn text(mut self, text: &str) -> Self {
+        if !text.is_empty() {
+            self.children.push(Node::Text(text.to_owned()));
+        }
+        self
+    }
+
+    /// Returns the value of the attribute with the given local name.
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| local_name(n) == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the child elements.
+    pub(crate) fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|n| match *n {
+            Node::Element(ref e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Returns the child elements with the given name.
+    pub(crate) fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    /// Returns the first child element with the given name.
+    pub(crate) fn first(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Returns the text content of the element and its descendants.
+    pub(crate) fn content(&self) -> String {
+        let mut content = String::new();
+        for node in &self.children {
+            match *node {
+                Node::Element(ref e) => content.push_str(&e.content()),
+                Node::Text(ref t) => content.push_str(t),
+            }
+        }
+        content
+    }
+
+    /// Writes the element out, indenting the child elements
+    /// unless the element has text content, which is kept exactly as it is.
+    pub(crate) fn write(&self, out: &mut String, indent: usize) {
+        let _ = write!(out, "<{}", self.name);
+        for (name, value) in &self.attributes {
+            let _ = write!(out, " {}=\"{}\"", name, escape(value, true));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        let mixed = self.children.iter().any(|n| matches!(*n, Node::Text(_)));
+        for node in &self.children {
+            if !mixed {
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent + 1));
+            }
+            match *node {
+                Node::Element(ref e) => e.write(out, if mixed { 0 } else { indent + 1 }),
+                Node::Text(ref t) => out.push_str(&escape(t, false)),
+            }
+        }
+        if !mixed {
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+        }
+        let _ = write!(out, "</{}>", self.name);
+    }
+
+    /// Returns the document with this element as the root, with an XML declaration
+    /// and the given document type declaration, like `<!DOCTYPE TS>`.
+    pub(crate) fn to_document(&self, doctype: Option<&str>) -> String {
+        let mut out = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_owned();
+        if let Some(doctype) = doctype {
+            out.push_str(doctype);
+            out.push('\n');
+        }
+        self.write(&mut out, 0);
+        out.push('\n');
+        out
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Escapes the text for element content or, if `attribute` is set, attribute values.
+pub(crate) fn escape(s: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\n' if attribute => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' if attribute => escaped.push_str("&#9;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reads a UTF-8 encoded document and returns its root element.
+pub(crate) fn read<R: Read>(mut reader: R) -> Result<Element, Error> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    let contents = String::from_utf8(contents).map_err(|_| DecodingError)?;
+    parse(contents.trim_start_matches('\u{feff}'))
+}
+
+/// Parses a document and returns its root element.
+pub(crate) fn parse(s: &str) -> Result<Element, Error> {
+    let mut parser = Parser { s, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos < s.len() {
+        return Err(parser.error());
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn error(&self) -> Error {
+        XmlSyntax(self.s[..self.pos].matches('\n').count() + 1)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips past the given terminator.
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, Error> {
+        match self.rest().find(end) {
+            Some(i) => {
+                let skipped = &self.rest()[..i];
+                self.pos += i + end.len();
+                Ok(skipped)
+            }
+            None => {
+                self.pos = self.s.len();
+                Err(self.error())
+            }
+        }
+    }
+
+    /// Skips whitespace, comments, processing instructions and the document type.
+    fn skip_misc(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                // The internal subset may contain `>` characters.
+                let end = rest.find('>').unwrap_or(rest.len());
+                if rest[..end].contains('[') {
+                    self.skip_past("]")?;
+                }
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, Error> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn element(&mut self) -> Result<Element, Error> {
+        if !self.rest().starts_with('<') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        let name = self.name()?;
+        let mut element = Element::new(local_name(name));
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error());
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ '"') | Some(q @ '\'') => q,
+                _ => return Err(self.error()),
+            };
+            self.pos += 1;
+            let value = self.skip_past(if quote == '"' { "\"" } else { "'" })?;
+            let value = unescape(value).ok_or_else(|| self.error())?;
+            element.attributes.push((attribute.to_owned(), value));
+        }
+
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let end = self.name()?;
+                self.skip_whitespace();
+                if end != name || !self.rest().starts_with('>') {
+                    return Err(self.error());
+                }
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += 9;
+                text.push_str(self.skip_past("]]>")?);
+            } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+                self.skip_misc_node()?;
+            } else if rest.starts_with('<') {
+                if !text.is_empty() {
+                    element.children.push(Node::Text(std::mem::take(&mut text)));
+                }
+                let child = self.element()?;
+                element.children.push(Node::Element(child));
+            } else if rest.is_empty() {
+                return Err(self.error());
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let chunk = unescape(&rest[..len]).ok_or_else(|| self.error())?;
+                text.push_str(&chunk);
+                self.pos += len;
+            }
+        }
+        if !text.is_empty() {
+            element.children.push(Node::Text(text));
+        }
+        // Whitespace between child elements is only formatting.
+        if element.elements().next().is_some() {
+            element.children.retain(|n| match *n {
+                Node::Text(ref t) => !t.trim().is_empty(),
+                Node::Element(_) => true,
+            });
+        }
+        Ok(element)
+    }
+
+    fn skip_misc_node(&mut self) -> Result<(), Error> {
+        if self.rest().starts_with("<!--") {
+            self.skip_past("-->")?;
+        } else {
+            self.skip_past("?>")?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the entity and character references in the text.
+fn unescape(s: &str) -> Option<String> {
+    if !s.contains('&') {
+        return Some(s.to_owned());
+    }
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        result.push_str(&rest[..i]);
+        let end = rest[i..].find(';')? + i;
+        let entity = &rest[i + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                std::char::from_u32(code)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let root = parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<!-- comment -->
+<x:root xmlns:x="urn:x" a='1 &amp; 2'>
+  <item id="&#65;&#x42;">text &lt;b&gt; <![CDATA[<raw>]]></item>
+  <empty/>
+  <mixed>a <b>bold</b> c</mixed>
+</x:root>
+"#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "root");
+        assert_eq!(root.get("a"), Some("1 & 2"));
+        assert_eq!(root.elements().count(), 3);
+        let item = root.first("item").unwrap();
+        assert_eq!(item.get("id"), Some("AB"));
+        assert_eq!(item.content(), "text <b> <raw>");
+        assert_eq!(root.first("mixed").unwrap().content(), "a bold c");
+        assert!(root.first("empty").unwrap().children.is_empty());
+
+        assert!(matches!(parse("<a>\n<b></a>"), Err(XmlSyntax(2))));
+        assert!(matches!(parse("<a>&bogus;</a>"), Err(XmlSyntax(1))));
+        assert!(matches!(parse("<a></a><b/>"), Err(XmlSyntax(1))));
+    }
+
+    #[test]
+    fn test_write() {
+        let root = Element::new("root")
+            .attr("a", "\"quoted\"\n")
+            .child(Element::new("item").text("  <kept>  "))
+            .child(Element::new("empty"));
+        let document = root.to_document(None);
+        assert_eq!(
+            document,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <root a=\"&quot;quoted&quot;&#10;\">\n  \
+             <item>  &lt;kept&gt;  </item>\n  \
+             <empty/>\n\
+             </root>\n"
+        );
+        assert_eq!(parse(&document).unwrap(), root);
+    }
+}
@@ -1,4 +1,6 @@
-use encoding::label::encoding_from_whatwg_label;
+#![cfg(feature = "std")]
+
+use gettext::po::PoFile;
 use gettext::{Catalog, ParseOptions};
 
 use std::fs::File;
@@ -64,8 +66,10 @@ fn test_cp1257() {
     // cp1257_forced
     {
         let reader: &[u8] = include_bytes!("../test_cases/cp1257_forced.mo");
+        // Without a header, it is not cp1257 unless forced.
+        assert!(Catalog::parse(reader).is_err());
         for enc_name in &["cp1257", "windows-1257", "x-cp1257"] {
-            let encoding = encoding_from_whatwg_label(enc_name).unwrap();
+            let encoding = encoding_rs::Encoding::for_label(enc_name.as_bytes()).unwrap();
             let catalog = ParseOptions::new()
                 .force_encoding(encoding)
                 .parse(reader)
@@ -73,6 +77,18 @@ fn test_cp1257() {
             assert_eq!(catalog.gettext("Garlic"), "Česnakas");
         }
     }
+
+    // cp1257_forced, with the `encoding` crate
+    #[cfg(feature = "encoding")]
+    {
+        let reader: &[u8] = include_bytes!("../test_cases/cp1257_forced.mo");
+        let encoding = encoding::label::encoding_from_whatwg_label("cp1257").unwrap();
+        let catalog = ParseOptions::new()
+            .force_encoding(encoding)
+            .parse(reader)
+            .unwrap();
+        assert_eq!(catalog.gettext("Garlic"), "Česnakas");
+    }
 }
 
 #[test]
@@ -119,3 +135,125 @@ fn test_complex_plural() {
         assert_eq!(cat.ngettext("Test", "Tests", i), "Plural 2");
     }
 }
+
+#[test]
+fn test_po_matches_mo() {
+    for name in &["1", "2", "integration", "complex_plural", "cp1257_meta"] {
+        let mo = File::open(format!("test_cases/{}.mo", name)).unwrap();
+        let po = File::open(format!("test_cases/{}.po", name)).unwrap();
+        let mo = Catalog::parse(mo).unwrap();
+        let po = Catalog::parse_po(po).unwrap();
+
+        let mut mo_messages = mo.messages().cloned().collect::<Vec<_>>();
+        let mut po_messages = po.messages().cloned().collect::<Vec<_>>();
+        mo_messages.sort_by(|a, b| (a.context(), a.id()).cmp(&(b.context(), b.id())));
+        po_messages.sort_by(|a, b| (a.context(), a.id()).cmp(&(b.context(), b.id())));
+        assert_eq!(mo_messages, po_messages, "{}", name);
+    }
+
+    let po = File::open("test_cases/complex_plural.po").unwrap();
+    let cat = Catalog::parse_po(po).unwrap();
+    assert_eq!(cat.ngettext("Test", "Tests", 2), "Plural 1");
+    assert_eq!(cat.ngettext("Test", "Tests", 5), "Plural 2");
+}
+
+#[test]
+fn test_po_invalid_utf8() {
+    let po = File::open("test_cases/invalid_utf8.po").unwrap();
+    match Catalog::parse_po(po) {
+        Err(gettext::Error::DecodingError) => (),
+        other => panic!("Expected DecodingError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_po_from_mo() {
+    for name in &["integration", "complex_plural", "cp1257_meta"] {
+        let mo = File::open(format!("test_cases/{}.mo", name)).unwrap();
+        let mo = Catalog::parse(mo).unwrap();
+        let po = PoFile::from_catalog(&mo);
+        assert!(po.entries[0].id.is_empty());
+        assert!(po.entries[0].translations[0].contains("charset=UTF-8"));
+
+        let mut written = vec![];
+        po.write(&mut written).unwrap();
+        let mut again = vec![];
+        PoFile::from_catalog(&mo).write(&mut again).unwrap();
+        assert_eq!(written, again, "{}", name);
+
+        let parsed = Catalog::parse_po(&written[..]).unwrap();
+        for message in mo.messages().filter(|m| !m.id().is_empty()) {
+            let translated = match message.context() {
+                Some(ctxt) => parsed.pgettext(ctxt, message.id()),
+                None => parsed.gettext(message.id()),
+            };
+            assert_eq!(translated, message.translations()[0], "{}", name);
+        }
+    }
+}
+
+#[test]
+fn test_po_round_trip() {
+    for name in &["1", "2", "integration", "complex_plural", "cp1257_meta"] {
+        let contents = std::fs::read(format!("test_cases/{}.po", name)).unwrap();
+        let po = PoFile::parse(&contents[..]).unwrap();
+        let mut written = vec![];
+        po.write(&mut written).unwrap();
+        assert_eq!(written, contents, "{}", name);
+    }
+
+    // Modified entries are encoded in the charset of the file.
+    let po = File::open("test_cases/cp1257_meta.po").unwrap();
+    let mut po = PoFile::parse(po).unwrap();
+    let last = po.entries.len() - 1;
+    po.entries[last].translations[0] = "ąčęėįšųūž".to_owned();
+    let mut written = vec![];
+    po.write(&mut written).unwrap();
+    assert!(std::str::from_utf8(&written).is_err());
+    assert_eq!(PoFile::parse(&written[..]).unwrap(), po);
+}
+
+#[test]
+fn test_po_fuzzy() {
+    let po = br#"
+#, fuzzy
+msgid ""
+msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+msgid "Translated"
+msgstr "Isverstas"
+
+#, fuzzy, c-format
+msgctxt "menu"
+msgid "Fuzzy %s"
+msgstr "Neaiskus %s"
+
+#, fuzzy
+msgid "Fuzzy untranslated"
+msgstr ""
+"#;
+    let catalog = Catalog::parse_po(&po[..]).unwrap();
+    assert_eq!(catalog.gettext("Translated"), "Isverstas");
+    assert_eq!(catalog.pgettext("menu", "Fuzzy %s"), "Fuzzy %s");
+    assert!(catalog.is_fuzzy(Some("menu"), "Fuzzy %s"));
+    assert!(!catalog.is_fuzzy(None, "Fuzzy %s"));
+    assert!(!catalog.is_fuzzy(None, "Translated"));
+    assert!(!catalog.is_fuzzy(None, ""));
+    assert_eq!(
+        catalog.fuzzy_messages().collect::<Vec<_>>(),
+        [(Some("menu"), "Fuzzy %s")]
+    );
+    // An untranslated entry is not fuzzy, whatever its flags say.
+    assert!(!catalog.is_fuzzy(None, "Fuzzy untranslated"));
+
+    let catalog = ParseOptions::new()
+        .include_fuzzy(true)
+        .parse_po(&po[..])
+        .unwrap();
+    assert_eq!(catalog.pgettext("menu", "Fuzzy %s"), "Neaiskus %s");
+    assert_eq!(catalog.gettext("Fuzzy untranslated"), "Fuzzy untranslated");
+    assert!(catalog.is_fuzzy(Some("menu"), "Fuzzy %s"));
+
+    let mo = File::open("test_cases/integration.mo").unwrap();
+    assert_eq!(Catalog::parse(mo).unwrap().fuzzy_messages().count(), 0);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "std")]
+
+use gettext::ReloadableCatalog;
+
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_reload_if_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.mo");
+    fs::copy("test_cases/2.mo", &path).unwrap();
+
+    let handle = ReloadableCatalog::open(&path).unwrap();
+    assert_eq!(handle.catalog().gettext("Image"), "Nuotrauka");
+    assert!(!handle.reload_if_changed().unwrap());
+
+    fs::copy("test_cases/integration.mo", &path).unwrap();
+    assert!(handle.reload_if_changed().unwrap());
+    assert_eq!(handle.catalog().gettext("Image"), "Image");
+    assert_eq!(handle.catalog().gettext("existent"), "egzistuojantis");
+    assert!(!handle.reload_if_changed().unwrap());
+}
+
+#[test]
+fn test_reload_keeps_old_catalog_on_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.mo");
+    fs::copy("test_cases/integration.mo", &path).unwrap();
+
+    let handle = ReloadableCatalog::open(&path).unwrap();
+    let before = handle.catalog();
+
+    fs::write(&path, b"definitely not an MO file").unwrap();
+    assert!(handle.reload_if_changed().is_err());
+    assert!(handle.reload().is_err());
+    assert!(Arc::ptr_eq(&before, &handle.catalog()));
+    assert_eq!(handle.catalog().gettext("existent"), "egzistuojantis");
+
+    fs::remove_file(&path).unwrap();
+    assert!(handle.reload_if_changed().is_err());
+    assert_eq!(handle.catalog().gettext("existent"), "egzistuojantis");
+}
+
+#[test]
+fn test_reload_from_many_threads() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.mo");
+    fs::copy("test_cases/2.mo", &path).unwrap();
+
+    let handle = Arc::new(ReloadableCatalog::open(&path).unwrap());
+    let readers = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let translated = handle.catalog().gettext("Image").to_owned();
+                    assert!(translated == "Nuotrauka" || translated == "Image");
+                    let _ = handle.reload_if_changed();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    fs::copy("test_cases/integration.mo", &path).unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    handle.reload_if_changed().unwrap();
+    assert_eq!(handle.catalog().gettext("Image"), "Image");
+}
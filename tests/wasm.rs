@@ -0,0 +1,33 @@
+//! Runs in Node.js, with `make test-wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm-bindgen"))]
+
+use gettext::wasm::WasmCatalog;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_catalog() {
+    let catalog = WasmCatalog::new(include_bytes!("../test_cases/integration.mo")).unwrap();
+    assert_eq!(catalog.gettext("non-existent"), "non-existent");
+    assert_eq!(catalog.gettext("existent"), "egzistuojantis");
+    assert_eq!(
+        catalog.ngettext("a good string", "good strings", 1.0),
+        "gera eilute"
+    );
+    assert_eq!(
+        catalog.ngettext("a good string", "good strings", 2.0),
+        "geros eilutes"
+    );
+    assert_eq!(
+        catalog.pgettext("ctxt", "existent"),
+        "egzistuojantis kontekste"
+    );
+    assert_eq!(
+        catalog.npgettext("ctxt", "a good string", "good strings", 2.0),
+        "geros eilutes kontekste"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_catalog() {
+    assert!(WasmCatalog::new(b"not an MO file").is_err());
+}